@@ -0,0 +1,52 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Build information shown by /version. Whatever cannot be found out (e.g.: git metadata, when building
+// from a tarball) is reported as "unknown" rather than failing the build.
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let commit = output("git", &["rev-parse", "--short", "HEAD"]);
+    let dirty = match (&commit, output("git", &["status", "--porcelain"])) {
+        (Some(_), Some(status)) => if status.is_empty() { "no" } else { "yes" },
+        _ => "unknown",
+    };
+    let rustc = env::var("RUSTC").unwrap_or("rustc".to_string());
+
+    println!("cargo:rustc-env=DENEDE_GIT_COMMIT={}", commit.as_deref().unwrap_or("unknown"));
+    println!("cargo:rustc-env=DENEDE_GIT_DIRTY={}", dirty);
+    println!("cargo:rustc-env=DENEDE_BUILD_DATE={}", chrono::Utc::now().format("%Y-%m-%d %H:%M UTC"));
+    println!("cargo:rustc-env=DENEDE_RUSTC_VERSION={}", output(&rustc, &["--version"]).as_deref().unwrap_or("unknown"));
+
+    // Found out again when the sources change (which may make them dirty), or when the checked out
+    // commit or the staged files do, if there is a repository at all:
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src");
+    for file in [".git/HEAD", ".git/index"] {
+        if Path::new(file).exists() {
+            println!("cargo:rerun-if-changed={}", file);
+        }
+    }
+}
+
+// Trimmed standard output of a command, if it could be run successfully:
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok().filter(|output| output.status.success())?;
+    String::from_utf8(output.stdout).ok().map(|stdout| stdout.trim().to_string())
+}
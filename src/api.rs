@@ -0,0 +1,153 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// HTTP API rolling dice for other tools, e.g.: a virtual tabletop overlay, with the same rolls
+// (and RANDOM.ORG numbers) as the bot. It only starts if DENEDE_API_PORT (or the configuration
+// file) gives it a port, and only answers those bearing one of the configured tokens:
+//
+//     POST /roll
+//     Authorization: Bearer <token>
+//     {"expression": "1d20+5", "times": 2}
+use std::convert::Infallible;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use hyper::body::HttpBody;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use ring::constant_time;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info_span, warn, Instrument};
+
+use denede_discord_bot::dice::{self, quote, Roll};
+use denede_discord_bot::ratelimit::{Limit, RateLimiter};
+
+use crate::config;
+use crate::server::Server;
+
+// Longest expression rolled, in characters, and longest body read, in bytes:
+const MAX_EXPRESSION_LENGTH: usize = 200;
+const MAX_BODY_LENGTH: usize = 1024;
+// Requests each token may make in a row, and how many more every minute:
+const BURST: u32 = 20;
+const PER_MINUTE: u32 = 60;
+
+// Requests made by each token, keyed by its place among the configured ones:
+static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RollRequest {
+    expression: String,
+    #[serde(default = "once")]
+    times: usize,
+}
+
+fn once() -> usize {
+    1
+}
+
+/// Serve the API on the given port.
+pub fn start(port: u16) -> Result<Server, String> {
+    Server::serve(port, "the roll API", handle)
+}
+
+async fn handle(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::POST, "/roll") => match authorize(&request) {
+            Some(token) => match LIMITER.get_or_init(|| RateLimiter::new(BURST, PER_MINUTE)).check(token, Instant::now()) {
+                Limit::Allowed => roll(request.into_body()).await,
+                Limit::Limited { .. } => error(StatusCode::TOO_MANY_REQUESTS, "Thou art rolling too fast, traveller."),
+            },
+            None => error(StatusCode::UNAUTHORIZED, "None may roll here without a token, traveller."),
+        },
+        (_, "/roll") => error(StatusCode::METHOD_NOT_ALLOWED, "Rolls are asked for with POST, traveller."),
+        _ => error(StatusCode::NOT_FOUND, "There is nothing here, traveller."),
+    };
+    Ok(response)
+}
+
+// Place of the bearer token among the configured ones, if it is one of them:
+fn authorize(request: &Request<Body>) -> Option<u64> {
+    let token = request.headers().get(AUTHORIZATION)?.to_str().ok()?.strip_prefix("Bearer ")?.trim();
+    // Compared in constant time, so tokens cannot be guessed by timing the answers:
+    let found = config::get()
+        .api_tokens
+        .iter()
+        .position(|known| constant_time::verify_slices_are_equal(known.as_bytes(), token.as_bytes()).is_ok());
+    found.map(|place| place as u64)
+}
+
+async fn roll(body: Body) -> Response<Body> {
+    let request: RollRequest = match read(body).await {
+        Ok(body) => match serde_json::from_slice(&body) {
+            Ok(request) => request,
+            Err(why) => return error(StatusCode::BAD_REQUEST, &format!("Mine eyes cannot read thy request: {why}")),
+        },
+        Err(response) => return response,
+    };
+    if request.expression.chars().count() > MAX_EXPRESSION_LENGTH {
+        return error(StatusCode::PAYLOAD_TOO_LARGE, &format!("No expression longer than {MAX_EXPRESSION_LENGTH} characters may be rolled, traveller."));
+    }
+    if !(1..=dice::MAX_EXPRESSIONS).contains(&request.times) {
+        return error(StatusCode::BAD_REQUEST, &format!("An expression may be rolled from 1 to {} times, traveller.", dice::MAX_EXPRESSIONS));
+    }
+
+    // Bracketed or not, as with /roll:
+    let expression = request.expression.trim();
+    let expr = if expression.starts_with('[') { expression.to_string() } else { format!("[{}]", expression) };
+    let roll = match Roll::parse(&expr) {
+        Some(Ok(roll)) => roll,
+        Some(Err(why)) => return error(StatusCode::UNPROCESSABLE_ENTITY, &why.quoting(&expr)),
+        None => return error(StatusCode::UNPROCESSABLE_ENTITY, &format!("{} is no roll I know of, traveller.", quote(&expr))),
+    };
+
+    let mut results = Vec::with_capacity(request.times);
+    for _ in 0..request.times {
+        results.push(roll.roll().instrument(info_span!("roll", expression = expr)).await);
+    }
+    let reports: Vec<_> = results.iter().map(|result| result.report(&expr)).collect();
+    match serde_json::to_string(&reports) {
+        Ok(body) => json_response(StatusCode::OK, body),
+        Err(why) => {
+            warn!("Could not serialize the rolls of {expr}: {why}");
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Alas, mine quill hath failed me...")
+        },
+    }
+}
+
+// Whole body of a request, unless it is longer than any request should be:
+async fn read(mut body: Body) -> Result<Vec<u8>, Response<Body>> {
+    let mut read = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|_| error(StatusCode::BAD_REQUEST, "Thy request was cut short, traveller."))?;
+        if read.len() + chunk.len() > MAX_BODY_LENGTH {
+            return Err(error(StatusCode::PAYLOAD_TOO_LARGE, "Thy request is far too long, traveller."));
+        }
+        read.extend_from_slice(&chunk);
+    }
+    Ok(read)
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(status, json!({ "error": message }).to_string())
+}
+
+fn json_response(status: StatusCode, body: String) -> Response<Body> {
+    Response::builder().status(status).header(CONTENT_TYPE, "application/json").body(Body::from(body)).expect("No response?")
+}
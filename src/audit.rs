@@ -0,0 +1,94 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use denede_discord_bot::dice::RollResult;
+use serde::Serialize;
+use serenity::model::prelude::*;
+use tracing::error;
+
+use crate::config;
+
+// Rolls recorded since startup:
+static RECORDED: AtomicUsize = AtomicUsize::new(0);
+// Taken while writing, so records never interleave:
+static WRITING: Mutex<()> = Mutex::new(());
+
+// A single line of the audit log:
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp: String,
+    user: &'a str,
+    user_id: UserId,
+    channel_id: ChannelId,
+    expression: &'a str,
+    truly_random: bool,
+    response: String,
+    result: &'a RollResult,
+}
+
+// File the rolls are recorded to, if DENEDE_AUDIT_LOG (or the configuration file) sets one:
+fn path() -> Option<&'static Path> {
+    config::get().audit_log.as_deref()
+}
+
+pub fn enabled() -> bool {
+    path().is_some()
+}
+
+pub fn recorded() -> usize {
+    RECORDED.load(Ordering::Relaxed)
+}
+
+// Append a roll to the audit log, as a line of JSON. Failing to do so must not keep the roll from being answered:
+pub fn record(user: &User, channel_id: ChannelId, expression: &str, result: &RollResult) {
+    let Some(path) = path() else {
+        return;
+    };
+
+    let record = Record {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        user: &user.name,
+        user_id: user.id,
+        channel_id,
+        expression,
+        truly_random: result.is_truly_random(),
+        response: result.to_string(),
+        result,
+    };
+    let line = match serde_json::to_string(&record) {
+        Ok(line) => line,
+        Err(why) => {
+            error!("Could not record a roll in the audit log: {why}");
+            return;
+        },
+    };
+
+    let _writing = WRITING.lock().expect("No audit log?");
+    let written = OpenOptions::new().create(true).append(true).open(path).and_then(|mut file| writeln!(file, "{line}"));
+    match written {
+        Ok(()) => {
+            RECORDED.fetch_add(1, Ordering::Relaxed);
+        },
+        Err(why) => error!("Could not write to the audit log {}: {why}", path.display()),
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Aliases exported from Avrae, the D&D Beyond bot, read as named dice expressions.
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::dice::{DiceError, Roll, Syntax};
+
+// Longest expression kept, as Discord allows no longer suggestions:
+const MAX_EXPRESSION: usize = 100;
+
+/// Why an alias was left out of the import.
+pub enum Skip {
+    // Named with more than letters, numerals, dashes and underscores, or too long a name:
+    Name,
+    // Scripted with Draconic, e.g.: "<drac2>...</drac2>" or "{roll('1d20')}"
+    Draconic,
+    // Filled in with what it is called with, e.g.: "r 1d20+%1%"
+    Arguments,
+    // Anything else than a roll, e.g.: "attack longsword"
+    NotRoll,
+    // A roll Denedé cannot make:
+    Dice(DiceError),
+    // A roll too long to be suggested back:
+    TooLong,
+}
+
+impl fmt::Display for Skip {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Skip::Name => write!(f, "its name may only bear letters, numerals, dashes and underscores, and no more than 32 of them"),
+            Skip::Draconic => write!(f, "it is scripted in Draconic, a tongue I do not speak"),
+            Skip::Arguments => write!(f, "it takes arguments"),
+            Skip::NotRoll => write!(f, "it is no roll"),
+            Skip::Dice(why) => write!(f, "{}", why),
+            Skip::TooLong => write!(f, "its roll is longer than {} characters", MAX_EXPRESSION),
+        }
+    }
+}
+
+/// Aliases read from an export, in order: the imported ones as their names and expressions (e.g.:
+/// "atk" and "1d20+5"), and the skipped ones as their names and why they were skipped.
+#[derive(Default)]
+pub struct Import {
+    pub imported: Vec<(String, String)>,
+    pub skipped: Vec<(String, Skip)>,
+}
+
+// An alias as exported in JSON, e.g.: {"name": "atk", "commands": "r 1d20+5"}
+#[derive(Deserialize)]
+struct Exported {
+    name: String,
+    commands: String,
+}
+
+/// Read the aliases of an export: either a JSON list of aliases, or the "!alias" commands that made
+/// them, one per line (the lines that follow one that are no "!alias" command belong to it).
+/// Expressions are checked against the given notation. A name aliased twice keeps its last roll.
+pub fn import(export: &str, syntax: Syntax) -> Result<Import, String> {
+    let aliases = if export.trim_start().starts_with(['[', '{']) {
+        let exported = match serde_json::from_str::<Vec<Exported>>(export) {
+            Ok(exported) => exported,
+            Err(_) => vec![serde_json::from_str::<Exported>(export).map_err(|why| why.to_string())?],
+        };
+        exported.into_iter().map(|alias| (alias.name, alias.commands)).collect()
+    } else {
+        commands(export)
+    };
+
+    let mut import = Import::default();
+    for (name, commands) in aliases {
+        match alias(&name, &commands, syntax) {
+            Ok(expression) => {
+                import.imported.retain(|(imported, _)| *imported != name);
+                import.imported.push((name, expression));
+            },
+            Err(why) => import.skipped.push((name, why)),
+        }
+    }
+    Ok(import)
+}
+
+// Names and commands of the "!alias" commands of an export, e.g.: "!alias atk r 1d20+5"
+fn commands(export: &str) -> Vec<(String, String)> {
+    static ALIAS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\S?alias\s+(?<name>\S+)\s*(?<commands>.*)$").expect("No alias regex?"));
+    let mut aliases: Vec<(String, String)> = Vec::new();
+    for line in export.lines() {
+        match ALIAS.captures(line.trim()) {
+            Some(captures) => aliases.push((captures["name"].to_owned(), captures["commands"].to_owned())),
+            None => if let Some((_, commands)) = aliases.last_mut() {
+                commands.push('\n');
+                commands.push_str(line);
+            },
+        }
+    }
+    aliases
+}
+
+// Expression an alias rolls, e.g.: "1d20+5" for "r 1d20+5 Attack!"
+fn alias(name: &str, commands: &str, syntax: Syntax) -> Result<String, Skip> {
+    static NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[\w-]{1,32}$").expect("No alias name regex?"));
+    static DRACONIC: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)</?drac2>|[{}]|<[a-z_]\w*>").expect("No Draconic regex?"));
+    static ARGUMENTS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"%(?:\d+|\*)%|&(?:\d+|\*|ARGS)&").expect("No arguments regex?"));
+    static ROLL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)^!?(?:r|roll)\s+(?<roll>.+)$").expect("No roll regex?"));
+
+    if !NAME.is_match(name) {
+        return Err(Skip::Name);
+    }
+    let commands = commands.trim();
+    if DRACONIC.is_match(commands) {
+        return Err(Skip::Draconic);
+    }
+    if ARGUMENTS.is_match(commands) {
+        return Err(Skip::Arguments);
+    }
+    let Some(captures) = ROLL.captures(commands) else {
+        return Err(Skip::NotRoll);
+    };
+
+    // Whatever follows the roll is only a comment on it, e.g.: "1d20+5 Attack!"
+    let roll = captures["roll"].trim();
+    let first = roll.split_whitespace().next().unwrap_or_default();
+    let parse = |expression: &str| Roll::parse_with(&format!("[{}]", expression), syntax);
+    let expression = match (parse(roll), parse(first)) {
+        (Some(Ok(_)), _) => roll,
+        (_, Some(Ok(_))) => first,
+        (Some(Err(why)), _) | (None, Some(Err(why))) => return Err(Skip::Dice(why)),
+        (None, None) => return Err(Skip::NotRoll),
+    };
+    if expression.chars().count() > MAX_EXPRESSION {
+        return Err(Skip::TooLong);
+    }
+    Ok(expression.to_owned())
+}
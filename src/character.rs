@@ -0,0 +1,275 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Character sheets of D&D 5e, kept by users to roll with, e.g.: their "$dex" in [1d20 + $dex].
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dice::{RollResult, Variables};
+
+// Bounds of what a sheet may hold:
+const MAX_NAME: usize = 32;
+const SCORES: std::ops::RangeInclusive<i64> = 1..=30;
+const MODIFIERS: std::ops::RangeInclusive<i64> = -5..=10;
+const PROFICIENCY: std::ops::RangeInclusive<i64> = 0..=10;
+
+/// The six abilities, in the order of a character sheet.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ability {
+    Strength,
+    Dexterity,
+    Constitution,
+    Intelligence,
+    Wisdom,
+    Charisma,
+}
+
+impl Ability {
+    pub const ALL: [Ability; 6] = [Ability::Strength, Ability::Dexterity, Ability::Constitution, Ability::Intelligence, Ability::Wisdom, Ability::Charisma];
+
+    /// Abbreviation of the ability, as in variables and options, e.g.: "dex".
+    pub fn short(self) -> &'static str {
+        match self {
+            Ability::Strength => "str",
+            Ability::Dexterity => "dex",
+            Ability::Constitution => "con",
+            Ability::Intelligence => "int",
+            Ability::Wisdom => "wis",
+            Ability::Charisma => "cha",
+        }
+    }
+
+    /// Name of the ability, e.g.: "Dexterity".
+    pub fn name(self) -> &'static str {
+        match self {
+            Ability::Strength => "Strength",
+            Ability::Dexterity => "Dexterity",
+            Ability::Constitution => "Constitution",
+            Ability::Intelligence => "Intelligence",
+            Ability::Wisdom => "Wisdom",
+            Ability::Charisma => "Charisma",
+        }
+    }
+
+    /// The ability an abbreviation stands for, e.g.: Dexterity for "dex".
+    pub fn from_short(short: &str) -> Option<Ability> {
+        Ability::ALL.into_iter().find(|ability| ability.short().eq_ignore_ascii_case(short))
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Skills of D&D 5e, with the ability each is checked with.
+pub const SKILLS: [(&str, Ability); 18] = [
+    ("acrobatics", Ability::Dexterity),
+    ("animal_handling", Ability::Wisdom),
+    ("arcana", Ability::Intelligence),
+    ("athletics", Ability::Strength),
+    ("deception", Ability::Charisma),
+    ("history", Ability::Intelligence),
+    ("insight", Ability::Wisdom),
+    ("intimidation", Ability::Charisma),
+    ("investigation", Ability::Intelligence),
+    ("medicine", Ability::Wisdom),
+    ("nature", Ability::Intelligence),
+    ("perception", Ability::Wisdom),
+    ("performance", Ability::Charisma),
+    ("persuasion", Ability::Charisma),
+    ("religion", Ability::Intelligence),
+    ("sleight_of_hand", Ability::Dexterity),
+    ("stealth", Ability::Dexterity),
+    ("survival", Ability::Wisdom),
+];
+
+/// The skill a name stands for, however it is written, e.g.: "sleight_of_hand" for "Sleight of Hand".
+pub fn skill(name: &str) -> Option<(&'static str, Ability)> {
+    let name = name.trim().to_lowercase().replace([' ', '-'], "_");
+    SKILLS.into_iter().find(|(skill, _)| *skill == name)
+}
+
+/// An ability of a character: its score, if known, and its modifier.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct Score {
+    pub score: Option<i64>,
+    pub modifier: i64,
+}
+
+impl Score {
+    /// Read a score (e.g.: "14", whose modifier is +2) or a signed modifier on its own (e.g.: "+2").
+    pub fn parse(text: &str) -> Result<Score, String> {
+        let text = text.trim();
+        let signed = text.starts_with(['+', '-']);
+        let Ok(value) = text.parse::<i64>() else {
+            return Err(format!("\"{}\" is neither a score (e.g.: 14) nor a modifier (e.g.: +2).", text));
+        };
+        if signed {
+            if !MODIFIERS.contains(&value) {
+                return Err(format!("Modifiers go from {} to +{}, not {}.", MODIFIERS.start(), MODIFIERS.end(), text));
+            }
+            Ok(Score { score: None, modifier: value })
+        } else {
+            if !SCORES.contains(&value) {
+                return Err(format!("Scores go from {} to {}, not {}.", SCORES.start(), SCORES.end(), text));
+            }
+            Ok(Score { score: Some(value), modifier: (value - 10).div_euclid(2) })
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.score {
+            Some(score) => write!(f, "{} ({:+})", score, self.modifier),
+            None => write!(f, "{:+}", self.modifier),
+        }
+    }
+}
+
+/// A character sheet: what later rolls, checks and saves read rather than asking for every time.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct Character {
+    pub name: String,
+    // In the order of Ability::ALL:
+    abilities: [Score; 6],
+    pub proficiency: i64,
+    // Skills the character is proficient in, as in SKILLS:
+    skills: Vec<String>,
+}
+
+impl Character {
+    /// A character with the given name, abilities (in the order of [`Ability::ALL`]), proficiency
+    /// bonus, and skills separated by commas (e.g.: "Stealth, sleight of hand"), if they make sense.
+    pub fn new(name: &str, abilities: [Score; 6], proficiency: i64, skills: &str) -> Result<Character, String> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME {
+            return Err(format!("A character's name must have from 1 to {} characters.", MAX_NAME));
+        }
+        if !PROFICIENCY.contains(&proficiency) {
+            return Err(format!("Proficiency bonuses go from +{} to +{}, not {:+}.", PROFICIENCY.start(), PROFICIENCY.end(), proficiency));
+        }
+        let mut proficient = Vec::new();
+        for name in skills.split(',').filter(|name| !name.trim().is_empty()) {
+            let Some((skill, _)) = skill(name) else {
+                return Err(format!("\"{}\" is no skill I know of.", name.trim()));
+            };
+            if !proficient.contains(&skill.to_string()) {
+                proficient.push(skill.to_string());
+            }
+        }
+        proficient.sort_by_key(|name| SKILLS.iter().position(|(skill, _)| skill == name));
+        Ok(Character { name: name.to_owned(), abilities, proficiency, skills: proficient })
+    }
+
+    pub fn ability(&self, ability: Ability) -> Score {
+        self.abilities[ability.index()]
+    }
+
+    /// Whether the character is proficient in the skill, e.g.: "stealth".
+    pub fn proficient(&self, skill: &str) -> bool {
+        self.skills.iter().any(|proficient| proficient == skill)
+    }
+
+    /// Skills the character is proficient in, in the order of [`SKILLS`].
+    pub fn skills(&self) -> &[String] {
+        &self.skills
+    }
+
+    /// Bonus of the character to checks of the skill: the modifier of its ability, plus their
+    /// proficiency bonus if proficient in it.
+    pub fn skill_bonus(&self, skill: &str) -> Option<i64> {
+        let (skill, ability) = self::skill(skill)?;
+        Some(self.ability(ability).modifier + if self.proficient(skill) { self.proficiency } else { 0 })
+    }
+
+    /// Check of an ability, e.g.: a Strength check, adding only its modifier.
+    pub fn ability_check(&self, ability: Ability) -> Check {
+        Check { ability, modifier: self.ability(ability).modifier, proficiency: None }
+    }
+
+    /// Check of a skill, e.g.: "stealth", adding the modifier of its ability, and the proficiency
+    /// bonus if the character is proficient in it. None for unknown skills.
+    pub fn skill_check(&self, skill: &str) -> Option<Check> {
+        let (skill, ability) = self::skill(skill)?;
+        let proficiency = self.proficient(skill).then_some(self.proficiency);
+        Some(Check { ability, modifier: self.ability(ability).modifier, proficiency })
+    }
+
+    /// Values of the variables of the character's rolls: the modifiers of the abilities (e.g.:
+    /// "$dex"), the proficiency bonus ("$prof"), initiative ("$init"), and the bonus of every skill
+    /// (e.g.: "$stealth", "$sleight_of_hand").
+    pub fn variables(&self) -> Variables {
+        let mut variables = Ability::ALL.into_iter().map(|ability| (ability.short().to_string(), self.ability(ability).modifier)).collect::<Variables>();
+        variables.insert("prof".to_string(), self.proficiency);
+        variables.insert("init".to_string(), self.ability(Ability::Dexterity).modifier);
+        for (skill, _) in SKILLS {
+            variables.insert(skill.to_string(), self.skill_bonus(skill).expect("No skill?"));
+        }
+        variables
+    }
+}
+
+/// A d20 check of a character, and the bonuses added to it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Check {
+    pub ability: Ability,
+    pub modifier: i64,
+    // Proficiency bonus, if proficient in the skill checked:
+    pub proficiency: Option<i64>,
+}
+
+impl Check {
+    /// Expression rolled for the check, with advantage (Some(true)), disadvantage (Some(false)) or
+    /// neither, e.g.: "[2d20kh1+5]".
+    pub fn expression(&self, advantage: Option<bool>) -> String {
+        format!("[{}{:+}]", dice(advantage), self.modifier + self.proficiency.unwrap_or(0))
+    }
+
+    /// Breakdown of the result of the check's expression, e.g.: "1d20 (14) + DEX (3) + prof (2) = 19",
+    /// or "2d20kh1 (~~4~~, 17) + DEX (3) = 20" with advantage. None for the result of any other roll.
+    pub fn breakdown(&self, advantage: Option<bool>, result: &RollResult) -> Option<String> {
+        let [d20s] = result.individuals()[..] else {
+            return None;
+        };
+        // Striking out the dice left out, e.g.: the lower with advantage:
+        let mut discarded = d20s.discarded();
+        let rolls = d20s.rolls().iter().map(|value| match discarded.iter().position(|discarded| discarded == value) {
+            Some(index) => {
+                discarded.remove(index);
+                format!("~~{}~~", value)
+            },
+            None => value.to_string(),
+        }).collect::<Vec<String>>();
+
+        let mut breakdown = format!("{} ({}) + {} ({})", dice(advantage), rolls.join(", "), self.ability.short().to_uppercase(), self.modifier);
+        if let Some(proficiency) = self.proficiency {
+            breakdown.push_str(&format!(" + prof ({})", proficiency));
+        }
+        Some(format!("{} = {}", breakdown, result.total()?))
+    }
+}
+
+// The d20s of a check, e.g.: "2d20kh1" with advantage:
+fn dice(advantage: Option<bool>) -> &'static str {
+    match advantage {
+        None => "1d20",
+        Some(true) => "2d20kh1",
+        Some(false) => "2d20kl1",
+    }
+}
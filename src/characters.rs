@@ -0,0 +1,46 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Characters of the users, whose values the variables of their rolls stand for, e.g.: the "$dex" of
+// [1d20 + $dex]. Each user may keep one character in every server.
+use serenity::model::id::{GuildId, UserId};
+use tracing::error;
+
+use denede_discord_bot::character::Character;
+use denede_discord_bot::dice::Variables;
+
+use crate::database;
+
+/// The user's character in the server, if they have one.
+pub fn get(user_id: UserId, guild_id: Option<GuildId>) -> Option<Character> {
+    let guild_id = guild_id?;
+    let sheet = database::get().character(user_id.get(), guild_id.get())
+        .map_err(|why| error!("Could not read the character of {user_id} in {guild_id}: {why}")).ok()??;
+    serde_json::from_str(&sheet).map_err(|why| error!("Could not read the character of {user_id} in {guild_id}: {why}")).ok()
+}
+
+/// Keep the user's character in the server, replacing the one they had, or forget it (None).
+pub fn set(user_id: UserId, guild_id: GuildId, character: Option<&Character>) -> Result<(), String> {
+    let sheet = character.map(serde_json::to_string).transpose().map_err(|why| why.to_string())?;
+    database::get().set_character(user_id.get(), guild_id.get(), sheet.as_deref()).map_err(|why| why.to_string())
+}
+
+/// Values of the variables of the user's rolls in the server, or None if they have no character
+/// there.
+pub fn variables(user_id: UserId, guild_id: Option<GuildId>) -> Option<Variables> {
+    get(user_id, guild_id).map(|character| character.variables())
+}
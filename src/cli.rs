@@ -0,0 +1,89 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Command line of the binary: running the bot (by default), or rolling dice right away without
+// Discord, e.g.: `denede-discord-bot roll --times 6 "4d6km3"`.
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use denede_discord_bot::dice::{self, quote, styled, Roll, Style};
+
+#[derive(Parser)]
+#[command(version, about = "Discord bot for generating D&D dice rolls.")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Roll an expression with the local generator and print the results, without Discord.
+    Roll {
+        /// Expression to roll, bracketed or not, e.g.: "2d6+3" or "[1d20+5 vs 15]".
+        expression: String,
+        /// Times the expression is rolled.
+        #[arg(long, default_value_t = 1)]
+        times: u32,
+        /// Print each result as a line of JSON instead.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Roll the expression the given times, printing every result on its own line. Exits with a
+/// failure if the expression cannot be rolled, so scripts can tell.
+pub fn roll(expression: &str, times: u32, json: bool) -> ExitCode {
+    if let Err(why) = dice::check_limits() {
+        eprintln!("{why}");
+        return ExitCode::FAILURE;
+    }
+    // Bracketed or not, as with /roll:
+    let expression = expression.trim();
+    let expr = if expression.starts_with('[') { expression.to_string() } else { format!("[{}]", expression) };
+    let roll = match Roll::parse(&expr) {
+        Some(Ok(roll)) => roll,
+        Some(Err(why)) => {
+            eprintln!("{}", why.quoting(&expr));
+            return ExitCode::FAILURE;
+        },
+        None => {
+            eprintln!("{} is no roll I know of, traveller.", quote(&expr));
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let mut rng = StdRng::from_entropy();
+    for _ in 0..times {
+        let result = roll.roll_local(&mut rng);
+        if json {
+            match serde_json::to_string(&result.report(&expr)) {
+                Ok(line) => println!("{line}"),
+                Err(why) => {
+                    eprintln!("Could not print the roll as JSON: {why}");
+                    return ExitCode::FAILURE;
+                },
+            }
+        } else {
+            // Plain text for the terminal, without the flourishes of chat:
+            println!("{}", styled(Style::plain(), || result.to_string()));
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,36 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::CreateCommand;
+use serenity::model::application::ResolvedOption;
+use serenity::model::permissions::Permissions;
+
+use crate::audit;
+
+pub fn run(_options: &[ResolvedOption]) -> Option<(String, bool)> {
+    let response = if audit::enabled() {
+        format!("A chronicle of every roll is being kept, traveller: {} of them recorded since I last awoke.", audit::recorded())
+    } else {
+        "No chronicle of the rolls is being kept, traveller.".to_string()
+    };
+    Some((response, true))
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("auditinfo").description("Tell whether rolls are being recorded in the audit log.")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+}
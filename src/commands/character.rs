@@ -0,0 +1,134 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::{GuildId, UserId};
+use tracing::error;
+
+use denede_discord_bot::character::{skill, Ability, Character, Score};
+
+use crate::characters;
+use crate::commands::MISSING_OPTION;
+
+pub fn run(options: &[ResolvedOption<'_>], user_id: UserId, guild_id: Option<GuildId>) -> Option<(String, bool)> {
+    let Some(guild_id) = guild_id else {
+        return Some(("Characters can only be kept within a server, traveller.".to_string(), true));
+    };
+
+    let Some(ResolvedOption { name: subcommand, value: ResolvedValue::SubCommand(options), .. }) = options.first() else {
+        return None;
+    };
+
+    // Only the sheet is meant to be seen by others:
+    match *subcommand {
+        "set" => Some((set(options, user_id, guild_id), true)),
+        "show" => match characters::get(user_id, Some(guild_id)) {
+            Some(character) => Some((show(&character), false)),
+            None => Some((NO_CHARACTER.to_string(), true)),
+        },
+        "delete" => Some((delete(user_id, guild_id), true)),
+        _ => None,
+    }
+}
+
+// Answer to users without a character in the server:
+pub const NO_CHARACTER: &str = "Thou hast no character in this realm, traveller. Register one with /character set.";
+
+fn set(options: &[ResolvedOption<'_>], user_id: UserId, guild_id: GuildId) -> String {
+    let string_option = |name: &str| options.iter().find_map(|option| match option {
+        ResolvedOption { name: option_name, value: ResolvedValue::String(value), .. } if *option_name == name => Some(*value),
+        _ => None,
+    });
+    let proficiency = options.iter().find_map(|option| match option {
+        ResolvedOption { name: "proficiency", value: ResolvedValue::Integer(value), .. } => Some(*value),
+        _ => None,
+    });
+
+    let (Some(name), Some(proficiency)) = (string_option("name"), proficiency) else {
+        return MISSING_OPTION.to_string();
+    };
+    let mut abilities = Vec::new();
+    for ability in Ability::ALL {
+        let Some(score) = string_option(ability.short()) else {
+            return MISSING_OPTION.to_string();
+        };
+        match Score::parse(score) {
+            Ok(score) => abilities.push(score),
+            Err(why) => return format!("Thy {} will not do, traveller: {}", ability.short().to_uppercase(), why),
+        }
+    }
+    let abilities = abilities.try_into().expect("No six abilities?");
+    let character = match Character::new(name, abilities, proficiency, string_option("skills").unwrap_or_default()) {
+        Ok(character) => character,
+        Err(why) => return format!("That character will not do, traveller: {}", why),
+    };
+
+    if let Err(why) = characters::set(user_id, guild_id, Some(&character)) {
+        error!("Could not save the character of {user_id} in {guild_id}: {why}");
+        return "Alas, mine quill hath failed me and thy character could not be inscribed.".to_string();
+    }
+    format!("Thy character is inscribed, traveller:\n{}", show(&character))
+}
+
+// The sheet of a character, e.g.: "**Mira** — STR 8 (-1) · DEX 16 (+3) · … · Proficiency +2"
+fn show(character: &Character) -> String {
+    let abilities = Ability::ALL.into_iter()
+        .map(|ability| format!("{} {}", ability.short().to_uppercase(), character.ability(ability)))
+        .collect::<Vec<String>>();
+    let mut sheet = format!("**{}** — {} · Proficiency {:+}", character.name.replace('*', "\\*"), abilities.join(" · "), character.proficiency);
+    if !character.skills().is_empty() {
+        let skills = character.skills().iter().map(|name| {
+            let bonus = character.skill_bonus(name).expect("No skill?");
+            let (_, ability) = skill(name).expect("No skill?");
+            format!("{} {:+} ({})", name.replace('_', " "), bonus, ability.short().to_uppercase())
+        }).collect::<Vec<String>>();
+        sheet.push_str(&format!("\nProficient in: {}", skills.join(", ")));
+    }
+    sheet
+}
+
+fn delete(user_id: UserId, guild_id: GuildId) -> String {
+    if characters::get(user_id, Some(guild_id)).is_none() {
+        return NO_CHARACTER.to_string();
+    }
+    if let Err(why) = characters::set(user_id, guild_id, None) {
+        error!("Could not delete the character of {user_id} in {guild_id}: {why}");
+        return "Alas, mine quill hath failed me and thy character could not be struck out.".to_string();
+    }
+    "Thy character is struck from the records of this realm.".to_string()
+}
+
+pub fn register() -> CreateCommand {
+    let mut set = CreateCommandOption::new(CommandOptionType::SubCommand, "set", "Register (or replace) your character in this server.")
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "name", "Name of the character.").required(true));
+    for ability in Ability::ALL {
+        set = set.add_sub_option(CreateCommandOption::new(CommandOptionType::String, ability.short(), format!("{} score (e.g.: 14), or modifier (e.g.: +2).", ability.short().to_uppercase()))
+            .required(true));
+    }
+    set = set
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::Integer, "proficiency", "Proficiency bonus.")
+            .required(true)
+            .min_int_value(0)
+            .max_int_value(10))
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "skills", "Skills the character is proficient in, separated by commas, e.g.: stealth, perception."));
+
+    CreateCommand::new("character").description("Keep your character in this server, whose values your rolls can use, e.g.: [1d20 + $dex].")
+        .add_option(set)
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "show", "Show your character in this server."))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "delete", "Delete your character in this server."))
+}
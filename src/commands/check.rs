@@ -0,0 +1,109 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::prelude::*;
+use tracing::error;
+
+use denede_discord_bot::character::{Ability, SKILLS};
+use denede_discord_bot::dice::{styled, Roll};
+use denede_discord_bot::randomorg;
+
+use crate::commands::character::NO_CHARACTER;
+use crate::commands::mystats::Unrecorded;
+use crate::commands::preferences;
+use crate::tally::Tally;
+use crate::{audit, characters};
+
+/// The check made, whether to show it only to whoever made it, and its roll, to be counted
+/// in the roll statistics once the response is sent.
+pub async fn run(options: &[ResolvedOption<'_>], user: &User, channel_id: ChannelId, guild_id: Option<GuildId>, tally: &Tally) -> Option<(String, bool, Unrecorded)> {
+    let mut unrecorded = Unrecorded::new(user.id, guild_id);
+    let Some(guild_id) = guild_id else {
+        return Some(("Checks are made by characters, and characters can only be kept within a server, traveller.".to_string(), true, unrecorded));
+    };
+
+    let string_option = |name: &str| options.iter().find_map(|option| match option {
+        ResolvedOption { name: option_name, value: ResolvedValue::String(value), .. } if *option_name == name => Some(*value),
+        _ => None,
+    });
+    let dc = options.iter().find_map(|option| match option {
+        ResolvedOption { name: "dc", value: ResolvedValue::Integer(value), .. } => Some(*value),
+        _ => None,
+    });
+    let advantage = match string_option("with") {
+        Some("advantage") => Some(true),
+        Some("disadvantage") => Some(false),
+        _ => None,
+    };
+
+    let Some(character) = characters::get(user.id, Some(guild_id)) else {
+        return Some((NO_CHARACTER.to_string(), true, unrecorded));
+    };
+    let (checked, check) = match (string_option("skill"), string_option("ability").and_then(Ability::from_short)) {
+        (Some(skill), None) => match character.skill_check(skill) {
+            Some(check) => (skill.replace('_', " "), check),
+            None => return Some((format!("\"{}\" is no skill I know of, traveller.", skill), true, unrecorded)),
+        },
+        (None, Some(ability)) => (ability.name().to_string(), character.ability_check(ability)),
+        _ => return Some(("Name either a skill or an ability to check, traveller (but not both).".to_string(), true, unrecorded)),
+    };
+
+    // Rolled as any other roll, e.g.: [1d20+5]
+    let expr = check.expression(advantage);
+    let Some(Ok(roll)) = Roll::parse(&expr) else {
+        error!("Could not parse the check {expr}");
+        return Some(("Alas, mine quill hath failed me and the check could not be rolled.".to_string(), true, unrecorded));
+    };
+    let result = roll.roll().await;
+    audit::record(user, channel_id, &expr, &result);
+    unrecorded.add(&expr, &result);
+    tally.count(&result);
+
+    let Some(breakdown) = check.breakdown(advantage, &result) else {
+        error!("Could not break the check {expr} down");
+        return Some(("Alas, mine quill hath failed me and the check could not be rolled.".to_string(), true, unrecorded));
+    };
+    let mut response = format!("**{}** makes a {} check: {}", character.name.replace('*', "\\*"), checked, breakdown);
+    if let Some((_, verdict)) = dc.and_then(|dc| result.against(dc)) {
+        response.push_str(&format!(" {}", verdict));
+    }
+    if let Some(note) = styled(preferences::of(user.id).style(), || randomorg::note(result.is_truly_random())) {
+        response.push_str(&format!(" {}", note));
+    }
+    Some((response, false, unrecorded))
+}
+
+pub fn register() -> CreateCommand {
+    let mut skill = CreateCommandOption::new(CommandOptionType::String, "skill", "Skill to check, with its ability and your proficiency, if any.");
+    for (name, ability) in SKILLS {
+        skill = skill.add_string_choice(format!("{} ({})", name.replace('_', " "), ability.short().to_uppercase()), name);
+    }
+    let mut ability = CreateCommandOption::new(CommandOptionType::String, "ability", "Ability to check, instead of a skill.");
+    for choice in Ability::ALL {
+        ability = ability.add_string_choice(choice.name(), choice.short());
+    }
+
+    CreateCommand::new("check").description("Roll a check of your character in this server, e.g.: stealth.")
+        .add_option(skill)
+        .add_option(ability)
+        .add_option(CreateCommandOption::new(CommandOptionType::String, "with", "Roll with advantage or disadvantage.")
+            .add_string_choice("advantage", "advantage")
+            .add_string_choice("disadvantage", "disadvantage"))
+        .add_option(CreateCommandOption::new(CommandOptionType::Integer, "dc", "Difficulty class the check is to meet, e.g.: 15 (a tie is a success)."))
+}
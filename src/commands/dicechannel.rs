@@ -0,0 +1,89 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::permissions::Permissions;
+use tracing::error;
+
+use crate::database;
+
+pub fn run(options: &[ResolvedOption<'_>], guild_id: Option<GuildId>, channel_id: ChannelId) -> Option<(String, bool)> {
+    let Some(guild_id) = guild_id else {
+        return Some(("Only the channels of a server can be told apart, traveller.".to_string(), true));
+    };
+
+    let Some(ResolvedOption { name: subcommand, value: ResolvedValue::SubCommand(options), .. }) = options.first() else {
+        return None;
+    };
+    // The given channel, or else the one the command was used in:
+    let channel_id = options.iter().find_map(|option| match option {
+        ResolvedOption { name: "channel", value: ResolvedValue::Channel(channel), .. } => Some(channel.id),
+        _ => None,
+    }).unwrap_or(channel_id);
+
+    let disabled = match *subcommand {
+        "disable" => true,
+        "enable" => false,
+        "list" => return Some((list(guild_id), true)),
+        _ => return None,
+    };
+    if let Err(why) = database::get().set_channel_disabled(guild_id.get(), channel_id.get(), disabled) {
+        error!("Could not save the dice setting of channel {channel_id}: {why}");
+        return Some(("Alas, mine quill hath failed me and the setting could not be inscribed.".to_string(), true));
+    }
+
+    if disabled {
+        Some((format!("I shall no longer look for rolls in the messages of <#{}>. /roll still works there.", channel_id), true))
+    } else {
+        Some((format!("I shall look for rolls in the messages of <#{}> once more.", channel_id), true))
+    }
+}
+
+/// Whether rolls in the messages of the channel are ignored.
+pub fn disabled(guild_id: Option<GuildId>, channel_id: ChannelId) -> bool {
+    // Direct messages have no channels to tell apart:
+    guild_id.is_some() && database::get().channel_disabled(channel_id.get()).unwrap_or_else(|why| {
+        error!("Could not read the dice setting of channel {channel_id}: {why}");
+        false
+    })
+}
+
+fn list(guild_id: GuildId) -> String {
+    let channels = database::get().disabled_channels(guild_id.get())
+        .map_err(|why| error!("Could not read the dice settings of {guild_id}: {why}")).unwrap_or_default()
+        .into_iter().map(|id| format!("<#{}>", id)).collect::<Vec<String>>();
+
+    if channels.is_empty() {
+        "I look for rolls in every channel of this realm.".to_string()
+    } else {
+        format!("I do not look for rolls in: {}", channels.join(", "))
+    }
+}
+
+pub fn register() -> CreateCommand {
+    let channel = CreateCommandOption::new(CommandOptionType::Channel, "channel", "Channel to change (default = this one).").required(false);
+
+    CreateCommand::new("dicechannel").description("Choose the channels whose messages are looked through for rolls.")
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "disable", "Stop looking for rolls in a channel's messages.")
+            .add_sub_option(channel.clone()))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "enable", "Look for rolls in a channel's messages again.")
+            .add_sub_option(channel))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "list", "List the channels whose messages are not looked through."))
+        .default_member_permissions(Permissions::MANAGE_CHANNELS)
+}
@@ -0,0 +1,59 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use tracing::error;
+
+use crate::database;
+
+pub fn run(options: &[ResolvedOption<'_>], guild_id: Option<GuildId>) -> Option<(String, bool)> {
+    let Some(guild_id) = guild_id else {
+        return Some(("Only a server may choose how its rolls are shown, traveller.".to_string(), true));
+    };
+    let Some(ResolvedOption { value: ResolvedValue::Boolean(enabled), .. }) = options.first() else {
+        return None;
+    };
+
+    if let Err(why) = database::get().set_embeds(guild_id.get(), *enabled) {
+        error!("Could not save the embeds setting of {guild_id}: {why}");
+        return Some(("Alas, mine quill hath failed me and the setting could not be inscribed.".to_string(), true));
+    }
+
+    if *enabled {
+        Some(("Rolls in this realm shall be shown as embeds henceforth.".to_string(), true))
+    } else {
+        Some(("Rolls in this realm shall be shown as plain text henceforth.".to_string(), true))
+    }
+}
+
+/// Whether rolls in the server are shown as embeds, unless asked otherwise.
+pub fn enabled(guild_id: Option<GuildId>) -> bool {
+    guild_id.is_some_and(|guild_id| database::get().embeds(guild_id.get()).unwrap_or_else(|why| {
+        error!("Could not read the embeds setting of {guild_id}: {why}");
+        false
+    }))
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("embeds").description("Choose whether rolls in this server are shown as embeds.")
+        .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "enabled", "Show rolls as embeds (true) or as plain text (false).")
+            .required(true))
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+}
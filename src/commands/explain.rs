@@ -0,0 +1,54 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::GuildId;
+
+use denede_discord_bot::dice::{quote, Roll};
+
+use crate::commands::settings;
+
+pub fn run(options: &[ResolvedOption], guild_id: Option<GuildId>) -> Option<(String, bool)> {
+    let mut expression = "";
+    for option in options {
+        if let ResolvedOption { name: "expression", value: ResolvedValue::String(value), .. } = option {
+            expression = value;
+        }
+    }
+
+    // Bracketed or not, as with /roll:
+    let expression = expression.trim();
+    let expr = if expression.starts_with('[') { expression.to_string() } else { format!("[{}]", expression) };
+    let response = match Roll::parse_with(&expr, settings::syntax(guild_id)) {
+        None => format!("{} is no roll I know of, traveller.", quote(&expr)),
+        Some(Err(why)) => why.quoting(&expr),
+        Some(Ok(roll)) => {
+            let description = roll.describe();
+            let mut chars = description.chars();
+            let description = chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default();
+            format!("Thus would I roll {}, traveller:\n{}", quote(&expr), description)
+        },
+    };
+    Some((response, false))
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("explain").description("Tell what an expression does, in plain words, without rolling it.")
+        .add_option(CreateCommandOption::new(CommandOptionType::String, "expression", "Expression to explain, e.g.: 3d20km1+2")
+            .required(true))
+}
@@ -0,0 +1,46 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use denede_discord_bot::dice::Roll;
+
+pub async fn run(options: &[ResolvedOption<'_>]) -> Option<(String, bool)> {
+    let mut count = 1;
+    let mut ephemeral = false;
+    for option in options {
+        match option {
+            ResolvedOption { name: "count", value: ResolvedValue::Integer(value), .. } => count = *value,
+            ResolvedOption { name: "hidden", value: ResolvedValue::Boolean(value), .. } => ephemeral = *value,
+            _ => {},
+        }
+    }
+
+    match Roll::coins(count) {
+        Ok(coins) => Some((coins.roll().await.to_string(), ephemeral)),
+        Err(why) => Some((why.to_string(), true)),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("flip").description("Flip one or more coins.")
+        .add_option(CreateCommandOption::new(CommandOptionType::Integer, "count", "Amount of coins to flip (default = 1).")
+            .required(false))
+        .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "hidden", "Hide the command's response to other users (default = false).")
+            .required(false))
+}
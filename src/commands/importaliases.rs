@@ -0,0 +1,144 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::OnceLock;
+use std::time::Duration;
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::channel::Attachment;
+use serenity::model::id::{GuildId, UserId};
+use tracing::{error, warn};
+
+use denede_discord_bot::avrae;
+
+use crate::commands::settings;
+use crate::commands::MISSING_OPTION;
+use crate::database;
+
+// Largest export read, far beyond what dozens of aliases take:
+const MAX_EXPORT_BYTES: u32 = 256 * 1024;
+// Most macros a user may keep:
+const MAX_MACROS: usize = 200;
+// Skipped aliases told one by one, so the report fits in a message:
+const MAX_REPORTED: usize = 15;
+// Longest suggestion Discord allows:
+const MAX_SUGGESTION: usize = 100;
+// Time the export is given to download:
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub async fn run(options: &[ResolvedOption<'_>], user_id: UserId, guild_id: Option<GuildId>) -> Option<(String, bool)> {
+    let Some(export) = options.iter().find_map(|option| match option {
+        ResolvedOption { name: "export", value: ResolvedValue::Attachment(export), .. } => Some(*export),
+        _ => None,
+    }) else {
+        return Some((MISSING_OPTION.to_string(), true));
+    };
+
+    // Macros are nobody else's business:
+    Some((import(export, user_id, guild_id).await, true))
+}
+
+async fn import(export: &Attachment, user_id: UserId, guild_id: Option<GuildId>) -> String {
+    if export.size > MAX_EXPORT_BYTES {
+        return format!("That scroll is far too long, traveller: I read none beyond {} KiB.", MAX_EXPORT_BYTES / 1024);
+    }
+    let text = match download(&export.url).await {
+        Ok(text) => text,
+        Err(why) => {
+            warn!("Could not download the aliases of {user_id}: {why}");
+            return "Alas, the scroll could not be fetched. Prithee try again anon.".to_string();
+        },
+    };
+    let mut import = match avrae::import(&text, settings::syntax(guild_id)) {
+        Ok(import) => import,
+        Err(why) => return format!("I could make naught of that scroll, traveller: it is neither Avrae's JSON nor its `!alias` commands ({}).", why),
+    };
+
+    let kept = match database::get().macros(user_id.get()) {
+        Ok(kept) => kept,
+        Err(why) => {
+            error!("Could not read the macros of {user_id}: {why}");
+            return "Alas, mine quill hath failed me and the macros could not be inscribed.".to_string();
+        },
+    };
+    // Aliases replacing kept macros take no more room:
+    let room = MAX_MACROS.saturating_sub(kept.iter().filter(|(name, _)| import.imported.iter().all(|(imported, _)| imported != name)).count());
+    let beyond = import.imported.split_off(room.min(import.imported.len()));
+    if let Err(why) = database::get().set_macros(user_id.get(), &import.imported) {
+        error!("Could not save the macros of {user_id}: {why}");
+        return "Alas, mine quill hath failed me and the macros could not be inscribed.".to_string();
+    }
+
+    report(&import, beyond.len())
+}
+
+/// The user's macros whose names or rolls start with what they have typed so far, labelled with
+/// their names, e.g.: "atk: 1d20+5".
+pub fn suggest(user_id: UserId, typed: &str) -> Vec<String> {
+    let typed = typed.trim().to_lowercase();
+    let macros = database::get().macros(user_id.get()).unwrap_or_else(|why| {
+        error!("Could not read the macros of {user_id}: {why}");
+        Vec::new()
+    });
+    macros.into_iter()
+        .filter(|(name, expression)| name.to_lowercase().starts_with(&typed) || expression.to_lowercase().starts_with(&typed))
+        .map(|(name, expression)| format!("{}: {}", name, expression))
+        .filter(|suggestion| suggestion.chars().count() <= MAX_SUGGESTION)
+        .collect()
+}
+
+// Single client for every download, so connections to Discord's CDN are reused:
+async fn download(url: &str) -> reqwest::Result<String> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    let client = CLIENT.get_or_init(|| reqwest::Client::builder().timeout(DOWNLOAD_TIMEOUT).build().expect("No client?"));
+    client.get(url).send().await?.error_for_status()?.text().await
+}
+
+// How many aliases were imported, and which were skipped and why, e.g.:
+// "Imported 12 macros, and skipped 1 alias:
+// • `init`: it is scripted in Draconic, a tongue I do not speak"
+fn report(import: &avrae::Import, beyond: usize) -> String {
+    let plural = |count: usize, one: &str, many: &str| format!("{} {}", count, if count == 1 { one } else { many });
+    let skipped = import.skipped.len() + beyond;
+    let mut report = format!("Imported {}", plural(import.imported.len(), "macro", "macros"));
+    if skipped == 0 {
+        report.push('.');
+    } else {
+        report.push_str(&format!(", and skipped {}:", plural(skipped, "alias", "aliases")));
+    }
+
+    for (name, why) in import.skipped.iter().take(MAX_REPORTED) {
+        let name = name.chars().filter(|&c| c != '`').take(32).collect::<String>();
+        report.push_str(&format!("\n• `{}`: {}", name, why));
+    }
+    if import.skipped.len() > MAX_REPORTED {
+        report.push_str(&format!("\n• …and {} more.", import.skipped.len() - MAX_REPORTED));
+    }
+    if beyond > 0 {
+        report.push_str(&format!("\n• {}, as no traveller may keep more than {} macros.", plural(beyond, "alias found no room", "aliases found no room"), MAX_MACROS));
+    }
+    if !import.imported.is_empty() {
+        report.push_str("\nType a macro's name in the expression of `/roll` to find it.");
+    }
+    report
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("importaliases").description("Keep the dice rolls of your Avrae aliases as your own macros.")
+        .add_option(CreateCommandOption::new(CommandOptionType::Attachment, "export", "Avrae's JSON export of your aliases, or a text file of their !alias commands.")
+            .required(true))
+}
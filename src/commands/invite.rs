@@ -0,0 +1,51 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::ApplicationId;
+use serenity::model::permissions::Permissions;
+
+use crate::config;
+
+// Permissions asked for when the bot is invited, unless DENEDE_INVITE_PERMISSIONS says otherwise:
+const PERMISSIONS: Permissions = Permissions::SEND_MESSAGES.union(Permissions::EMBED_LINKS).union(Permissions::READ_MESSAGE_HISTORY);
+
+pub fn run(options: &[ResolvedOption], application_id: Option<ApplicationId>) -> Option<(String, bool)> {
+    let mut ephemeral = false;
+    if let Some(ResolvedOption { name: "hidden", value: ResolvedValue::Boolean(hidden), .. }) = options.first() {
+        ephemeral = *hidden;
+    }
+
+    let Some(application_id) = application_id else {
+        return Some(("I have not yet awoken enough to know mine own name, traveller. Ask me again in a moment.".to_string(), true));
+    };
+    let url = format!("https://discord.com/oauth2/authorize?client_id={}&scope=bot%20applications.commands&permissions={}", application_id, permissions());
+    Some((format!("Wouldst thou have me roll in thine own halls, traveller? Summon me thus: <{}>", url), ephemeral))
+}
+
+// Permission integer of the invite, as configured:
+fn permissions() -> u64 {
+    config::get().invite_permissions.unwrap_or(PERMISSIONS.bits())
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("invite").description("Show the link to invite this bot to another server.").add_option(
+        CreateCommandOption::new(CommandOptionType::Boolean, "hidden", "Hide the command's response to other users (default = false).")
+            .required(false),
+    )
+}
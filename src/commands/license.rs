@@ -15,20 +15,26 @@
  *  You should have received a copy of the GNU Affero General Public License
  *  along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
-use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::builder::{CreateAttachment, CreateCommand, CreateCommandOption};
 use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
 
-pub fn run(options: &[ResolvedOption]) -> Option<(String, bool)> {
-    let ephemeral: bool;
-    if let Some(ResolvedOption {
-        value: ResolvedValue::Boolean(is_ephemeral), ..
-    }) = options.first() {
-        ephemeral = *is_ephemeral;
-    } else {
-        ephemeral = true;
+use crate::commands::Response;
+
+// Full text of the license, far too long for a message, so it is sent as a file:
+const LICENSE: &str = include_str!("../../LICENSE");
+
+pub fn run(options: &[ResolvedOption]) -> Option<(Response, bool)> {
+    let mut ephemeral = true;
+    let mut summary = false;
+    for option in options {
+        match option {
+            ResolvedOption { name: "hidden", value: ResolvedValue::Boolean(value), .. } => ephemeral = *value,
+            ResolvedOption { name: "summary", value: ResolvedValue::Boolean(value), .. } => summary = *value,
+            _ => {},
+        }
     }
 
-    Some(("Denedé: Discord bot for generating D&D dice rolls, written in Rust. \
+    let notice = "Denedé: Discord bot for generating D&D dice rolls, written in Rust. \
     Copyright (C) 2023-2024  Bolu <bolu@tuta.io>\n\
     \n\
     This program is free software: you can redistribute it and/or modify \
@@ -42,14 +48,22 @@ pub fn run(options: &[ResolvedOption]) -> Option<(String, bool)> {
     GNU Affero General Public License for more details.\n\
     \n\
     You should have received a copy of the GNU Affero General Public License \
-    along with this program. If not, see <https://www.gnu.org/licenses/>.\n".to_string(),
-    ephemeral))
+    along with this program. If not, see <https://www.gnu.org/licenses/>.\n".to_string();
+
+    if summary {
+        return Some((Response::Content(notice), ephemeral));
+    }
+    let notice = format!("{}\nThe full text of the license is attached, and my source code can be found here: https://github.com/0xb01u/denede", notice);
+    Some((Response::File(notice, CreateAttachment::bytes(LICENSE, "LICENSE.txt")), ephemeral))
 }
 
 pub fn register() -> CreateCommand {
     CreateCommand::new("license").description("Show the software license for this bot.").add_option(
         CreateCommandOption::new(CommandOptionType::Boolean, "hidden", "Hide the command's response to other users (default = true).")
             .required(false),
+    ).add_option(
+        CreateCommandOption::new(CommandOptionType::Boolean, "summary", "Show only the license notice, without the full text (default = false).")
+            .required(false),
     )
 }
 
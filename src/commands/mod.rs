@@ -15,7 +15,57 @@
  *  You should have received a copy of the GNU Affero General Public License
  *  along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
+use serenity::builder::{CreateAttachment, CreateEmbed};
+
+pub mod auditinfo;
+pub mod character;
+pub mod check;
+pub mod dicechannel;
+pub mod embeds;
+pub mod explain;
+pub mod importaliases;
+pub mod invite;
 pub mod license;
+pub mod mystats;
 pub mod ping;
+pub mod preferences;
 pub mod code;
+pub mod flip;
+pub mod probability;
+pub mod roll;
+pub mod settings;
+pub mod shuffle;
+pub mod statroll;
+pub mod table;
+pub mod uptime;
+pub mod verify;
+pub mod version;
+
+// Answer to a command lacking an option Discord should have required:
+pub const MISSING_OPTION: &str = "Thou hast left out something I need, traveller. Prithee try again, telling me all I ask for.";
+
+// What a slash command answers with:
+#[derive(Clone)]
+pub enum Response {
+    Content(String),
+    // Embeds, below a note that may be empty:
+    Embeds(String, Vec<CreateEmbed>),
+    // A file, below a note, e.g.: the full text of the license:
+    File(String, CreateAttachment),
+    // Text too long for a single message: the first part answers, and the others follow it up:
+    Parts(Vec<String>),
+}
+
+impl Response {
+    /// The response itself, and the text of the messages that follow it up, if any.
+    pub fn split(self) -> (Response, Vec<String>) {
+        match self {
+            Response::Parts(parts) => {
+                let mut parts = parts.into_iter();
+                (Response::Content(parts.next().unwrap_or_default()), parts.collect())
+            },
+            response => (response, Vec::new()),
+        }
+    }
+}
 
@@ -0,0 +1,151 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::{GuildId, UserId};
+use tracing::error;
+
+use denede_discord_bot::dice::{escape, RollResult};
+use denede_discord_bot::store::{Notable, RollStats};
+
+use crate::database;
+
+/// Rolls of a user, counted towards their statistics once the response showing them is sent, so the
+/// luckiest and unluckiest can link to it.
+pub struct Unrecorded {
+    user_id: UserId,
+    guild_id: Option<GuildId>,
+    stats: RollStats,
+}
+
+impl Unrecorded {
+    pub fn new(user_id: UserId, guild_id: Option<GuildId>) -> Unrecorded {
+        Unrecorded { user_id, guild_id, stats: RollStats::default() }
+    }
+
+    /// Count a roll of the given expression.
+    pub fn add(&mut self, expression: &str, result: &RollResult) {
+        // Seeded rolls are not left to fortune:
+        if let RollResult::Seeded { .. } = result {
+            return;
+        }
+        let dice = result.individuals();
+        let faces = dice.iter().filter(|dice| dice.sides() > 1)
+            .flat_map(|dice| dice.rolls().iter().map(|&value| (value - 1) as f64 / (dice.sides() - 1) as f64))
+            .collect::<Vec<_>>();
+        let d20s = dice.iter().filter(|dice| dice.sides() == 20).flat_map(|dice| dice.rolls()).collect::<Vec<_>>();
+
+        let stats = &mut self.stats;
+        stats.rolls += 1;
+        stats.d20s += d20s.len() as u64;
+        stats.natural_20s += d20s.iter().filter(|&&&value| value == 20).count() as u64;
+        stats.natural_1s += d20s.iter().filter(|&&&value| value == 1).count() as u64;
+        stats.d20_sum += d20s.into_iter().sum::<i64>();
+        if !faces.is_empty() {
+            let luck = faces.iter().sum::<f64>() / faces.len() as f64;
+            // Placed once the response is sent:
+            stats.notice(&Notable { luck, expression: expression.to_string(), total: result.total(), place: String::new() });
+        }
+    }
+
+    /// Count the rolls towards the user's statistics. The place is where they can be found, e.g.: a link
+    /// to the message they were shown in.
+    pub fn record(mut self, place: &str) {
+        if self.stats.rolls == 0 {
+            return;
+        }
+        for notable in self.stats.luckiest.iter_mut().chain(self.stats.unluckiest.iter_mut()) {
+            notable.place = place.to_string();
+        }
+        if let Err(why) = database::get().add_roll_stats(self.user_id.get(), self.guild_id.map(GuildId::get), &self.stats) {
+            error!("Could not count the rolls of {} towards their statistics: {why}", self.user_id);
+        }
+    }
+}
+
+/// Count a roll of a user towards their statistics. The place is where it can be found, e.g.: a link to
+/// its message.
+pub fn record(user_id: UserId, guild_id: Option<GuildId>, expression: &str, place: String, result: &RollResult) {
+    let mut unrecorded = Unrecorded::new(user_id, guild_id);
+    unrecorded.add(expression, result);
+    unrecorded.record(&place);
+}
+
+pub fn run(options: &[ResolvedOption], user_id: UserId, guild_id: Option<GuildId>) -> Option<(String, bool)> {
+    let mut global = guild_id.is_none();
+    let mut ephemeral = true;
+    for option in options {
+        match option {
+            ResolvedOption { name: "scope", value: ResolvedValue::String(value), .. } => global = *value == "global",
+            ResolvedOption { name: "hidden", value: ResolvedValue::Boolean(value), .. } => ephemeral = *value,
+            _ => {},
+        }
+    }
+
+    let kept = match database::get().roll_stats(user_id.get()) {
+        Ok(kept) => kept,
+        Err(why) => {
+            error!("Could not read the statistics of {user_id}: {why}");
+            return Some(("Alas, mine quill hath failed me and thy statistics could not be read.".to_string(), true));
+        },
+    };
+    let mut stats = RollStats::default();
+    for (_, guild_stats) in kept.iter().filter(|(guild, _)| global || *guild == guild_id.map(GuildId::get)) {
+        stats.add(guild_stats);
+    }
+    Some((show(&stats, global), ephemeral))
+}
+
+fn show(stats: &RollStats, global: bool) -> String {
+    let whereabouts = if global { "everywhere" } else { "in this server" };
+    if stats.rolls == 0 {
+        return format!("Thou hast rolled naught {} yet, traveller.", whereabouts);
+    }
+    let share = |count: u64| format!("{} ({:.1}%)", count, 100.0 * count as f64 / stats.d20s as f64);
+    let mut lines = vec![
+        format!("Thy fortunes {}, traveller:", whereabouts),
+        format!("Rolls: {}", stats.rolls),
+        format!("d20s rolled: {}", stats.d20s),
+    ];
+    if stats.d20s > 0 {
+        lines.push(format!("Natural 20s: {}", share(stats.natural_20s)));
+        lines.push(format!("Natural 1s: {}", share(stats.natural_1s)));
+        lines.push(format!("Average d20: {:.2}", stats.d20_sum as f64 / stats.d20s as f64));
+    }
+    let notable = |notable: &Notable| match notable.total {
+        Some(total) => format!("{} → **{}**, {}", escape(&notable.expression), total, notable.place),
+        None => format!("{}, {}", escape(&notable.expression), notable.place),
+    };
+    if let Some(luckiest) = &stats.luckiest {
+        lines.push(format!("Luckiest roll: {}", notable(luckiest)));
+    }
+    if let Some(unluckiest) = &stats.unluckiest {
+        lines.push(format!("Unluckiest roll: {}", notable(unluckiest)));
+    }
+    lines.join("\n")
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("mystats").description("Tell thy roll statistics.")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "scope", "Whether to count the rolls of this server only, or of everywhere.")
+                .add_string_choice("guild", "guild")
+                .add_string_choice("global", "global")
+        )
+        .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "hidden", "Whether only thou canst see the statistics (yes by default)."))
+}
@@ -15,10 +15,39 @@
  *  You should have received a copy of the GNU Affero General Public License
  *  along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
+use std::sync::Arc;
+
 use serenity::all::Context;
 use serenity::builder::{CreateCommand, CreateCommandOption, CreateInteractionResponse, CreateInteractionResponseMessage, EditInteractionResponse};
+use serenity::gateway::ShardManager;
 use serenity::model::application::{CommandOptionType, Interaction, ResolvedOption, ResolvedValue};
 use serenity::model::timestamp::Timestamp;
+use serenity::prelude::TypeMapKey;
+use tracing::warn;
+
+/// Key of the shard manager in the client's data, so the health of the gateway connection can be told.
+pub struct ShardManagerContainer;
+
+impl TypeMapKey for ShardManagerContainer {
+    type Value = Arc<ShardManager>;
+}
+
+// Health of the shard the command came through, e.g.: "Gateway latency: 42ms (shard 0 of 2, connected)"
+async fn gateway(ctx: &Context) -> String {
+    let data = ctx.data.read().await;
+    let Some(shard_manager) = data.get::<ShardManagerContainer>() else {
+        return "Gateway latency: unknown".to_string();
+    };
+    let runners = shard_manager.runners.lock().await;
+    let shards = runners.len();
+    match runners.get(&ctx.shard_id) {
+        Some(runner) => match runner.latency {
+            Some(latency) => format!("Gateway latency: {}ms (shard {} of {}, {})", latency.as_millis(), ctx.shard_id, shards, runner.stage),
+            None => format!("Gateway latency: no heartbeat acknowledged yet (shard {} of {}, {})", ctx.shard_id, shards, runner.stage),
+        },
+        None => format!("Gateway latency: unknown (shard {})", ctx.shard_id),
+    }
+}
 
 pub async fn run(options: &[ResolvedOption<'_>], ctx: &Context, interaction: &Interaction) -> Option<(String, bool)> {
     if let Interaction::Command(command) = interaction {
@@ -34,20 +63,27 @@ pub async fn run(options: &[ResolvedOption<'_>], ctx: &Context, interaction: &In
         let interaction_date_sent = *interaction.id().created_at();
         let mut now = *Timestamp::now();
         // Take only milliseconds, omit nanoseconds (Discord timestamps only measure up to milliseconds):
-        now = now.replace_nanosecond(now.millisecond() as u32 * 1_000_000).unwrap();
+        now = now.replace_nanosecond(now.millisecond() as u32 * 1_000_000).unwrap_or(now);
 
-        let data = CreateInteractionResponseMessage::new().content(format!("Pong.\nReception latency: {}", now - interaction_date_sent)).ephemeral(ephemeral);
+        let gateway = gateway(ctx).await;
+        let data = CreateInteractionResponseMessage::new().content(format!("Pong.\nReception latency: {}\n{}", now - interaction_date_sent, gateway)).ephemeral(ephemeral);
         let builder = CreateInteractionResponse::Message(data);
         if let Err(why) = command.create_response(&ctx.http, builder).await {
-            println!("Cannot respond to ping command: {why}");
+            warn!("Cannot respond to ping command: {why}");
         }
 
         // Wait for response to be sent and compute roundtrip latency:
-        let response = command.get_response(&ctx.http).await.unwrap();
+        let response = match command.get_response(&ctx.http).await {
+            Ok(response) => response,
+            Err(why) => {
+                warn!("Cannot get ping response: {why}");
+                return None;
+            },
+        };
         let response_date_sent = *response.id.created_at();
-        let edit = EditInteractionResponse::new().content(format!("Pong.\nReception latency: {}\nRoundtrip latency: {}", now - interaction_date_sent, response_date_sent - interaction_date_sent));
+        let edit = EditInteractionResponse::new().content(format!("Pong.\nReception latency: {}\nRoundtrip latency: {}\n{}", now - interaction_date_sent, response_date_sent - interaction_date_sent, gateway));
         if let Err(why) = command.edit_response(&ctx.http, edit).await {
-            println!("Cannot edit ping response: {why}");
+            warn!("Cannot edit ping response: {why}");
         }
     }
     None
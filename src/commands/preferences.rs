@@ -0,0 +1,109 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::UserId;
+use tracing::error;
+
+use denede_discord_bot::dice::Style;
+use denede_discord_bot::store::Preferences as Chosen;
+
+use crate::database;
+
+/// How a user wants their rolls shown. Whatever is not set is shown as for everyone else.
+#[derive(Clone, Copy, Default)]
+pub struct Preferences(Chosen);
+
+impl Preferences {
+    /// Whether only the results of the rolls are shown.
+    pub fn compact(&self) -> bool {
+        self.0.compact.unwrap_or(false)
+    }
+
+    /// Style the rolls are shown with.
+    pub fn style(&self) -> Style {
+        let default = Style::default();
+        Style {
+            crits: self.0.crits.unwrap_or(default.crits),
+            pips: self.0.pips.unwrap_or(default.pips),
+            pseudo_random: self.0.pseudo_random.unwrap_or(default.pseudo_random),
+        }
+    }
+}
+
+pub fn run(options: &[ResolvedOption<'_>], user_id: UserId) -> Option<(String, bool)> {
+    let Some(ResolvedOption { name: subcommand, value: ResolvedValue::SubCommand(options), .. }) = options.first() else {
+        return None;
+    };
+
+    // Preferences are nobody else's business:
+    let chosen = match *subcommand {
+        "show" => return Some((show(&of(user_id)), true)),
+        "set" => {
+            let mut chosen = of(user_id).0;
+            for option in options {
+                match option {
+                    ResolvedOption { name: "compact", value: ResolvedValue::Boolean(value), .. } => chosen.compact = Some(*value),
+                    ResolvedOption { name: "crits", value: ResolvedValue::Boolean(value), .. } => chosen.crits = Some(*value),
+                    ResolvedOption { name: "pips", value: ResolvedValue::Boolean(value), .. } => chosen.pips = Some(*value),
+                    ResolvedOption { name: "pseudo-random", value: ResolvedValue::Boolean(value), .. } => chosen.pseudo_random = Some(*value),
+                    _ => {},
+                }
+            }
+            Some(chosen)
+        },
+        "reset" => None,
+        _ => return None,
+    };
+
+    if let Err(why) = database::get().set_preferences(user_id.get(), chosen.as_ref()) {
+        error!("Could not save the preferences of {user_id}: {why}");
+        return Some(("Alas, mine quill hath failed me and thy preferences could not be inscribed.".to_string(), true));
+    }
+    Some((show(&Preferences(chosen.unwrap_or_default())), true))
+}
+
+/// Preferences of a user, as far as they have set any.
+pub fn of(user_id: UserId) -> Preferences {
+    Preferences(database::get().preferences(user_id.get()).unwrap_or_else(|why| {
+        error!("Could not read the preferences of {user_id}: {why}");
+        Chosen::default()
+    }))
+}
+
+fn show(preferences: &Preferences) -> String {
+    let style = preferences.style();
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    format!(
+        "Thy rolls are shown thus, traveller:\nOutput: {}\nNatural 20s and 1s highlighted: {}\nd6s shown by their faces: {}\nPseudo-random rolls marked: {}",
+        if preferences.compact() { "compact" } else { "verbose" }, yes_no(style.crits), yes_no(style.pips), yes_no(style.pseudo_random),
+    )
+}
+
+pub fn register() -> CreateCommand {
+    let preference = |name: &str, description: &str| CreateCommandOption::new(CommandOptionType::Boolean, name, description).required(false);
+
+    CreateCommand::new("preferences").description("Choose how thy rolls are shown.")
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "show", "Show how thy rolls are shown."))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "set", "Change how thy rolls are shown. Leave out what should stay as it is.")
+            .add_sub_option(preference("compact", "Show only the result of each roll, not every die."))
+            .add_sub_option(preference("crits", "Highlight natural 20s and 1s of d20s."))
+            .add_sub_option(preference("pips", "Show d6s by their faces, e.g.: ⚄"))
+            .add_sub_option(preference("pseudo-random", "Mark the rolls that are pseudo-random.")))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "reset", "Show thy rolls as everyone else's again."))
+}
@@ -0,0 +1,86 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::GuildId;
+
+use denede_discord_bot::dice::{quote, Distribution, Roll};
+
+use crate::commands::settings;
+
+// Ranges of totals shown in the histogram, and widest bar drawn for them:
+const BUCKETS: usize = 10;
+const BAR_WIDTH: usize = 20;
+
+pub fn run(options: &[ResolvedOption], guild_id: Option<GuildId>) -> Option<(String, bool)> {
+    let mut expression = "";
+    let mut at_least = None;
+    for option in options {
+        match option {
+            ResolvedOption { name: "expression", value: ResolvedValue::String(value), .. } => expression = value,
+            ResolvedOption { name: "at_least", value: ResolvedValue::Integer(value), .. } => at_least = Some(*value),
+            _ => {},
+        }
+    }
+
+    // Bracketed or not, as with /roll:
+    let expression = expression.trim();
+    let expr = if expression.starts_with('[') { expression.to_string() } else { format!("[{}]", expression) };
+    let response = match Roll::parse_with(&expr, settings::syntax(guild_id)) {
+        None => format!("{} is no roll I know of, traveller.", quote(&expr)),
+        Some(Err(why)) => why.quoting(&expr),
+        Some(Ok(roll)) => match roll.distribution() {
+            None => "Symbols have no chances to reckon, traveller. Only numerals do!".to_string(),
+            Some(Err(why)) => why.quoting(&expr),
+            Some(Ok(distribution)) => show(&expr, &distribution, at_least),
+        },
+    };
+    Some((response, false))
+}
+
+fn show(expr: &str, distribution: &Distribution, at_least: Option<i64>) -> String {
+    let mut lines = vec![format!("Chances of {}:", quote(expr))];
+    if !distribution.exact() {
+        lines[0].push_str(" (estimated from many local rolls)");
+    }
+    lines.push(format!("Minimum: {} · Maximum: {} · Mean: {:.2}", distribution.minimum(), distribution.maximum(), distribution.mean()));
+
+    let histogram = distribution.histogram(BUCKETS);
+    let ranges = histogram.iter().map(|&(low, high, _)| if low == high { low.to_string() } else { format!("{}–{}", low, high) }).collect::<Vec<_>>();
+    let widest = ranges.iter().map(|range| range.chars().count()).max().unwrap_or(0);
+    let tallest = histogram.iter().map(|&(_, _, chance)| chance).fold(0.0, f64::max);
+    lines.push("```".to_string());
+    for (range, (_, _, chance)) in ranges.iter().zip(&histogram) {
+        let bar = if tallest > 0.0 { (chance / tallest * BAR_WIDTH as f64).round() as usize } else { 0 };
+        let padding = widest - range.chars().count();
+        lines.push(format!("{}{} {:<width$} {:>6.2}%", " ".repeat(padding), range, "█".repeat(bar), chance * 100.0, width = BAR_WIDTH));
+    }
+    lines.push("```".to_string());
+
+    if let Some(value) = at_least {
+        lines.push(format!("Chance of {} or more: {:.2}%", value, distribution.at_least(value) * 100.0));
+    }
+    lines.join("\n")
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("probability").description("Tell the chances of every total of an expression, without rolling it.")
+        .add_option(CreateCommandOption::new(CommandOptionType::String, "expression", "Expression to reckon the chances of, e.g.: 2d6+3")
+            .required(true))
+        .add_option(CreateCommandOption::new(CommandOptionType::Integer, "at_least", "Total whose chance of being met or beaten is also told."))
+}
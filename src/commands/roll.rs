@@ -0,0 +1,399 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serenity::builder::{CreateActionRow, CreateButton, CreateCommand, CreateCommandOption, CreateMessage};
+use serenity::model::application::{ButtonStyle, CommandOptionType, ComponentInteraction, ResolvedOption, ResolvedValue};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+use tracing::{debug, info_span, warn, Instrument};
+
+use denede_discord_bot::dice::{chunks, escape, parse_bound, quote, split_line, styled, Roll, RollResult, Style};
+use denede_discord_bot::metrics;
+use denede_discord_bot::retry::with_retries;
+use denede_discord_bot::verify;
+
+use crate::commands::mystats::{self, Unrecorded};
+use crate::commands::{embeds, preferences, settings, Response};
+use crate::tally::Tally;
+use crate::{audit, characters, embed};
+
+// Longest custom id Discord allows for a button:
+const MAX_CUSTOM_ID: usize = 100;
+// Longest label given to the command that is shown:
+const MAX_LABEL: usize = 100;
+// Longest line of a response kept whole, leaving room within Discord's 2000 characters for a note:
+const PART_LENGTH: usize = 1900;
+// Most messages a response to /roll is split into, before only the totals are shown:
+const MAX_PARTS: usize = 5;
+
+// How the expressions of a command are rolled and shown:
+struct Choices {
+    seed: Option<u64>,
+    compact: bool,
+    style: Style,
+    as_embeds: bool,
+    // Difficulty class every roll is compared against, e.g.: 15
+    dc: Option<i64>,
+    // Label of the expressions without one of their own, e.g.: "Stealth check"
+    label: Option<String>,
+}
+
+impl Choices {
+    // The user's preferences, and the server's choice of embeds:
+    fn of(user: &User, guild_id: Option<GuildId>) -> Choices {
+        let preferences = preferences::of(user.id);
+        Choices { seed: None, compact: preferences.compact(), style: preferences.style(), as_embeds: embeds::enabled(guild_id), dc: None, label: None }
+    }
+}
+
+/// Response to the command, whether it is hidden, its buttons, and its rolls, to be counted towards
+/// the user's statistics once it is sent.
+pub async fn run(options: &[ResolvedOption<'_>], ctx: &Context, user: &User, channel_id: ChannelId, guild_id: Option<GuildId>, tally: &Tally) -> Option<(Response, bool, Vec<CreateButton>, Unrecorded)> {
+    let mut expression = "";
+    let mut ephemeral = settings::hidden_rolls(guild_id);
+    let mut secret = false;
+    // The user's preferences, unless overridden for this command:
+    let mut choices = Choices::of(user, guild_id);
+    for option in options {
+        match option {
+            ResolvedOption { name: "expression", value: ResolvedValue::String(value), .. } => expression = value,
+            ResolvedOption { name: "label", value: ResolvedValue::String(value), .. } => {
+                let label = value.trim().chars().take(MAX_LABEL).collect::<String>();
+                choices.label = Some(escape(&label)).filter(|label| !label.is_empty());
+            },
+            ResolvedOption { name: "hidden", value: ResolvedValue::Boolean(value), .. } => ephemeral = *value,
+            ResolvedOption { name: "seed", value: ResolvedValue::Integer(value), .. } => choices.seed = Some(*value as u64),
+            ResolvedOption { name: "dc", value: ResolvedValue::Integer(value), .. } => choices.dc = Some(*value),
+            ResolvedOption { name: "compact", value: ResolvedValue::Boolean(value), .. } => choices.compact = *value,
+            ResolvedOption { name: "pips", value: ResolvedValue::Boolean(value), .. } => choices.style.pips = *value,
+            ResolvedOption { name: "embed", value: ResolvedValue::Boolean(value), .. } => choices.as_embeds = *value,
+            ResolvedOption { name: "secret", value: ResolvedValue::Boolean(value), .. } => secret = *value,
+            _ => {},
+        }
+    }
+
+    match roll(expression, user, channel_id, guild_id, &choices, tally).await {
+        // Only the user sees secret rolls, unless they cannot be sent to them:
+        Some((response, _, rolled_secretly, unrecorded)) if secret || rolled_secretly => {
+            let messages = match &response {
+                Response::Content(content) => vec![CreateMessage::new().content(content)],
+                Response::Embeds(note, embeds) => vec![CreateMessage::new().content(note).embeds(embeds.clone())],
+                Response::File(note, file) => vec![CreateMessage::new().content(note).add_file(file.clone())],
+                Response::Parts(parts) => parts.iter().map(|part| CreateMessage::new().content(part)).collect(),
+            };
+            let mut whispered = Ok(());
+            for message in messages {
+                whispered = with_retries(|| user.direct_message(ctx, message.clone())).await.map(|_| ());
+                if whispered.is_err() {
+                    break;
+                }
+            }
+            match whispered {
+                Ok(()) => Some((Response::Content(secret_note(user)), ephemeral, Vec::new(), unrecorded)),
+                Err(why) => {
+                    warn!("Could not send a secret roll to {}: {why}", user.id);
+                    metrics::get().send_failure("secret_roll");
+                    Some((response, true, Vec::new(), unrecorded))
+                },
+            }
+        },
+        // Hidden rolls are not to be followed by others:
+        Some((response, _, _, unrecorded)) if ephemeral => Some((response, true, Vec::new(), unrecorded)),
+        Some((response, results, _, unrecorded)) => Some((response, false, buttons(user.id, expression, &results), unrecorded)),
+        None => Some((Response::Content("Thou hast given me naught to roll, traveller.".to_string()), true, Vec::new(), Unrecorded::new(user.id, guild_id))),
+    }
+}
+
+/// Whether the response to the command is to be hidden from other users: as asked, or as the server
+/// chose otherwise.
+pub fn hidden(options: &[ResolvedOption<'_>], guild_id: Option<GuildId>) -> bool {
+    options.iter().find_map(|option| match option {
+        ResolvedOption { name: "hidden", value: ResolvedValue::Boolean(hidden), .. } => Some(*hidden),
+        _ => None,
+    }).unwrap_or(settings::hidden_rolls(guild_id))
+}
+
+/// What others see of a secret roll.
+pub fn secret_note(user: &User) -> String {
+    format!("{} rolled secretly 🎲", escape(user.global_name.as_deref().unwrap_or(&user.name)))
+}
+
+// Roll every expression of the given text, if there is any, along with the results of those that
+// could be rolled, whether any of them was secret, and the rolls yet to be counted towards the
+// user's statistics:
+async fn roll(expression: &str, user: &User, channel_id: ChannelId, guild_id: Option<GuildId>, choices: &Choices, tally: &Tally) -> Option<(Response, Vec<RollResult>, bool, Unrecorded)> {
+    let Choices { seed, compact, style, as_embeds, dc, .. } = *choices;
+    // Expressions are separated by semicolons, and may be labelled, e.g.: "Attack: 1d20+5; Damage: 2d6+3"
+    // Bracketed expressions are also accepted, as in chat, e.g.: "[1d20+5] [2d6+3]"
+    static BRACKETS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[[^\[\]]+\]").expect("No brackets regex?"));
+    let mut labelled = Vec::new();
+    for part in expression.split(';').map(str::trim).filter(|part| !part.is_empty()) {
+        let (label, expr) = match part.split_once(':') {
+            Some((label, expr)) => (Some(label.trim()), expr.trim()),
+            None => (None, part),
+        };
+
+        let exprs = if BRACKETS.is_match(expr) {
+            BRACKETS.find_iter(expr).map(|found| found.as_str().to_string()).collect::<Vec<String>>()
+        } else {
+            vec![format!("[{}]", expr)]
+        };
+        let label = label.filter(|label| !label.is_empty()).map(escape);
+        labelled.extend(exprs.into_iter().map(|expr| (label.clone(), expr)));
+    }
+
+    // Characters are only looked up for expressions that may have variables, e.g.: "1d20 + $dex"
+    let variables = expression.contains('$').then(|| characters::variables(user.id, guild_id)).flatten();
+
+    // The label of the command stands out, numbered if several expressions share it, e.g.: "**Stealth check #2**"
+    let shared = labelled.iter().filter(|(label, _)| label.is_none()).count();
+    let mut index = 0;
+    let mut lines = Vec::new();
+    let mut summaries = Vec::new();
+    let mut embeds = Vec::new();
+    let mut results = Vec::new();
+    let mut secret = false;
+    let mut unrecorded = Unrecorded::new(user.id, guild_id);
+    for (label, expr) in labelled {
+        let (label, bold) = match (label, &choices.label) {
+            (Some(label), _) => (Some(label), false),
+            (None, Some(label)) if shared > 1 => {
+                index += 1;
+                (Some(format!("{} #{}", label, index)), true)
+            },
+            (None, Some(label)) => (Some(label.clone()), true),
+            (None, None) => (None, false),
+        };
+
+        // Rolled with its variables bound, e.g.: "[1d20 + 3]" for "[1d20 + $dex]"
+        let (expr, roll) = parse_bound(&expr, settings::syntax(guild_id), variables.as_ref());
+        let result = match roll {
+            None => Err(format!("{} is no roll I know of, traveller.", quote(&expr))),
+            Some(Err(why)) => {
+                metrics::get().parse_error(&why);
+                Err(why.quoting(&expr))
+            },
+            Some(Ok(roll)) => {
+                secret |= roll.is_secret();
+                let roll = if compact { Roll::Compact(Box::new(roll)) } else { roll };
+                // Every expression is rolled with the same seed, so each can be reproduced on its own:
+                let roll = match seed {
+                    Some(seed) => Roll::Seeded { seed, roll: Box::new(roll) },
+                    None => roll,
+                };
+                let started = std::time::Instant::now();
+                let result = roll.roll().instrument(info_span!("roll", expression = expr)).await;
+                debug!(expression = expr, total = ?result.total(), truly_random = result.is_truly_random(), latency_ms = started.elapsed().as_millis() as u64, "Rolled");
+                audit::record(user, channel_id, &expr, &result);
+                unrecorded.add(&expr, &result);
+                tally.count(&result);
+                Ok(result)
+            },
+        };
+
+        let verdict = result.as_ref().ok().zip(dc).and_then(|(result, dc)| result.against(dc));
+        if as_embeds {
+            let title = match &label {
+                Some(label) => format!("{}: {}", label, expr),
+                None => expr.clone(),
+            };
+            embeds.push(match (&result, verdict) {
+                (Ok(result), Some((success, verdict))) => styled(style, || embed::roll(user, &title, &expr, result)).verdict(success, &verdict),
+                (Ok(result), None) => styled(style, || embed::roll(user, &title, &expr, result)),
+                (Err(why), _) => embed::error(user, &title, why),
+            });
+        } else {
+            let shown = match (&result, verdict) {
+                (Ok(result), Some((_, verdict))) => styled(style, || verify::with_note(&expr, result, &verdict)),
+                (Ok(result), None) => styled(style, || verify::with_code(&expr, result)),
+                (Err(why), _) => why.clone(),
+            };
+            // Only the total, should the whole result be too long to send:
+            let summary = match &result {
+                Ok(result) => format!("{} = {} *(too many dice to tell)*", quote(&expr), result.total().map_or("?".to_string(), |total| format!("**{}**", total))),
+                Err(why) => why.clone(),
+            };
+            let prefix = match label {
+                Some(label) if bold => format!("**{}**: ", label),
+                Some(label) => format!("{}: ", label),
+                None => String::new(),
+            };
+            lines.push(format!("{}{}", prefix, shown));
+            summaries.push(format!("{}{}", prefix, summary));
+        }
+        results.extend(result);
+    }
+    if lines.is_empty() && embeds.is_empty() {
+        return None;
+    }
+
+    // Everything must fit in a single message:
+    if as_embeds {
+        let mut messages = embed::messages(embeds);
+        let note = if messages.len() > 1 { "…and more rolls than a single scroll can hold." } else { "" };
+        return Some((Response::Embeds(note.to_string(), messages.swap_remove(0)), results, secret, unrecorded));
+    }
+
+    // Results too long for a single message follow it in others, split between numbers, unless they
+    // would take too many; then only their totals are shown:
+    let split = |lines: &[String]| chunks(&lines.iter().flat_map(|line| split_line(line, PART_LENGTH)).collect::<Vec<String>>());
+    let mut parts = split(&lines);
+    if parts.len() > MAX_PARTS {
+        parts = split(&summaries);
+    }
+    if parts.len() > MAX_PARTS {
+        parts.truncate(MAX_PARTS);
+        parts[MAX_PARTS - 1].push_str("…and more rolls than these scrolls can hold.");
+    }
+    let response = match parts.len() {
+        1 => Response::Content(parts.swap_remove(0)),
+        _ => Response::Parts(parts),
+    };
+
+    Some((response, results, secret, unrecorded))
+}
+
+/// Buttons for the results of the given expressions (as written for /roll), for the user who rolled
+/// them: to roll them again, unless they do not fit within the button, and to roll a lone d20 again
+/// with advantage or disadvantage. Seeded rolls would only roll the same, so they have none.
+pub fn buttons(user_id: UserId, expression: &str, results: &[RollResult]) -> Vec<CreateButton> {
+    let mut buttons = Vec::new();
+    if results.is_empty() || results.iter().any(|result| matches!(result, RollResult::Seeded { .. })) {
+        return buttons;
+    }
+
+    let custom_id = format!("reroll:{}:{}", user_id, expression);
+    if custom_id.chars().count() <= MAX_CUSTOM_ID {
+        buttons.push(again(&custom_id));
+    }
+    if let [result] = results {
+        if let Some((value, modifier)) = result.lone_d20() {
+            let truly_random = u8::from(result.is_truly_random());
+            buttons.push(advantage_button(&format!("adv:{}:{}:{}:{}", user_id, value, modifier, truly_random)));
+            buttons.push(advantage_button(&format!("dis:{}:{}:{}:{}", user_id, value, modifier, truly_random)));
+        }
+    }
+    buttons
+}
+
+/// Row holding the given buttons, if any.
+pub fn row(buttons: Vec<CreateButton>) -> Vec<CreateActionRow> {
+    if buttons.is_empty() {
+        Vec::new()
+    } else {
+        vec![CreateActionRow::Buttons(buttons)]
+    }
+}
+
+/// Re-roll button of the given custom id, e.g.: for the result of pressing it.
+pub fn again(custom_id: &str) -> CreateButton {
+    CreateButton::new(custom_id).label("Re-roll").emoji('🎲').style(ButtonStyle::Secondary)
+}
+
+// Button to roll a lone d20 again with advantage (adv:…) or disadvantage (dis:…):
+fn advantage_button(custom_id: &str) -> CreateButton {
+    let label = if custom_id.starts_with("adv:") { "Roll with advantage" } else { "Roll with disadvantage" };
+    CreateButton::new(custom_id).label(label).style(ButtonStyle::Secondary)
+}
+
+// Whether the user who pressed a button may do so: the user who rolled, or someone who may manage messages:
+fn may_press(component: &ComponentInteraction, user_id: &str) -> bool {
+    let moderator = component.member.as_ref().and_then(|member| member.permissions).is_some_and(|permissions| permissions.manage_messages());
+    component.user.id.to_string() == user_id || moderator
+}
+
+/// Roll again the expressions of a re-roll button, as the user who pressed it would, with the buttons
+/// of the new rolls, and the rolls to be counted towards the user's statistics once they are shown.
+pub async fn reroll(component: &ComponentInteraction, tally: &Tally) -> Option<(Response, bool, Vec<CreateButton>, Unrecorded)> {
+    let (user_id, expression) = component.data.custom_id.strip_prefix("reroll:")?.split_once(':')?;
+    if !may_press(component, user_id) {
+        return Some((Response::Content("These dice are not thine to cast again, traveller.".to_string()), true, Vec::new(), Unrecorded::new(component.user.id, component.guild_id)));
+    }
+
+    let user = &component.user;
+    match roll(expression, user, component.channel_id, component.guild_id, &Choices::of(user, component.guild_id), tally).await {
+        Some((response, results, _, unrecorded)) => Some((response, false, buttons(user.id, expression, &results), unrecorded)),
+        None => Some((Response::Content("Thou hast given me naught to roll, traveller.".to_string()), true, Vec::new(), Unrecorded::new(user.id, component.guild_id))),
+    }
+}
+
+/// Roll the lone d20 of an advantage or disadvantage button again, returning the line showing both
+/// dice, or why it may not be pressed.
+pub async fn advantage(component: &ComponentInteraction, tally: &Tally) -> Option<Result<String, String>> {
+    let custom_id = &component.data.custom_id;
+    let advantage = custom_id.starts_with("adv:");
+    let mut parts = custom_id.strip_prefix(if advantage { "adv:" } else { "dis:" })?.split(':');
+    let (user_id, value, modifier, truly_random) = (parts.next()?, parts.next()?.parse().ok()?, parts.next()?.parse::<i64>().ok()?, parts.next()? == "1");
+    if !may_press(component, user_id) {
+        return Some(Err("This d20 is not thine to cast again, traveller.".to_string()));
+    }
+
+    let user = &component.user;
+    let result = RollResult::advantage(value, modifier, advantage, truly_random).await;
+    let (label, dice) = if advantage { ("With advantage", "2d20kh1") } else { ("With disadvantage", "2d20kl1") };
+    let expr = if modifier == 0 { format!("[{}]", dice) } else { format!("[{}{:+}]", dice, modifier) };
+    audit::record(user, component.channel_id, &expr, &result);
+    mystats::record(user.id, component.guild_id, &expr, component.message.link(), &result);
+    tally.count(&result);
+    Some(Ok(styled(preferences::of(user.id).style(), || format!("{}: {:#}", label, result))))
+}
+
+/// Buttons of a message once its advantage and disadvantage buttons have been used, which cannot be
+/// pressed anymore.
+pub fn spent(message: &Message) -> Vec<CreateActionRow> {
+    let custom_ids = message.components.iter().flat_map(|row| &row.components).filter_map(|component| match component {
+        ActionRowComponent::Button(Button { data: ButtonKind::NonLink { custom_id, .. }, .. }) => Some(custom_id.as_str()),
+        _ => None,
+    });
+    row(custom_ids.map(|custom_id| match custom_id.split_once(':') {
+        Some(("adv" | "dis", _)) => advantage_button(custom_id).disabled(true),
+        _ => again(custom_id),
+    }).collect())
+}
+
+/// The command, as /roll and as its shorter alias /r.
+pub fn register() -> Vec<CreateCommand> {
+    vec![
+        command("roll", "Roll one or more dice expressions, e.g.: Attack: 1d20+5; Damage: 2d6+3"),
+        command("r", "Roll one or more dice expressions (same as /roll), e.g.: 1d20+5"),
+    ]
+}
+
+fn command(name: &str, description: &str) -> CreateCommand {
+    CreateCommand::new(name).description(description)
+        .add_option(CreateCommandOption::new(CommandOptionType::String, "expression", "Expressions to roll, separated by semicolons, optionally labelled.")
+            .required(true).set_autocomplete(true))
+        .add_option(CreateCommandOption::new(CommandOptionType::String, "label", "Label shown before the result, e.g.: Stealth check")
+            .required(false).max_length(MAX_LABEL as u16))
+        .add_option(CreateCommandOption::new(CommandOptionType::Integer, "dc", "Difficulty class each roll is to meet, e.g.: 15 (a tie is a success).")
+            .required(false))
+        .add_option(CreateCommandOption::new(CommandOptionType::Integer, "seed", "Roll reproducibly from this seed, instead of randomly. Not for actual play!")
+            .required(false).min_int_value(0))
+        .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "compact", "Show only the result of each roll, not every die (default = false).")
+            .required(false))
+        .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "pips", "Show d6s by their faces, e.g.: ⚄ (default = true).")
+            .required(false))
+        .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "embed", "Show each roll as an embed (default = the server's choice).")
+            .required(false))
+        .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "secret", "Send the result only to you, by direct message (default = false).")
+            .required(false))
+        .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "hidden", "Hide the command's response to other users (default = the server's choice, or false).")
+            .required(false))
+}
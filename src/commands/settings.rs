@@ -0,0 +1,357 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::{ChannelId, GuildId, UserId, WebhookId};
+use serenity::model::permissions::Permissions;
+use tracing::{error, info};
+
+use denede_discord_bot::dice::{Delimiters, Syntax};
+use denede_discord_bot::seal;
+use denede_discord_bot::store::{Preferences, Store};
+
+use crate::commands::{table, MISSING_OPTION};
+use crate::config;
+use crate::database;
+
+// Delimiters of every server that has been looked up, so their regexes are built only once:
+static DELIMITERS: LazyLock<Mutex<HashMap<GuildId, Arc<Delimiters>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+static BRACKETS: LazyLock<Arc<Delimiters>> = LazyLock::new(|| Arc::new(Delimiters::default()));
+// Where Discord webhooks may be executed from:
+const WEBHOOK_DOMAINS: &[&str] = &["discord.com", "canary.discord.com", "ptb.discord.com", "discordapp.com"];
+
+pub fn run(options: &[ResolvedOption<'_>], guild_id: Option<GuildId>, channel_id: ChannelId) -> Option<(String, bool)> {
+    let Some(guild_id) = guild_id else {
+        return Some(("Only a server may be given settings, traveller.".to_string(), true));
+    };
+
+    match options.first() {
+        Some(ResolvedOption { name: "delimiter", value: ResolvedValue::SubCommand(options), .. }) => delimiter(options, guild_id),
+        Some(ResolvedOption { name: "strict", value: ResolvedValue::SubCommand(options), .. }) => set_strict(options, guild_id),
+        Some(ResolvedOption { name: "rollvisibility", value: ResolvedValue::SubCommand(options), .. }) => set_roll_visibility(options, guild_id),
+        Some(ResolvedOption { name: "syntax", value: ResolvedValue::SubCommand(options), .. }) => set_syntax(options, guild_id),
+        Some(ResolvedOption { name: "webhook", value: ResolvedValue::SubCommand(options), .. }) => set_webhook(options, guild_id, channel_id),
+        _ => None,
+    }
+}
+
+fn delimiter(options: &[ResolvedOption<'_>], guild_id: GuildId) -> Option<(String, bool)> {
+    let string_option = |name: &str| options.iter().find_map(|option| match option {
+        ResolvedOption { name: option_name, value: ResolvedValue::String(value), .. } if *option_name == name => Some(value.trim()),
+        _ => None,
+    });
+
+    let Some(open) = string_option("open") else {
+        return Some((MISSING_OPTION.to_string(), true));
+    };
+    let close = string_option("close").unwrap_or("");
+    let delimiters = match Delimiters::new(open, close) {
+        Ok(delimiters) => delimiters,
+        Err(why) => return Some((why, true)),
+    };
+
+    let saved = (!delimiters.is_default()).then_some((open, close));
+    if let Err(why) = database::get().set_delimiters(guild_id.get(), saved) {
+        error!("Could not save the delimiters of {guild_id}: {why}");
+        return Some(("Alas, mine quill hath failed me and the setting could not be inscribed.".to_string(), true));
+    }
+
+    let response = format!("Rolls in this realm shall henceforth be written as {}.", delimiters.around("1d20"));
+    DELIMITERS.lock().expect("No delimiters?").insert(guild_id, Arc::new(delimiters));
+    Some((response, true))
+}
+
+/// Delimiters rolls are written within in the server's messages.
+pub fn delimiters(guild_id: Option<GuildId>) -> Arc<Delimiters> {
+    let Some(guild_id) = guild_id else {
+        return BRACKETS.clone();
+    };
+
+    let mut cache = DELIMITERS.lock().expect("No delimiters?");
+    cache.entry(guild_id).or_insert_with(|| {
+        let saved = database::get().delimiters(guild_id.get())
+            .map_err(|why| error!("Could not read the delimiters of {guild_id}: {why}")).ok().flatten()
+            .and_then(|(open, close)| Delimiters::new(&open, &close).map_err(|why| error!("Could not read the delimiters of {guild_id}: {why}")).ok());
+        saved.map(Arc::new).unwrap_or(BRACKETS.clone())
+    }).clone()
+}
+
+fn set_strict(options: &[ResolvedOption<'_>], guild_id: GuildId) -> Option<(String, bool)> {
+    let Some(ResolvedOption { value: ResolvedValue::Boolean(enabled), .. }) = options.first() else {
+        return None;
+    };
+
+    if let Err(why) = database::get().set_strict(guild_id.get(), *enabled) {
+        error!("Could not save the strict setting of {guild_id}: {why}");
+        return Some(("Alas, mine quill hath failed me and the setting could not be inscribed.".to_string(), true));
+    }
+
+    if *enabled {
+        Some(("Ill-formed rolls in this realm shall be pointed out henceforth.".to_string(), true))
+    } else {
+        Some(("Ill-formed rolls in this realm shall be quietly overlooked henceforth.".to_string(), true))
+    }
+}
+
+/// Whether text within the server's delimiters that is no roll is pointed out, rather than skipped.
+pub fn strict(guild_id: Option<GuildId>) -> bool {
+    guild_id.is_some_and(|guild_id| database::get().strict(guild_id.get()).unwrap_or_else(|why| {
+        error!("Could not read the strict setting of {guild_id}: {why}");
+        false
+    }))
+}
+
+fn set_roll_visibility(options: &[ResolvedOption<'_>], guild_id: GuildId) -> Option<(String, bool)> {
+    let Some(ResolvedOption { value: ResolvedValue::String(visibility), .. }) = options.first() else {
+        return None;
+    };
+
+    let hidden = *visibility == "hidden";
+    if let Err(why) = database::get().set_hidden_rolls(guild_id.get(), hidden) {
+        error!("Could not save the roll visibility of {guild_id}: {why}");
+        return Some(("Alas, mine quill hath failed me and the setting could not be inscribed.".to_string(), true));
+    }
+
+    if hidden {
+        Some(("Rolls made with /roll in this realm shall be kept secret henceforth, unless asked otherwise.".to_string(), true))
+    } else {
+        Some(("Rolls made with /roll in this realm shall be seen by all henceforth, unless asked otherwise.".to_string(), true))
+    }
+}
+
+/// Whether /roll hides its response from other users in the server, unless asked otherwise.
+pub fn hidden_rolls(guild_id: Option<GuildId>) -> bool {
+    guild_id.is_some_and(|guild_id| database::get().hidden_rolls(guild_id.get()).unwrap_or_else(|why| {
+        error!("Could not read the roll visibility of {guild_id}: {why}");
+        false
+    }))
+}
+
+fn set_syntax(options: &[ResolvedOption<'_>], guild_id: GuildId) -> Option<(String, bool)> {
+    let Some(ResolvedOption { value: ResolvedValue::String(notation), .. }) = options.first() else {
+        return None;
+    };
+
+    let roll20 = *notation == "roll20";
+    if let Err(why) = database::get().set_roll20(guild_id.get(), roll20) {
+        error!("Could not save the syntax of {guild_id}: {why}");
+        return Some(("Alas, mine quill hath failed me and the setting could not be inscribed.".to_string(), true));
+    }
+
+    if roll20 {
+        Some(("Rolls in this realm shall be read in the tongue of Roll20 henceforth, e.g.: 2d20kh1, 8d6!, 1d20ro<2.".to_string(), true))
+    } else {
+        Some(("Rolls in this realm shall be read in the tongue of MapTool henceforth, as they were of old.".to_string(), true))
+    }
+}
+
+/// Notation rolls are written in within the server, in chat and with /roll.
+pub fn syntax(guild_id: Option<GuildId>) -> Syntax {
+    let roll20 = guild_id.is_some_and(|guild_id| database::get().roll20(guild_id.get()).unwrap_or_else(|why| {
+        error!("Could not read the syntax of {guild_id}: {why}");
+        false
+    }));
+    if roll20 { Syntax::Roll20 } else { Syntax::MapTool }
+}
+
+// The URL of a webhook is never told back, not even in part, since it lets anyone post as it:
+fn set_webhook(options: &[ResolvedOption<'_>], guild_id: GuildId, channel_id: ChannelId) -> Option<(String, bool)> {
+    // The given channel, or else the one the command was used in:
+    let channel_id = options.iter().find_map(|option| match option {
+        ResolvedOption { name: "channel", value: ResolvedValue::Channel(channel), .. } => Some(channel.id),
+        _ => None,
+    }).unwrap_or(channel_id);
+    let url = options.iter().find_map(|option| match option {
+        ResolvedOption { name: "url", value: ResolvedValue::String(url), .. } => Some(url.trim()),
+        _ => None,
+    });
+
+    let sealed = match url {
+        Some(url) if parse_webhook(url).is_none() => return Some(("That is no Discord webhook I could deliver rolls through, traveller.".to_string(), true)),
+        Some(url) => match seal::seal(url) {
+            Some(sealed) => Some(sealed),
+            None => return Some(("I was given no secret to seal webhooks with (DENEDE_WEBHOOK_SECRET), so I shall keep none.".to_string(), true)),
+        },
+        None => None,
+    };
+    if let Err(why) = database::get().set_webhook(guild_id.get(), channel_id.get(), sealed.as_deref()) {
+        error!("Could not save the webhook of channel {channel_id}: {why}");
+        return Some(("Alas, mine quill hath failed me and the setting could not be inscribed.".to_string(), true));
+    }
+
+    if sealed.is_some() {
+        Some((format!("Rolls in the messages of <#{}> shall henceforth be delivered through the given webhook.", channel_id), true))
+    } else {
+        Some((format!("Rolls in the messages of <#{}> shall be delivered by mine own hand once more.", channel_id), true))
+    }
+}
+
+/// Id and token of the webhook rolls in the channel's messages are delivered through, if any.
+pub fn webhook(guild_id: Option<GuildId>, channel_id: ChannelId) -> Option<(WebhookId, String)> {
+    // Direct messages have no webhooks:
+    guild_id?;
+    let sealed = database::get().webhook(channel_id.get())
+        .map_err(|why| error!("Could not read the webhook of channel {channel_id}: {why}")).ok()??;
+    // E.g.: the secret changed since it was sealed:
+    let Some(url) = seal::open(&sealed) else {
+        error!("Could not open the sealed webhook of channel {channel_id}");
+        return None;
+    };
+    parse_webhook(&url)
+}
+
+// Id and token of a webhook from its URL, e.g.: https://discord.com/api/webhooks/<id>/<token>
+fn parse_webhook(url: &str) -> Option<(WebhookId, String)> {
+    let (domain, path) = url.strip_prefix("https://")?.split_once('/')?;
+    if !WEBHOOK_DOMAINS.contains(&domain) {
+        return None;
+    }
+    let (id, token) = path.strip_prefix("api/webhooks/")?.split_once('/')?;
+    let id = id.parse::<u64>().ok().filter(|id| *id != 0)?;
+    if token.is_empty() || !token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+    Some((WebhookId::new(id), token.to_string()))
+}
+
+/// Move the settings kept in files by older versions (one per server, under the delimiters, strict,
+/// hiddenrolls and embeds directories, one per channel under the dicechannels directory, one per
+/// table under the tables directory, and the preferences of every user in preferences.json) into
+/// the database. Imported files and directories
+/// are renamed, not removed, so they are imported only once but can still be recovered.
+pub fn import_legacy(store: &Store) {
+    let data_dir = &config::get().data_dir;
+    import_dir(&data_dir.join("delimiters"), |guild_id, contents| {
+        let (open, close) = contents.split_once('\n').unwrap_or((contents, ""));
+        store.set_delimiters(guild_id, Some((open, close)))
+    });
+    import_dir(&data_dir.join("strict"), |guild_id, _| store.set_strict(guild_id, true));
+    import_dir(&data_dir.join("hiddenrolls"), |guild_id, _| store.set_hidden_rolls(guild_id, true));
+    import_dir(&data_dir.join("embeds"), |guild_id, _| store.set_embeds(guild_id, true));
+    import_nested(&data_dir.join("dicechannels"), |guild_id, channel, _| match channel.parse() {
+        Ok(channel_id) => store.set_channel_disabled(guild_id, channel_id, true),
+        // Not a channel's file, so nothing to import:
+        Err(_) => Ok(()),
+    });
+    import_nested(&data_dir.join("tables"), |guild_id, file, contents| match file.strip_suffix(".txt") {
+        Some(name) => store.set_table(guild_id, name, Some(&table::parse_legacy(contents))),
+        None => Ok(()),
+    });
+
+    let preferences = data_dir.join("preferences.json");
+    if preferences.exists() {
+        let imported = import_file(&preferences, |contents| {
+            let preferences = serde_json::from_str::<HashMap<UserId, Preferences>>(contents).map_err(|why| why.to_string())?;
+            preferences.iter().try_for_each(|(user_id, preferences)| store.set_preferences(user_id.get(), Some(preferences))).map_err(|why| why.to_string())
+        });
+        set_aside(&preferences, !imported);
+    }
+}
+
+// Import every server's file in the directory, if it exists, then set the directory aside:
+fn import_dir(dir: &Path, import: impl Fn(u64, &str) -> rusqlite::Result<()>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut failed = false;
+    for entry in entries.flatten() {
+        let Some(guild_id) = entry.file_name().to_str().and_then(|name| name.parse().ok()) else {
+            continue;
+        };
+        failed |= !import_file(&entry.path(), |contents| import(guild_id, contents).map_err(|why| why.to_string()));
+    }
+    set_aside(dir, failed);
+}
+
+// Import every file in every server's directory within the directory (e.g.: tables/<guild>/<table>.txt),
+// along with its name, if the directory exists, then set the directory aside:
+fn import_nested(dir: &Path, import: impl Fn(u64, &str, &str) -> rusqlite::Result<()>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut failed = false;
+    for entry in entries.flatten() {
+        let Some(guild_id) = entry.file_name().to_str().and_then(|name| name.parse().ok()) else {
+            continue;
+        };
+        let Ok(files) = fs::read_dir(entry.path()) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let Some(name) = file.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            failed |= !import_file(&file.path(), |contents| import(guild_id, &name, contents).map_err(|why| why.to_string()));
+        }
+    }
+    set_aside(dir, failed);
+}
+
+// Import the contents of a file, telling whether it could be:
+fn import_file(path: &Path, import: impl Fn(&str) -> Result<(), String>) -> bool {
+    let imported = fs::read_to_string(path).map_err(|why| why.to_string()).and_then(|contents| import(&contents));
+    if let Err(why) = &imported {
+        error!("Could not import {}: {why}", path.display());
+    }
+    imported.is_ok()
+}
+
+// Rename an imported file or directory, so it is not imported again:
+fn set_aside(path: &Path, failed: bool) {
+    // Whatever could not be imported is left where it was, to be tried again on the next start:
+    if failed {
+        return;
+    }
+    let imported = path.with_extension("imported");
+    match fs::rename(path, &imported) {
+        Ok(()) => info!("Imported {} into the database, and moved it to {}.", path.display(), imported.display()),
+        Err(why) => error!("Could not move {} aside after importing it: {why}", path.display()),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("settings").description("Change the settings of this server.")
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "delimiter", "Change what rolls are written within in chat, e.g.: {{ and }} for {{1d20}}.")
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "open", "What opens a roll (default = [).")
+                .required(true))
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "close", "What closes a roll (default = ]). Leave out to write rolls as e.g.: r!1d20")
+                .required(false)))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "strict", "Choose whether text in chat that looks like a roll but is not one is pointed out.")
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::Boolean, "enabled", "Point out ill-formed rolls (true) or overlook them (false).")
+                .required(true)))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "rollvisibility", "Choose whether /roll shows its response to everyone unless asked otherwise.")
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "visibility", "Who sees the response of /roll by default (default = public).")
+                .required(true)
+                .add_string_choice("public", "public")
+                .add_string_choice("hidden", "hidden")))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "syntax", "Choose the notation rolls are written in, e.g.: Roll20's for expressions copied from its sheets.")
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "notation", "Notation of the rolls (default = maptool).")
+                .required(true)
+                .add_string_choice("maptool", "maptool")
+                .add_string_choice("roll20", "roll20")))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "webhook", "Deliver the rolls in a channel's messages through a webhook, under the roller's name.")
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "url", "URL of the webhook. Leave out to deliver rolls as the bot again.")
+                .required(false))
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::Channel, "channel", "Channel to change (default = this one).")
+                .required(false)))
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+}
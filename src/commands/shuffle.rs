@@ -0,0 +1,75 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::UserId;
+
+use denede_discord_bot::dice::{call_randomorg_sequence, styled};
+use denede_discord_bot::randomorg;
+
+use crate::commands::preferences;
+
+// Arbitrary limit, so the shuffled list stays readable:
+const MAX_ENTRIES: usize = 50;
+
+pub async fn run(options: &[ResolvedOption<'_>], user_id: UserId) -> Option<(String, bool)> {
+    let mut list = "";
+    let mut ephemeral = false;
+    for option in options {
+        match option {
+            ResolvedOption { name: "list", value: ResolvedValue::String(value), .. } => list = value,
+            ResolvedOption { name: "hidden", value: ResolvedValue::Boolean(value), .. } => ephemeral = *value,
+            _ => {},
+        }
+    }
+
+    let entries = list.split(',').map(str::trim).filter(|entry| !entry.is_empty()).collect::<Vec<&str>>();
+    if entries.is_empty() {
+        return Some(("Thou hast given me naught to shuffle, traveller.".to_string(), true));
+    }
+    if entries.len() > MAX_ENTRIES {
+        return Some(("Inquired for overmuch entries. I may only shuffle up to half a hundred!".to_string(), true));
+    }
+
+    let (order, truly_random) = call_randomorg_sequence(entries.len() as i64).await;
+
+    // Shorten overly long entries, so the whole list fits in a single message:
+    let max_len = 1900 / entries.len() - 6;
+    let mut response = String::new();
+    for (position, index) in order.iter().enumerate() {
+        let entry = entries[(index - 1) as usize];
+        if entry.chars().count() > max_len {
+            response.push_str(&format!("{}. {}…\n", position + 1, entry.chars().take(max_len - 1).collect::<String>()));
+        } else {
+            response.push_str(&format!("{}. {}\n", position + 1, entry));
+        }
+    }
+    if let Some(note) = styled(preferences::of(user_id).style(), || randomorg::note(truly_random)) {
+        response.push_str(note);
+    }
+
+    Some((response, ephemeral))
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("shuffle").description("Shuffle a list into a random order, e.g.: for turn order.")
+        .add_option(CreateCommandOption::new(CommandOptionType::String, "list", "Entries to shuffle, separated by commas.")
+            .required(true))
+        .add_option(CreateCommandOption::new(CommandOptionType::Boolean, "hidden", "Hide the command's response to other users (default = false).")
+            .required(false))
+}
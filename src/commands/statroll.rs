@@ -0,0 +1,60 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use denede_discord_bot::dice::{Dice, DieKind};
+
+// Point-buy cost of each ability score from 3 to 18 (extending the usual 8 to 15 table):
+const POINT_BUY_COSTS: [i64; 16] = [-9, -6, -4, -2, -1, 0, 1, 2, 3, 4, 5, 7, 9, 12, 15, 19];
+
+pub async fn run(options: &[ResolvedOption<'_>]) -> Option<(String, bool)> {
+    let ephemeral: bool;
+    if let Some(ResolvedOption {
+        value: ResolvedValue::Boolean(is_ephemeral), ..
+    }) = options.first() {
+        ephemeral = *is_ephemeral;
+    } else {
+        ephemeral = false;
+    }
+
+    // Six ability scores, each of them 4d6 dropping the lowest:
+    let dice = Dice::new(4, 6, DieKind::Drop(1)).ok().expect("No ability dice?");
+    let mut scores = Vec::new();
+    for _ in 0..6 {
+        scores.push(dice.roll().await);
+    }
+    scores.sort_by_key(|score| -score.total());
+
+    let totals = scores.iter().map(|score| score.total()).collect::<Vec<i64>>();
+    let mut response = format!("Ability scores: {}\n", totals.iter().map(|total| total.to_string()).collect::<Vec<String>>().join(", "));
+    for score in &scores {
+        response.push_str(&format!("{}\n", score));
+    }
+    let point_buy = totals.iter().map(|&total| POINT_BUY_COSTS[(total - 3) as usize]).sum::<i64>();
+    response.push_str(&format!("Total: {} — point-buy equivalent: {}", totals.iter().sum::<i64>(), point_buy));
+
+    Some((response, ephemeral))
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("statroll").description("Roll a set of six ability scores (4d6, dropping the lowest).").add_option(
+        CreateCommandOption::new(CommandOptionType::Boolean, "hidden", "Hide the command's response to other users (default = false).")
+            .required(false),
+    )
+}
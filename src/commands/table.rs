@@ -0,0 +1,170 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::LazyLock;
+use regex::Regex;
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::model::id::{GuildId, UserId};
+use tracing::error;
+
+use denede_discord_bot::dice::{call_randomorg, styled};
+use denede_discord_bot::randomorg;
+
+use crate::commands::{preferences, MISSING_OPTION};
+use crate::database;
+
+pub async fn run(options: &[ResolvedOption<'_>], user_id: UserId, guild_id: Option<GuildId>) -> Option<(String, bool)> {
+    let Some(guild_id) = guild_id else {
+        return Some(("Tables can only be kept within a server, traveller.".to_string(), true));
+    };
+
+    let Some(ResolvedOption { name: subcommand, value: ResolvedValue::SubCommand(options), .. }) = options.first() else {
+        return None;
+    };
+    let string_option = |name: &str| options.iter().find_map(|option| match option {
+        ResolvedOption { name: option_name, value: ResolvedValue::String(value), .. } if *option_name == name => Some(*value),
+        _ => None,
+    });
+
+    // Only list and roll responses are meant to be seen by others:
+    match *subcommand {
+        "add" => match (string_option("name"), string_option("entries")) {
+            (Some(name), Some(entries)) => Some((add(guild_id, name, entries), true)),
+            _ => Some((MISSING_OPTION.to_string(), true)),
+        },
+        "roll" => match string_option("name") {
+            Some(name) => Some((roll(user_id, guild_id, name).await, false)),
+            None => Some((MISSING_OPTION.to_string(), true)),
+        },
+        "list" => Some((list(guild_id), false)),
+        "remove" => match string_option("name") {
+            Some(name) => Some((remove(guild_id, name), true)),
+            None => Some((MISSING_OPTION.to_string(), true)),
+        },
+        _ => None,
+    }
+}
+
+// Table names are typed in commands, so keep them simple:
+fn valid_name(name: &str) -> bool {
+    static TABLE_NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[\w-]{1,32}$").expect("No table name regex?"));
+    TABLE_NAME.is_match(name)
+}
+
+// Weighted entries of a table, e.g.: "3x Goblins; Orc; 2x Nothing"
+fn parse_entries(entries: &str) -> Vec<(i64, String)> {
+    static WEIGHTED: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?<weight>\d{1,6})x (?<text>.+)$").expect("No weighted entry regex?"));
+    entries.split(';').map(str::trim).filter(|entry| !entry.is_empty()).map(|entry| {
+        match WEIGHTED.captures(entry) {
+            Some(captures) => (captures["weight"].parse::<i64>().expect("No weight?"), captures["text"].to_owned()),
+            None => (1, entry.to_owned()),
+        }
+    }).collect()
+}
+
+/// Weighted entries of a table as kept in files by older versions, one per line, e.g.: "3\tGoblins"
+pub fn parse_legacy(contents: &str) -> Vec<(i64, String)> {
+    contents.lines().filter_map(|line| {
+        let (weight, text) = line.split_once('\t')?;
+        Some((weight.parse::<i64>().ok()?, text.to_owned()))
+    }).collect()
+}
+
+fn load(guild_id: GuildId, name: &str) -> Option<Vec<(i64, String)>> {
+    database::get().table(guild_id.get(), name).map_err(|why| error!("Could not read table {name} of {guild_id}: {why}")).ok()?
+}
+
+fn add(guild_id: GuildId, name: &str, entries: &str) -> String {
+    if !valid_name(name) {
+        return "A table's name may only bear letters, numerals, dashes and underscores, and no more than two score minus eight of them.".to_string();
+    }
+    let entries = parse_entries(entries);
+    if entries.is_empty() || entries.iter().any(|(weight, _)| *weight == 0) {
+        return "A table needs at least one entry, and every entry must have some chance of being chosen.".to_string();
+    }
+
+    if let Err(why) = database::get().set_table(guild_id.get(), name, Some(&entries)) {
+        error!("Could not save table {name} of {guild_id}: {why}");
+        return "Alas, mine quill hath failed me and the table could not be inscribed.".to_string();
+    }
+    format!("Table \"{}\" inscribed, with {} entries.", name, entries.len())
+}
+
+async fn roll(user_id: UserId, guild_id: GuildId, name: &str) -> String {
+    let Some(entries) = load(guild_id, name) else {
+        return format!("I know of no table named \"{}\" in this realm.", name);
+    };
+
+    // Tables are only written with positive weights, but those of older files may have been tampered with:
+    let total_weight = entries.iter().map(|(weight, _)| weight).sum::<i64>();
+    if total_weight < 1 || entries.iter().any(|(weight, _)| *weight < 0) {
+        return format!("The table \"{}\" is marred beyond reading, traveller. Prithee inscribe it anew.", name);
+    }
+    let (roll, truly_random) = call_randomorg(1, total_weight).await;
+
+    // Walk the entries until the rolled value falls within one's weight:
+    let mut remaining = roll[0];
+    let Some((_, chosen)) = entries.iter().find(|(weight, _)| {
+        remaining -= weight;
+        remaining <= 0
+    }) else {
+        return format!("The table \"{}\" is marred beyond reading, traveller. Prithee inscribe it anew.", name);
+    };
+
+    let mut response = format!("{} (1d{}) → {}", roll[0], total_weight, chosen);
+    if let Some(note) = styled(preferences::of(user_id).style(), || randomorg::note(truly_random)) {
+        response.push_str(&format!(" {}", note));
+    }
+    response
+}
+
+fn list(guild_id: GuildId) -> String {
+    let names = database::get().tables(guild_id.get()).map_err(|why| error!("Could not read the tables of {guild_id}: {why}")).unwrap_or_default();
+
+    if names.is_empty() {
+        "No tables have been inscribed in this realm yet.".to_string()
+    } else {
+        format!("Tables of this realm: {}", names.join(", "))
+    }
+}
+
+fn remove(guild_id: GuildId, name: &str) -> String {
+    if !valid_name(name) || load(guild_id, name).is_none() {
+        return format!("I know of no table named \"{}\" in this realm.", name);
+    }
+    if let Err(why) = database::get().set_table(guild_id.get(), name, None) {
+        error!("Could not remove table {name} of {guild_id}: {why}");
+        return "Alas, mine quill hath failed me and the table could not be struck out.".to_string();
+    }
+    format!("Table \"{}\" struck from the records.", name)
+}
+
+pub fn register() -> CreateCommand {
+    let name = CreateCommandOption::new(CommandOptionType::String, "name", "Name of the table.").required(true);
+
+    CreateCommand::new("table").description("Keep tables of this server and roll on them.")
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Add (or replace) a table.")
+            .add_sub_option(name.clone())
+            .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "entries", "Entries separated by \";\". Prefix one with e.g. \"3x \" to make it thrice as likely.")
+                .required(true)))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "roll", "Roll on a table.")
+            .add_sub_option(name.clone()))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "list", "List the tables of this server."))
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Remove a table.")
+            .add_sub_option(name))
+}
@@ -0,0 +1,47 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::time::Duration;
+
+use serenity::builder::CreateCommand;
+use serenity::model::application::ResolvedOption;
+
+use crate::tally::Tally;
+
+pub fn run(_options: &[ResolvedOption], tally: &Tally) -> Option<(String, bool)> {
+    let uptime = tally.uptime().map_or("a moment".to_string(), since);
+    let response = format!(
+        "I have been awake for {}, watching over {} {}. Since then I have cast {} {}, {} of them with mine own hands rather than RANDOM.ORG's.",
+        uptime, tally.guilds(), if tally.guilds() == 1 { "server" } else { "servers" }, tally.rolls(), if tally.rolls() == 1 { "roll" } else { "rolls" }, tally.pseudo_random(),
+    );
+    Some((response, true))
+}
+
+// Compact form of a duration, e.g.: "3d 4h 5m"
+fn since(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h {}m", days, hours, minutes),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("uptime").description("Tell how long the bot has been awake, and how many rolls it has cast since.")
+}
@@ -0,0 +1,55 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use denede_discord_bot::verify;
+
+pub fn run(options: &[ResolvedOption]) -> Option<(String, bool)> {
+    let mut code = "";
+    let mut expression = "";
+    let mut results = "";
+    for option in options {
+        match option {
+            ResolvedOption { name: "code", value: ResolvedValue::String(value), .. } => code = value,
+            ResolvedOption { name: "expression", value: ResolvedValue::String(value), .. } => expression = value,
+            ResolvedOption { name: "results", value: ResolvedValue::String(value), .. } => results = value,
+            _ => {},
+        }
+    }
+
+    if !verify::enabled() {
+        return Some(("I set no seal upon my rolls, traveller, so none can be verified.".to_string(), true));
+    }
+    let response = match verify::check(code, expression, results) {
+        Some(true) => format!("Verily, I rolled {} for {} myself. This roll is true!", results, expression),
+        Some(false) => format!("Beware, traveller! I never rolled {} for {} with that seal.", results, expression),
+        None => "That seal is unknown to me, traveller. It should look like this: 6710a3f2-9c1e77ab".to_string(),
+    };
+    Some((response, false))
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("verify").description("Check that a roll was really made by Denedé, with its verification code.")
+        .add_option(CreateCommandOption::new(CommandOptionType::String, "code", "Verification code shown with the roll.")
+            .required(true))
+        .add_option(CreateCommandOption::new(CommandOptionType::String, "expression", "Expression that was rolled, e.g.: 1d20+5")
+            .required(true))
+        .add_option(CreateCommandOption::new(CommandOptionType::String, "results", "Results the roll is claimed to have shown, every die and the total, e.g.: 4, 5 + 3 = 12")
+            .required(true))
+}
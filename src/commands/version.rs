@@ -0,0 +1,43 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+pub fn run(options: &[ResolvedOption]) -> Option<(String, bool)> {
+    let mut ephemeral = true;
+    if let Some(ResolvedOption { name: "hidden", value: ResolvedValue::Boolean(hidden), .. }) = options.first() {
+        ephemeral = *hidden;
+    }
+
+    let changes = match env!("DENEDE_GIT_DIRTY") {
+        "yes" => " (with uncommitted changes)",
+        "no" => "",
+        _ => " (uncommitted changes unknown)",
+    };
+    Some((format!(
+        "Denedé v{}\nCommit: {}{}\nBuilt: {}\nCompiler: {}",
+        env!("CARGO_PKG_VERSION"), env!("DENEDE_GIT_COMMIT"), changes, env!("DENEDE_BUILD_DATE"), env!("DENEDE_RUSTC_VERSION"),
+    ), ephemeral))
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("version").description("Show the version of this bot and how it was built.").add_option(
+        CreateCommandOption::new(CommandOptionType::Boolean, "hidden", "Hide the command's response to other users (default = true).")
+            .required(false),
+    )
+}
@@ -0,0 +1,344 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Settings of the bot, from an optional TOML file (DENEDE_CONFIG, or ./denede.toml) and from
+// environment variables, which take precedence. The library reads its own settings (dice limits,
+// RNG options, etc.) from the environment, so those found only in the file are handed to it there.
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use denede_discord_bot::dice::{self, HIGHEST_MAX_DICE, HIGHEST_MAX_SIDES};
+
+// File read unless DENEDE_CONFIG names another:
+const DEFAULT_FILE: &str = "denede.toml";
+// What is logged unless RUST_LOG or the file say otherwise:
+const DEFAULT_LOG_FILTER: &str = "warn,denede_discord_bot=info";
+// Where data is kept unless DENEDE_DATA_DIR or the file say otherwise:
+const DEFAULT_DATA_DIR: &str = "data";
+
+/// Settings the bot itself needs while running.
+pub struct Config {
+    pub token: String,
+    // Where per-server settings, preferences and tables are kept:
+    pub data_dir: PathBuf,
+    pub log_filter: String,
+    // First status line shown, if not the default one:
+    pub status: Option<String>,
+    // Whether newly joined servers are greeted:
+    pub welcome: bool,
+    // Whether the channel is told that someone rolled secretly in chat:
+    pub secret_note: bool,
+    pub invite_permissions: Option<u64>,
+    // Shards to connect with, if not as many as Discord recommends:
+    pub shards: Option<u32>,
+    // Port the metrics are served on, if they are at all:
+    pub metrics_port: Option<u16>,
+    // Port the roll API is served on, if it is at all, and the tokens it may be used with:
+    pub api_port: Option<u16>,
+    pub api_tokens: Vec<String>,
+    // File the rolls are recorded to, if any:
+    pub audit_log: Option<PathBuf>,
+}
+
+// Layout of the file, every setting being optional:
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct File {
+    token: Option<String>,
+    data_dir: Option<String>,
+    status: Option<String>,
+    welcome: Option<bool>,
+    secret_note: Option<bool>,
+    invite_permissions: Option<u64>,
+    shards: Option<u32>,
+    metrics_port: Option<u16>,
+    api_port: Option<u16>,
+    api_tokens: Option<Vec<String>>,
+    audit_log: Option<String>,
+    dice: DiceFile,
+    rng: RngFile,
+    logging: LoggingFile,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DiceFile {
+    max_amount: Option<i64>,
+    max_sides: Option<i64>,
+    compact: Option<usize>,
+    highlight_crits: Option<bool>,
+    pips: Option<bool>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RngFile {
+    offline: Option<bool>,
+    pseudo_random_note: Option<bool>,
+    pool_size: Option<usize>,
+    randomorg_timeout_ms: Option<u64>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LoggingFile {
+    filter: Option<String>,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Settings loaded on startup.
+pub fn get() -> &'static Config {
+    CONFIG.get().expect("No config?")
+}
+
+// Where a setting was taken from:
+#[derive(Clone, Copy)]
+enum Origin {
+    Environment,
+    File,
+    Default,
+}
+
+// A setting as reported: its environment variable, what it was set to, and where that came from:
+struct Line {
+    name: &'static str,
+    shown: String,
+    origin: Origin,
+}
+
+/// What was made of every setting on startup, shown as a table, along with whatever keeps the bot
+/// from starting.
+pub struct Report {
+    path: PathBuf,
+    lines: Vec<Line>,
+    fatal: Vec<String>,
+}
+
+// Yes/no setting, written as in the library's environment variables: 1, true, yes, 0, false or no.
+struct Flag(bool);
+
+impl FromStr for Flag {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Flag, ()> {
+        match value.to_lowercase().as_str() {
+            "1" | "true" | "yes" => Ok(Flag(true)),
+            "0" | "false" | "no" => Ok(Flag(false)),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Flag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0 { "yes" } else { "no" })
+    }
+}
+
+/// Read, check and keep the settings. Every mistake in them is reported (which setting, and why),
+/// not only the first one.
+pub fn load() -> Result<(&'static Config, Report), Report> {
+    let (path, file) = match read_file() {
+        Ok(read) => read,
+        Err(why) => return Err(Report { path: PathBuf::from(DEFAULT_FILE), lines: Vec::new(), fatal: vec![why] }),
+    };
+    let mut report = Report { path, lines: Vec::new(), fatal: Vec::new() };
+
+    // Settings of the library, checked here so mistakes in the file are reported as such:
+    let dice = &file.dice;
+    if let Some(amount) = dice.max_amount.filter(|amount| !(1..=HIGHEST_MAX_DICE).contains(amount)) {
+        report.fatal(format!("{} must be a whole number from 1 to {}, not {}.", report.key("dice.max_amount"), HIGHEST_MAX_DICE, amount));
+    }
+    if let Some(sides) = dice.max_sides.filter(|sides| !(1..=HIGHEST_MAX_SIDES).contains(sides)) {
+        report.fatal(format!("{} must be a whole number from 1 to {}, not {}.", report.key("dice.max_sides"), HIGHEST_MAX_SIDES, sides));
+    }
+    report.export("MAX_DICE_AMOUNT", dice.max_amount);
+    report.export("MAX_DICE_SIDES", dice.max_sides);
+    report.export("DENEDE_COMPACT_DICE", dice.compact);
+    report.export("DENEDE_HIGHLIGHT_CRITS", dice.highlight_crits.map(Flag));
+    report.export("DENEDE_DICE_PIPS", dice.pips.map(Flag));
+    report.export("DENEDE_OFFLINE_RNG", file.rng.offline.map(Flag));
+    report.export("DENEDE_PSEUDO_RANDOM_NOTE", file.rng.pseudo_random_note.map(Flag));
+    report.export("DENEDE_POOL_SIZE", file.rng.pool_size);
+    report.export("RANDOMORG_TIMEOUT_MS", file.rng.randomorg_timeout_ms);
+    // Only set in the environment:
+    report.export::<u32>("DENEDE_RATE_BURST", None);
+    report.export::<u32>("DENEDE_RATE_PER_MINUTE", None);
+    report.export::<String>("RANDOMORG_URL", None);
+    report.secret("DENEDE_VERIFY_SECRET", None);
+    report.secret("DENEDE_WEBHOOK_SECRET", None);
+    if let Err(why) = dice::check_limits() {
+        report.fatal(why);
+    }
+
+    let token = report.secret("DISCORD_TOKEN", file.token);
+    if token.is_none() {
+        report.fatal(format!("No Discord token was given: set {} or the DISCORD_TOKEN environment variable.", report.key("token")));
+    }
+    let data_dir = report.setting("DENEDE_DATA_DIR", file.data_dir, DEFAULT_DATA_DIR).unwrap_or(DEFAULT_DATA_DIR.to_string());
+    let log_filter = report.setting("RUST_LOG", file.logging.filter, DEFAULT_LOG_FILTER).unwrap_or(DEFAULT_LOG_FILTER.to_string());
+    let status = report.setting("DENEDE_STATUS", file.status, "(the usual one)").filter(|status| !status.trim().is_empty());
+    let welcome = match env::var("DENEDE_NO_WELCOME") {
+        Ok(off) => report.line("DENEDE_NO_WELCOME", !(off.is_empty() || off == "0"), Origin::Environment),
+        Err(_) if file.welcome.is_some() => report.line("DENEDE_NO_WELCOME", file.welcome == Some(false), Origin::File),
+        Err(_) => report.line("DENEDE_NO_WELCOME", false, Origin::Default),
+    };
+    let secret_note = match env::var("DENEDE_SECRET_NOTE") {
+        Ok(note) => report.line("DENEDE_SECRET_NOTE", note != "0", Origin::Environment),
+        Err(_) if file.secret_note.is_some() => report.line("DENEDE_SECRET_NOTE", file.secret_note == Some(true), Origin::File),
+        Err(_) => report.line("DENEDE_SECRET_NOTE", true, Origin::Default),
+    };
+    let invite_permissions = report.setting("DENEDE_INVITE_PERMISSIONS", file.invite_permissions, "(the usual ones)");
+    let shards = report.setting("DENEDE_SHARDS", file.shards, "(as Discord recommends)");
+    if shards == Some(0) {
+        report.fatal(format!("At least one shard is needed: check {} or the DENEDE_SHARDS environment variable.", report.key("shards")));
+    }
+    let metrics_port = report.setting("DENEDE_METRICS_PORT", file.metrics_port, "(not served)");
+    let api_port = report.setting("DENEDE_API_PORT", file.api_port, "(not served)");
+    // Comma separated in the environment:
+    let api_tokens: Vec<String> = match env::var("DENEDE_API_TOKENS") {
+        Ok(tokens) => tokens.split(',').map(|token| token.trim().to_string()).collect(),
+        Err(_) => file.api_tokens.clone().unwrap_or_default(),
+    };
+    let api_tokens: Vec<String> = api_tokens.into_iter().filter(|token| !token.trim().is_empty()).collect();
+    let origin = match (env::var_os("DENEDE_API_TOKENS"), &file.api_tokens) {
+        (Some(_), _) => Origin::Environment,
+        (None, Some(_)) => Origin::File,
+        (None, None) => Origin::Default,
+    };
+    report.line("DENEDE_API_TOKENS", format!("({} given)", api_tokens.len()), origin);
+    if api_port.is_some() && api_tokens.is_empty() {
+        report.fatal(format!("The roll API needs at least one token: set {} or the DENEDE_API_TOKENS environment variable.", report.key("api_tokens")));
+    }
+    let audit_log = report.setting("DENEDE_AUDIT_LOG", file.audit_log, "(not kept)").filter(|path| !path.is_empty()).map(PathBuf::from);
+
+    let Some(token) = token.filter(|_| report.fatal.is_empty()) else {
+        return Err(report);
+    };
+    let config = Config {
+        token,
+        data_dir: PathBuf::from(data_dir),
+        log_filter,
+        status,
+        welcome: !welcome,
+        secret_note,
+        invite_permissions,
+        shards,
+        metrics_port,
+        api_port,
+        api_tokens,
+        audit_log,
+    };
+    Ok((CONFIG.get_or_init(|| config), report))
+}
+
+impl Report {
+    // Where a key of the file is set, for mistakes in it:
+    fn key(&self, name: &str) -> String {
+        format!("`{}` in {}", name, self.path.display())
+    }
+
+    fn fatal(&mut self, why: String) {
+        self.fatal.push(why);
+    }
+
+    // Report a setting, handing back its value:
+    fn line<T: fmt::Display>(&mut self, name: &'static str, value: T, origin: Origin) -> T {
+        self.lines.push(Line { name, shown: value.to_string(), origin });
+        value
+    }
+
+    // Value of a setting: that of its environment variable if set, or else that of the file. A
+    // mistaken value is reported, and the setting is left as if it were not set:
+    fn setting<T: FromStr + fmt::Display>(&mut self, name: &'static str, file: Option<T>, default: &str) -> Option<T> {
+        let (value, origin) = match env::var(name) {
+            Ok(value) => match value.trim().parse() {
+                Ok(parsed) => (Some(parsed), Origin::Environment),
+                Err(_) => {
+                    self.fatal(format!("The {} environment variable is wrong: \"{}\".", name, value));
+                    self.lines.push(Line { name, shown: format!("{value} (wrong)"), origin: Origin::Environment });
+                    return None;
+                },
+            },
+            Err(_) if file.is_some() => (file, Origin::File),
+            Err(_) => (None, Origin::Default),
+        };
+        let shown = value.as_ref().map_or(default.to_string(), T::to_string);
+        self.lines.push(Line { name, shown, origin });
+        value
+    }
+
+    // Same as a setting, but only telling whether it was given, never what it is:
+    fn secret(&mut self, name: &'static str, file: Option<String>) -> Option<String> {
+        let secret = self.setting(name, file, "").filter(|secret| !secret.trim().is_empty());
+        if let Some(line) = self.lines.last_mut() {
+            line.shown = if secret.is_some() { "(given)" } else { "(none)" }.to_string();
+        }
+        secret
+    }
+
+    // Hand a setting of the file to the library, unless its environment variable already sets it:
+    fn export<T: FromStr + fmt::Display>(&mut self, name: &'static str, file: Option<T>) {
+        if let Some(value) = self.setting(name, file, "(built in)") {
+            if env::var_os(name).is_none() {
+                env::set_var(name, value.to_string());
+            }
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.lines.iter().map(|line| line.name.len()).max().unwrap_or(0).max("SETTING".len());
+        let shown_width = self.lines.iter().map(|line| line.shown.chars().count()).max().unwrap_or(0).max("VALUE".len());
+        writeln!(f, "  {:width$}  {:shown_width$}  FROM", "SETTING", "VALUE")?;
+        for line in &self.lines {
+            let origin = match line.origin {
+                Origin::Environment => "environment".to_string(),
+                Origin::File => self.path.display().to_string(),
+                Origin::Default => "default".to_string(),
+            };
+            writeln!(f, "  {:width$}  {:shown_width$}  {}", line.name, line.shown, origin)?;
+        }
+        for why in &self.fatal {
+            writeln!(f, "  FATAL: {why}")?;
+        }
+        Ok(())
+    }
+}
+
+// Path and contents of the file, if there is one. Only a file that was asked for must exist:
+fn read_file() -> Result<(PathBuf, File), String> {
+    let (path, required) = match env::var("DENEDE_CONFIG") {
+        Ok(path) => (PathBuf::from(path), true),
+        Err(_) => (PathBuf::from(DEFAULT_FILE), false),
+    };
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) if !required => return Ok((path, File::default())),
+        Err(why) => return Err(format!("Could not read the configuration file {} (from DENEDE_CONFIG): {}", path.display(), why)),
+    };
+    let file = toml::from_str(&contents).map_err(|why| format!("The configuration file {} is wrong: {}", path.display(), why))?;
+    Ok((path, file))
+}
@@ -0,0 +1,46 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Database the bot keeps its state in, opened on startup. Like the configuration, it is kept in a
+// static rather than in the client's data, as the functions reading it mostly run without a Context.
+use std::fs;
+use std::sync::OnceLock;
+
+use denede_discord_bot::store::Store;
+
+use crate::commands::settings;
+use crate::config;
+
+// File the database is kept in, under the data directory:
+const FILE: &str = "denede.sqlite3";
+
+static DATABASE: OnceLock<Store> = OnceLock::new();
+
+/// Database opened on startup.
+pub fn get() -> &'static Store {
+    DATABASE.get().expect("No database?")
+}
+
+/// Open the database, bring it up to date, and move into it whatever was kept in older files.
+pub fn open() -> Result<&'static Store, String> {
+    let data_dir = &config::get().data_dir;
+    fs::create_dir_all(data_dir).map_err(|why| format!("Could not create the data directory {}: {}", data_dir.display(), why))?;
+    let path = data_dir.join(FILE);
+    let store = Store::open(&path).map_err(|why| format!("Could not open the database {}: {}", path.display(), why))?;
+    settings::import_legacy(&store);
+    Ok(DATABASE.get_or_init(|| store))
+}
@@ -0,0 +1,33 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::future::Future;
+use std::time::Duration;
+
+/// Wait for `work`, running `on_slow` first if it has not finished within `patience`, e.g.: to defer
+/// the response to an interaction before Discord gives up on it. Returns what `work` yields, and
+/// whether it was slow.
+pub async fn patiently<T>(work: impl Future<Output = T>, patience: Duration, on_slow: impl Future<Output = ()>) -> (T, bool) {
+    tokio::pin!(work);
+    match tokio::time::timeout(patience, &mut work).await {
+        Ok(output) => (output, false),
+        Err(_) => {
+            on_slow.await;
+            (work.await, true)
+        },
+    }
+}
@@ -0,0 +1,3276 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{LazyLock, OnceLock};
+use std::task::{Context, Poll, Waker};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::lexer::{self, Kind, Span, Token};
+use crate::{metrics, pool, randomorg};
+
+// Operations that can follow the dice size in a roll, along with the aliases Foundry VTT spells
+// them as, in the order they are tried, with the digits they take. Per-die clamps (min3, max4) come
+// before matches (m, m3), so they are never taken for one:
+const DICE_OPS: [(&str, Digits); 17] = [
+    ("km", Digits::Required), ("kh", Digits::Optional), ("kl", Digits::Optional), ("k", Digits::Required),
+    ("dh", Digits::Required), ("d", Digits::Required), ("min", Digits::Required), ("max", Digits::Required),
+    ("m", Digits::Optional), ("x", Digits::None), ("e", Digits::None), ("h", Digits::None), ("b", Digits::None),
+    ("r<", Digits::Required), ("r", Digits::One), ("sr", Digits::None), ("wod", Digits::None),
+];
+
+// Arbitrary limits, so only reasonable amounts of numbers of reasonable size are returned:
+// Default limits on the amount of dice rolled at once and on their sides, which can be changed
+// through the MAX_DICE_AMOUNT and MAX_DICE_SIDES environment variables:
+const DEFAULT_MAX_DICE: i64 = 20;
+const DEFAULT_MAX_SIDES: i64 = 1_000;
+/// Highest limits that may be configured, within what RANDOM.ORG can generate.
+pub const HIGHEST_MAX_DICE: i64 = 10_000;
+pub const HIGHEST_MAX_SIDES: i64 = 1_000_000_000;
+// Dice whose faces show symbols instead of numbers, by the letter that identifies them, e.g.: [3dH]
+const SYMBOLIC_DICE: [(char, &[&str]); 1] = [
+    ('H', &["Blank", "Blank", "Hit", "Hit", "Hit", "Crit"]),
+];
+
+// Coins are two-sided symbolic dice, e.g.: [coin] [3coins]
+const COIN: &[&str] = &["Heads", "Tails"];
+// Flipping coins is cheap, so more of them are allowed than dice:
+const MAX_COINS: i64 = 100;
+
+// Averages of rolls that cannot be computed exactly are estimated from this many local rolls, always
+// starting from the same seed so the same expression always yields the same estimate:
+const AVERAGE_ITERATIONS: i64 = 10_000;
+const AVERAGE_SEED: u64 = 0xd20;
+// Distributions of rolls that cannot be reckoned exactly are likewise estimated from this many local rolls:
+const DISTRIBUTION_ITERATIONS: u64 = 100_000;
+// Most dice rolled, or chances added up, to find the distribution of a roll, so none can take forever:
+const DISTRIBUTION_BUDGET: u64 = 10_000_000;
+
+/// Maximum amount of times a single die may explode, so a lucky streak cannot go on forever.
+pub const MAX_EXPLOSIONS: usize = 100;
+const EXPLOSIONS_CAPPED: &str = "(the dice were stopped after a hundred explosions, lest they burn forever)";
+
+// Natural 20s and 1s of a d20, which are made to stand out:
+const NATURAL_20: &str = "**20** 🎉";
+const NATURAL_1: &str = "**1** 💀";
+// Faces of a d6, shown instead of its numbers:
+const PIPS: [&str; 6] = ["⚀", "⚁", "⚂", "⚃", "⚄", "⚅"];
+// Rolls with more dice than this in a single group only show their result, unless the
+// DENEDE_COMPACT_DICE environment variable says otherwise:
+const DEFAULT_COMPACT_DICE: usize = 20;
+
+// Reasons for which a roll cannot be (sensibly) made, each with the bytes of the expression it is
+// blamed on (empty if it is blamed on no part of it in particular):
+#[derive(Serialize)]
+pub enum DiceError {
+    // A number too long to even be parsed:
+    NumberTooLarge(Span),
+    TooManyDice(Span),
+    TooManySides(Span),
+    BonusTooLarge(Span),
+    // The argument of the operation does not make sense for the amount of dice:
+    KeepTooMany(Span),
+    MatchTooMany(Span),
+    // Fewer than two of a kind are no match at all, e.g.: [10d6m0]
+    MatchTooFew(Span),
+    // Rolls of d1s, d0s or 0 dice, with their obvious result:
+    Trivial(i64, Span),
+    // Trivial rolls with absurdly large numbers:
+    Jest(Span),
+    UnknownFunction(String, Span),
+    DivisionByZero(Span),
+    TooManyCoins(Span),
+    // Symbols cannot be added, compared, etc.:
+    SymbolicArithmetic(Span),
+    // A roll compared more than once, e.g.: [1d20+7 vs 15 vs 10]
+    SeveralComparisons(Span),
+    // Average or bound of what is no roll, but an average or a bound itself, e.g.: [avg avg 1d6] [max avg 1d6]
+    NotRolled(Span),
+    // World of Darkness pools need ten-sided dice:
+    AgainNotD10(Span),
+    // A result that could not be represented, e.g.: a sum of many huge numbers:
+    ResultTooLarge(Span),
+    // A distribution that would take too long to find:
+    TooComplex(Span),
+    // Text that is no roll at all, reported only by servers that ask so:
+    IllFormed(Span),
+    // Part of a roll in Roll20's notation that has no counterpart here, e.g.: "cs>19" in [1d20cs>19]
+    Unsupported(String, Span),
+    // Operation of MapTool that is known, but not rolled here, e.g.: "o" in [5d10o]
+    NotSupported(String, Span),
+    // Variable of a roll made by someone without a character, e.g.: "$dex" in [1d20 + $dex]
+    NoCharacter(String, Span),
+    // Variable their character has no value for, e.g.: "$dexx" in [1d20 + $dexx]
+    UnknownVariable(String, Span),
+    // Errors in more than one part of a roll, e.g.: [max(21d6, 1d1001)], with the part each comes from:
+    Several(Vec<(String, DiceError)>),
+}
+
+impl DiceError {
+    /// Flavor text of the error, pointing out the expression that caused it, and the part of it to
+    /// blame if there is one, e.g.: "…in `[21d6]`, at `21`".
+    pub fn quoting(&self, expr: &str) -> String {
+        match self {
+            // Each of the errors points out its own part:
+            DiceError::Several(errors) => {
+                let mut text = String::from(SEVERAL_ERRORS);
+                for (part, why) in errors {
+                    text.push_str(&format!("\n{} …in {}{}", why, quote(part), pointing(why.span(), expr, part)));
+                }
+                text
+            },
+            _ => format!("{} …in {}{}", self, quote(expr), pointing(self.span(), expr, expr)),
+        }
+    }
+
+    /// Bytes of the expression the error is blamed on, if any; none for errors found in several parts.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            DiceError::NumberTooLarge(span) | DiceError::TooManyDice(span) | DiceError::TooManySides(span) | DiceError::BonusTooLarge(span)
+            | DiceError::KeepTooMany(span) | DiceError::MatchTooMany(span) | DiceError::MatchTooFew(span) | DiceError::Trivial(_, span)
+            | DiceError::Jest(span) | DiceError::UnknownFunction(_, span) | DiceError::DivisionByZero(span) | DiceError::TooManyCoins(span)
+            | DiceError::SymbolicArithmetic(span) | DiceError::SeveralComparisons(span) | DiceError::NotRolled(span) | DiceError::AgainNotD10(span)
+            | DiceError::ResultTooLarge(span) | DiceError::TooComplex(span) | DiceError::IllFormed(span) | DiceError::Unsupported(_, span)
+            | DiceError::NotSupported(_, span) | DiceError::NoCharacter(_, span) | DiceError::UnknownVariable(_, span) => Some(*span),
+            DiceError::Several(_) => None,
+        }
+    }
+
+    // A single error as is, or several of them together:
+    fn combine(mut errors: Vec<(String, DiceError)>) -> DiceError {
+        if errors.len() == 1 {
+            errors.remove(0).1
+        } else {
+            DiceError::Several(errors)
+        }
+    }
+}
+
+// Heading of several errors found in one roll:
+const SEVERAL_ERRORS: &str = "More than one part of thy roll hath gone awry, traveller:";
+
+// Where in an expression an error is blamed on, e.g.: ", at `21`" in "[21d6]", unless it is blamed on
+// no part in particular, or on all of what is quoted:
+fn pointing(span: Option<Span>, expr: &str, quoted: &str) -> String {
+    let whole = [Some(quoted), quoted.strip_prefix('[').and_then(|quoted| quoted.strip_suffix(']'))];
+    match span.filter(|span| !span.is_empty()).and_then(|span| span.of(expr)) {
+        Some(part) if !whole.contains(&Some(part)) => format!(", at {}", quote(part)),
+        _ => String::new(),
+    }
+}
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiceError::NumberTooLarge(_) => write!(f, "That numeral is overlarge for mine ancient, fatigued orbs to even peruse. I am apprehensive thou shalt require another's aid. Should thou seek assistance with lesser matters, I am at thy service!"),
+            DiceError::TooManyDice(_) => write!(f, "Inquired for overmuch rolls. I may only proffer up to {} at once!", max_dice()),
+            DiceError::TooManySides(_) => write!(f, "Entreaded for an excessive sum. I can only reckon unto {}!", max_sides()),
+            DiceError::BonusTooLarge(_) => write!(f, "Besought an excessive boon. Be not so covetous, traveller!"),
+            DiceError::KeepTooMany(_) => write!(f, "Thou wouldst keep more dice than thou hast cast. Such sorcery is beyond mine ken!"),
+            DiceError::MatchTooMany(_) => write!(f, "Thou seekest more of a kind than thou hast dice cast. Such a feat is beyond mine ken!"),
+            DiceError::MatchTooFew(_) => write!(f, "A match must be of at least two dice, traveller. A lone die matcheth naught but itself!"),
+            DiceError::Trivial(value, _) => write!(f, "I deem thy sagacity to be not especially lofty, thus I shall provide a rejoinder to thy entreaty, as a gesture of courtesy: {}", value),
+            DiceError::Jest(_) => write!(f, "Deem me not a fool, traveller. Be earnest and cease thy jesting with me!"),
+            DiceError::UnknownFunction(name, _) => write!(f, "The incantation \"{}\" is foreign to mine ears. I am only versed in the arts of min, max, floor, ceil and round!", name),
+            DiceError::AgainNotD10(_) => write!(f, "The rites of the World of Darkness are only performed with ten-sided dice, traveller!"),
+            DiceError::TooManyCoins(_) => write!(f, "Mine purse holds but a hundred coins, traveller. I cannot flip more than that!"),
+            DiceError::SymbolicArithmetic(_) => write!(f, "Thou canst not reckon with symbols as though they were numerals. Cast thy symbolic dice on their own, traveller!"),
+            DiceError::DivisionByZero(_) => write!(f, "Thou wouldst have me divide by naught? Not even the arcane arts permit such folly!"),
+            DiceError::SeveralComparisons(_) => write!(f, "A roll can be measured against but one mark at a time, traveller. Choose thy difficulty!"),
+            DiceError::NotRolled(_) => write!(f, "An average or a bound is no roll, traveller, and hath neither average nor bounds of its own!"),
+            DiceError::ResultTooLarge(_) => write!(f, "Such a sum would overflow the very heavens, traveller. Mine abacus hath not beads enough to reckon it!"),
+            DiceError::TooComplex(_) => write!(f, "Reckoning the fortunes of such a roll would keep me busy until the stars burn out, traveller. Ask me of a humbler one!"),
+            DiceError::IllFormed(_) => write!(f, "Thy formula is ill-formed, traveller. I can make neither heads nor tails of it!"),
+            DiceError::Unsupported(part, _) => write!(f, "The Roll20 rite {} is foreign to me, traveller, and I would rather not guess at it!", quote(part)),
+            DiceError::NoCharacter(name, _) => write!(f, "Thou hast no character for me to read {} from, traveller! Register one with /character set.", quote(name)),
+            DiceError::UnknownVariable(name, _) => write!(f, "Thy character hath no {} I know of, traveller!", quote(name)),
+            DiceError::NotSupported(part, _) => write!(f, "The MapTool rite {} is known to me, traveller, yet it is not among those I perform!", quote(part)),
+            DiceError::Several(errors) => {
+                write!(f, "{}", SEVERAL_ERRORS)?;
+                for (part, why) in errors {
+                    write!(f, "\n{} …in {}", why, quote(part))?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+// Functions that can be applied to several rolls, e.g.: [max(1d20, 1d20) + 5]
+pub enum Function {
+    Min,
+    Max,
+}
+
+// Rounding applied to the result of a division, e.g.: [floor(3d6/2)]
+#[derive(Clone, Copy, Serialize)]
+pub enum Rounding {
+    Floor,
+    Ceil,
+    Round,
+}
+
+// Argument of a function, e.g.: the "2d6+3" and "10" in [min(2d6+3, 10)]
+pub enum Argument {
+    Dice(Dice),
+    Constant(i64),
+}
+
+/// Notation rolls are written in: MapTool's, or Roll20's, e.g.: [2d20kh1] [8d6!] [{1d20+5, 1d20+3}kh1]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Syntax {
+    #[default]
+    MapTool,
+    Roll20,
+}
+
+// Anything that can be rolled from a bracketed expression:
+pub enum Roll {
+    Dice(Dice),
+    Function { function: Function, args: Vec<Argument>, bonus: i64 },
+    // Division of a roll, rounded to a whole number, e.g.: [floor(3d6/2)] [ceil(1d20/3) + 1]
+    Divide { rounding: Rounding, dividend: Argument, divisor: Argument, bonus: i64 },
+    // Dice whose amount is decided by another roll, e.g.: [(1d4)d6]
+    Nested { inner: Dice, outer: Dice },
+    // Roll compared against a difficulty class, e.g.: [1d20+7 vs 15] [1d20+7 dc 15]
+    Versus { roll: Box<Roll>, dc: i64 },
+    // Dice showing symbols instead of numbers, with the symbol on each face, e.g.: [3dH]
+    Symbolic { amount: i64, faces: &'static [&'static str] },
+    // Roll that must not exceed a target number, e.g.: [3d6 <= 12] [gurps12]
+    // Plain 3d6 rolls follow GURPS rules for criticals:
+    Under { roll: Box<Roll>, target: i64, gurps: bool },
+    // Savage Worlds trait die plus wild d6, both exploding, keeping the higher, e.g.: [sw8] [sw8+1]
+    Wild { sides: i64, bonus: i64 },
+    // Dice and numbers added or subtracted together, e.g.: [1d20 + 1d4 - 1] [-2] [10 - 12]
+    // Each term is paired with whether it is subtracted:
+    Sum(Vec<(bool, Argument)>),
+    // Average value of a roll, instead of an actual roll, e.g.: [avg 2d6+3]
+    Average(Box<Roll>),
+    // Lowest or highest possible value of a roll, instead of an actual roll, e.g.: [min 2d6+3] [max 2d6+3]
+    Theoretical { roll: Box<Roll>, maximum: bool },
+    // Roll driven by a seeded PRNG, so it can be reproduced, e.g.: [seed:1234 3d6]
+    Seeded { seed: u64, roll: Box<Roll> },
+    // Roll showing only its result, e.g.: [! 40d6]
+    Compact(Box<Roll>),
+    // Roll whose result is only for the one who rolled it (e.g.: the GM), rolled as any other: [gm 2d6]
+    Secret(Box<Roll>),
+}
+
+impl Roll {
+    // Parse a bracketed expression, e.g.: "[2d20+5]" or "[max(1d20, 1d20)]". Returns None if the text is not a roll at all:
+    pub fn parse(expr: &str) -> Option<Result<Roll, DiceError>> {
+        Roll::parse_with(expr, Syntax::MapTool)
+    }
+
+    /// Same as [`Roll::parse`], but with the expression written in the given notation. Only dice,
+    /// their sums and groups of rolls are written differently in Roll20's; the rest (e.g.: [gm …],
+    /// [… vs 15]) is the same in both. Errors point at the bytes of `expr` they are blamed on.
+    pub fn parse_with(expr: &str, syntax: Syntax) -> Option<Result<Roll, DiceError>> {
+        match lexer::tokenize(expr).as_slice() {
+            [open, inner @ .., close] if open.is('[') && close.is(']') => Roll::parse_tokens(inner, syntax),
+            _ => None,
+        }
+    }
+
+    // Parse the tokens within the brackets of an expression, e.g.: those of "2d20+5" in "[2d20+5]":
+    fn parse_tokens(tokens: &[Token], syntax: Syntax) -> Option<Result<Roll, DiceError>> {
+        if let Some(rest) = after_word(tokens, "gm") {
+            return Some(Roll::parse_tokens(rest, syntax)?.map(|roll| Roll::Secret(Box::new(roll))));
+        }
+
+        if let Some(rest) = after_word(tokens, "avg") {
+            return match Roll::parse_tokens(rest, syntax)? {
+                Ok(roll) if roll.is_estimate() => Some(Err(DiceError::NotRolled(span(rest)))),
+                Ok(roll) => Some(Ok(Roll::Average(Box::new(roll)))),
+                Err(why) => Some(Err(why)),
+            };
+        }
+
+        // Seeded rolls, e.g.: [seed:1234 3d6] [seed: 1234 3d6]
+        if let [seed, colon, rest @ ..] = tokens {
+            if let [number, space, rest @ ..] = skip_space(rest) {
+                if seed.is_word("seed") && colon.is(':') && number.kind == Kind::Number && space.kind == Kind::Space && !rest.is_empty() {
+                    let Ok(seed) = number.text.parse::<u64>() else {
+                        return Some(Err(DiceError::NumberTooLarge(number.span)));
+                    };
+                    return Some(Roll::parse_tokens(rest, syntax)?.map(|roll| Roll::Seeded { seed, roll: Box::new(roll) }));
+                }
+            }
+        }
+
+        if let [bang, rest @ ..] = tokens {
+            if bang.is('!') && !rest.is_empty() {
+                let rest = match rest {
+                    [space, rest @ ..] if space.kind == Kind::Space && !rest.is_empty() => rest,
+                    _ => rest,
+                };
+                return Some(Roll::parse_tokens(rest, syntax)?.map(|roll| Roll::Compact(Box::new(roll))));
+            }
+        }
+
+        // Beware of min() and max() functions, e.g.: [max (1d20, 1d20)], which are not rolls on their own:
+        if let Some(rest) = after_word(tokens, "min").or_else(|| after_word(tokens, "max")) {
+            if let Some(roll) = Roll::parse_tokens(rest, syntax) {
+                return match roll {
+                    Ok(roll) if roll.is_estimate() => Some(Err(DiceError::NotRolled(span(rest)))),
+                    Ok(roll) => Some(Ok(Roll::Theoretical { roll: Box::new(roll), maximum: tokens[0].is_word("max") })),
+                    Err(why) => Some(Err(why)),
+                };
+            }
+        }
+
+        // Symbolic dice, e.g.: [3dH]
+        if let [amount @ .., die] = tokens {
+            if let Some(faces) = symbolic_faces(die) {
+                if let Some(amount) = optional_number(amount) {
+                    let written = amount.map_or(die.span, |amount| amount.span);
+                    let amount = match amount {
+                        Some(amount) => match number(amount) {
+                            Ok(amount) => amount,
+                            Err(why) => return Some(Err(why)),
+                        },
+                        None => 1,
+                    };
+                    if amount > max_dice() {
+                        return Some(Err(DiceError::TooManyDice(written)));
+                    }
+                    if amount == 0 {
+                        return Some(Err(DiceError::Trivial(0, span(tokens))));
+                    }
+                    return Some(Ok(Roll::Symbolic { amount, faces }));
+                }
+            }
+        }
+
+        // Fudge dice, in either notation, e.g.: [4dF] [dF+1]
+        if let Some(index) = tokens.iter().position(|token| token.kind == Kind::Word && (token.text == "df" || token.text == "dF")) {
+            let (amount, die, rest) = (&tokens[..index], &tokens[index], &tokens[index + 1..]);
+            if let (Some(amount), Some(bonus)) = (optional_number(amount), trailing_bonus(rest)) {
+                let written = Written {
+                    dice: span(tokens),
+                    amount: amount.map_or(die.span, |amount| amount.span),
+                    sides: die.span,
+                    bonus: bonus.as_ref().map_or(Span::default(), |bonus| bonus.span),
+                    ..Written::default()
+                };
+                let bonus = bonus.map_or(Ok(0), |bonus| number(&bonus.number).map(|value| if bonus.negative { -value } else { value }));
+                let numbers = amount.map_or(Ok(1), number).and_then(|amount| Ok((amount, bonus?)));
+                return Some(numbers.and_then(|(amount, bonus)| Dice { amount, sides: 3, kind: DieKind::Fudge, sort: None, bonus, written }.validate()).map(Roll::Dice));
+            }
+        }
+
+        // Coins, e.g.: [coin] [3 coins]
+        if let [amount @ .., coins] = tokens {
+            if coins.is_word("coin") || coins.is_word("coins") {
+                if let Some(amount) = optional_number(skip_trailing_space(amount)) {
+                    return Some(amount.map_or(Ok(1), number).and_then(|amount| Roll::coins(amount).map_err(|why| match why {
+                        DiceError::TooManyCoins(_) => DiceError::TooManyCoins(span(tokens)),
+                        DiceError::Trivial(value, _) => DiceError::Trivial(value, span(tokens)),
+                        why => why,
+                    })));
+                }
+            }
+        }
+
+        // Symbolic dice mixed with anything else that would otherwise be a roll, e.g.: [1dH+2], taken as d6s:
+        if let Some(index) = (0..tokens.len()).find(|&i| is_lone_symbolic_die(tokens, i)) {
+            let replaced = tokens.iter().enumerate().flat_map(|(i, token)| match is_lone_symbolic_die(tokens, i) {
+                true => vec![
+                    Token { kind: Kind::Word, text: "d", span: Span::new(token.span.start, token.span.start + 1) },
+                    Token { kind: Kind::Number, text: "6", span: Span::new(token.span.start + 1, token.span.end) },
+                ],
+                false => vec![*token],
+            }).collect::<Vec<Token>>();
+            if Roll::parse_tokens(&replaced, syntax).is_some() {
+                let start = if index > 0 && tokens[index - 1].kind == Kind::Number { index - 1 } else { index };
+                return Some(Err(DiceError::SymbolicArithmetic(span(&tokens[start..=index]))));
+            }
+        }
+
+        // Rolls compared against a difficulty class, e.g.: [1d20+7 vs 15] [1d20+7 dc 15] [1d20+7vs15]
+        if let Some((rest, dc)) = trailing_number(tokens) {
+            if let Some((roll, comparison)) = before_comparison(skip_trailing_space(rest)) {
+                let roll = match Roll::parse_tokens(&roll, syntax)? {
+                    Ok(Roll::Versus { .. } | Roll::Under { .. }) => return Some(Err(DiceError::SeveralComparisons(comparison.to(dc.span)))),
+                    Ok(roll) => roll,
+                    Err(why) => return Some(Err(why)),
+                };
+                return Some(dc.value().map(|dc| Roll::Versus { roll: Box::new(roll), dc }));
+            }
+        }
+
+        // Rolls that must not exceed a target number, e.g.: [3d6 <= 12] [gurps12]
+        if let Some((rest, target)) = trailing_number(tokens) {
+            let rest = skip_trailing_space(rest);
+            let roll = match rest {
+                [gurps] if gurps.is_word("gurps") => Some(Ok(Roll::Dice(Dice::new(3, 6, DieKind::Regular).ok().expect("No GURPS dice?")))),
+                [roll @ .., less, equal] if less.is('<') && equal.is('=') && !roll.is_empty() => {
+                    let roll = match roll {
+                        [roll @ .., space] if space.kind == Kind::Space && !roll.is_empty() => roll,
+                        _ => roll,
+                    };
+                    match Roll::parse_tokens(roll, syntax)? {
+                        Ok(Roll::Versus { .. } | Roll::Under { .. }) => return Some(Err(DiceError::SeveralComparisons(less.span.to(target.span)))),
+                        Ok(roll) => Some(Ok(roll)),
+                        Err(why) => Some(Err(why)),
+                    }
+                },
+                _ => None,
+            };
+            if let Some(roll) = roll {
+                return Some(roll.and_then(|roll| {
+                    let target = target.value()?;
+                    let gurps = matches!(roll, Roll::Dice(Dice { amount: 3, sides: 6, kind: DieKind::Regular, bonus: 0, .. }));
+                    Ok(Roll::Under { roll: Box::new(roll), target, gurps })
+                }));
+            }
+        }
+
+        // Savage Worlds trait dice, e.g.: [sw8] [sw8+1]
+        if let [sw, sides, rest @ ..] = tokens {
+            if let (true, Some(bonus)) = (sw.is_word("sw") && sides.kind == Kind::Number, trailing_bonus(rest)) {
+                let (sides_span, sides) = (sides.span, number(sides));
+                return Some(sides.and_then(|sides| Ok((sides, bonus.map_or(Ok(0), |bonus| bonus.value())?))).and_then(|(sides, bonus)| {
+                    // Dice of less than two sides would explode forever:
+                    if sides < 2 {
+                        return Err(DiceError::Jest(sides_span));
+                    }
+                    if sides > max_sides() {
+                        return Err(DiceError::TooManySides(sides_span));
+                    }
+                    Ok(Roll::Wild { sides, bonus })
+                }));
+            }
+        }
+
+        // Dice are written differently in Roll20's notation:
+        if syntax == Syntax::Roll20 {
+            return Roll::parse_roll20(tokens);
+        }
+
+        // Dice whose amount is decided by another roll, e.g.: [(1d4)d6]
+        if let [open, rest @ ..] = tokens {
+            if let Some(close) = rest.iter().position(|token| token.is(')')) {
+                let (inner, outer) = (&rest[..close], &rest[close + 1..]);
+                let parens = |tokens: &[Token]| tokens.iter().any(|token| token.is('(') || token.is(')'));
+                if open.is('(') && !inner.is_empty() && !parens(inner) && outer.first().is_some_and(|die| die.kind == Kind::Word && die.text.starts_with('d')) && !parens(outer) {
+                    let inner = match Dice::parse(inner, None)? {
+                        Ok(inner) => inner,
+                        Err(why) => return Some(Err(why)),
+                    };
+                    // The actual amount is only known after rolling the inner dice, so check the rest with the maximum amount:
+                    let outer = match Dice::parse(outer, Some((max_dice(), open.span.to(rest[close].span))))? {
+                        Ok(outer) => outer,
+                        Err(why) => return Some(Err(why)),
+                    };
+                    return Some(Ok(Roll::Nested { inner, outer }));
+                }
+            }
+        }
+
+        // Functions, e.g.: [max(1d20, 1d20) + 5] [floor(3d6 / 2)]
+        let Some((name, args, bonus)) = function_call(tokens) else {
+            if let Some(dice) = Dice::parse(tokens, None) {
+                return Some(dice.map(Roll::Dice));
+            }
+            return Roll::parse_sum(tokens);
+        };
+
+        let bonus = match bonus.map_or(Ok(0), |bonus| bonus.value()) {
+            Ok(bonus) => bonus,
+            Err(why) => return Some(Err(why)),
+        };
+
+        let rounding = match name.text.to_lowercase().as_str() {
+            "floor" => Some(Rounding::Floor),
+            "ceil" => Some(Rounding::Ceil),
+            "round" => Some(Rounding::Round),
+            _ => None,
+        };
+        if let Some(rounding) = rounding {
+            let slash = args.iter().position(|token| token.is('/'))?;
+            let (dividend_tokens, divisor_tokens) = (trim(&args[..slash]), trim(&args[slash + 1..]));
+            let (dividend, divisor) = match (parse_argument(dividend_tokens)?, parse_argument(divisor_tokens)?) {
+                (Ok(dividend), Ok(divisor)) => (dividend, divisor),
+                (Err(why), Ok(_)) | (Ok(_), Err(why)) => return Some(Err(why)),
+                (Err(dividend_why), Err(divisor_why)) => {
+                    return Some(Err(DiceError::Several(vec![(text(dividend_tokens), dividend_why), (text(divisor_tokens), divisor_why)])));
+                },
+            };
+            if let Argument::Constant(0) = divisor {
+                return Some(Err(DiceError::DivisionByZero(span(divisor_tokens))));
+            }
+            return Some(Ok(Roll::Divide { rounding, dividend, divisor, bonus }));
+        }
+
+        let mut parsed = Vec::new();
+        let mut errors = Vec::new();
+        for arg in args.split(|token| token.is(',')).map(trim) {
+            match parse_argument(arg)? {
+                Ok(argument) => parsed.push(argument),
+                Err(why) => errors.push((text(arg), why)),
+            }
+        }
+        if !errors.is_empty() {
+            return Some(Err(DiceError::combine(errors)));
+        }
+
+        let function = match name.text.to_lowercase().as_str() {
+            "min" => Function::Min,
+            "max" => Function::Max,
+            _ => return Some(Err(DiceError::UnknownFunction(name.text.to_owned(), name.span))),
+        };
+
+        Some(Ok(Roll::Function { function, args: parsed, bonus }))
+    }
+
+    // Flip the given amount of coins:
+    pub fn coins(amount: i64) -> Result<Roll, DiceError> {
+        if amount > MAX_COINS {
+            return Err(DiceError::TooManyCoins(Span::default()));
+        }
+        if amount < 1 {
+            return Err(DiceError::Trivial(0, Span::default()));
+        }
+        Ok(Roll::Symbolic { amount, faces: COIN })
+    }
+
+    // Parse a sum of dice and signed numbers, e.g.: "1d20 + 1d4 - 1" or "-2":
+    fn parse_sum(tokens: &[Token]) -> Option<Result<Roll, DiceError>> {
+        // Plain numbers, e.g.: [1], are not rolls:
+        if !tokens.iter().any(|token| token.is('+') || token.is('-')) {
+            return None;
+        }
+
+        let mut terms = Vec::new();
+        let mut errors = Vec::new();
+        let mut rest = skip_space(tokens);
+        let mut negative = false;
+        if let [sign, after @ ..] = rest {
+            if sign.is('+') || sign.is('-') {
+                negative = sign.is('-');
+                rest = skip_space(after);
+            }
+        }
+        loop {
+            // Each term is either dice (without a bonus) or a number, e.g.: "1d20" "4d6km2sa" "1":
+            let length = rest.iter().take_while(|token| matches!(token.kind, Kind::Number | Kind::Word) || token.is('<')).count();
+            let (term, after) = rest.split_at(length);
+            match term {
+                [] => return None,
+                [constant] if constant.kind == Kind::Number => match number(constant) {
+                    Ok(value) => terms.push((negative, Argument::Constant(value))),
+                    Err(why) => errors.push((text(term), why)),
+                },
+                _ => match Dice::read(term, None, false)? {
+                    Ok(dice) => terms.push((negative, Argument::Dice(dice))),
+                    Err(why) => errors.push((text(term), why)),
+                },
+            }
+
+            if after.is_empty() {
+                break;
+            }
+            let [sign, after @ ..] = skip_space(after) else {
+                return None;
+            };
+            if !sign.is('+') && !sign.is('-') {
+                return None;
+            }
+            negative = sign.is('-');
+            rest = skip_space(after);
+        }
+        if !errors.is_empty() {
+            return Some(Err(DiceError::combine(errors)));
+        }
+
+        if !fits(&terms) {
+            return Some(Err(DiceError::ResultTooLarge(span(tokens))));
+        }
+
+        Some(Ok(Roll::Sum(terms)))
+    }
+
+    // Parse a roll in Roll20's notation: dice with their modifiers, added to or subtracted from each
+    // other and numbers, or a group of rolls, e.g.: "2d20kh1+5" "8d6! - 2" "{1d20+5, 1d20+3}kh1".
+    // Modifiers with no counterpart here are reported, rather than rolled as something else:
+    fn parse_roll20(tokens: &[Token]) -> Option<Result<Roll, DiceError>> {
+        let tokens = trim(tokens);
+
+        // Rolls within braces are added up, or only the highest (or lowest) one is kept:
+        if let Some(group) = Roll::parse_group(tokens) {
+            return group;
+        }
+
+        let mut terms = Vec::new();
+        let mut bonuses = Vec::new();
+        let mut errors = Vec::new();
+        let mut rest = tokens;
+        let mut negative = false;
+        if let [sign, after @ ..] = rest {
+            if sign.is('+') || sign.is('-') {
+                negative = sign.is('-');
+                rest = skip_space(after);
+            }
+        }
+        loop {
+            // Anything up to the next sign or space is a term, e.g.: "2d20kh1" or "5":
+            let length = rest.iter().take_while(|token| !is_blank(token) && !token.is('+') && !token.is('-')).count();
+            let (term, after) = rest.split_at(length);
+            if term.is_empty() {
+                return None;
+            }
+            match parse_roll20_argument(term)? {
+                Ok(argument) => {
+                    terms.push((negative, argument));
+                    bonuses.push(span(term));
+                },
+                Err(why) => errors.push((text(term), why)),
+            }
+
+            if after.is_empty() {
+                break;
+            }
+            let [sign, after @ ..] = skip_space(after) else {
+                return None;
+            };
+            if !sign.is('+') && !sign.is('-') {
+                return None;
+            }
+            negative = sign.is('-');
+            rest = skip_space(after);
+        }
+        if !errors.is_empty() {
+            return Some(Err(DiceError::combine(errors)));
+        }
+
+        // Plain bracketed numbers, e.g.: [1], are not rolls:
+        let dice = terms.iter().filter(|(_, term)| matches!(term, Argument::Dice(_))).count();
+        if dice == 0 && !tokens.iter().any(|token| token.is('+') || token.is('-')) {
+            return None;
+        }
+        // Dice plus (or minus) numbers are dice with a bonus, as [2d6+3] is:
+        if let [(false, Argument::Dice(first)), rest @ ..] = terms.as_slice() {
+            if dice == 1 {
+                let bonus = rest.iter().try_fold(first.bonus, |bonus, (negative, term)| match term {
+                    Argument::Constant(value) if *negative => bonus.checked_sub(*value),
+                    Argument::Constant(value) => bonus.checked_add(*value),
+                    Argument::Dice(_) => None,
+                });
+                let Some(bonus) = bonus else {
+                    return Some(Err(DiceError::ResultTooLarge(span(tokens))));
+                };
+                let written = Written { bonus: span_between(&bonuses[1..]).unwrap_or(first.written.bonus), ..first.written };
+                return Some(Dice { bonus, written, ..first.clone() }.validate().map(Roll::Dice));
+            }
+        }
+        if !fits(&terms) {
+            return Some(Err(DiceError::ResultTooLarge(span(tokens))));
+        }
+        Some(Ok(Roll::Sum(terms)))
+    }
+
+    // Parse a group of rolls in Roll20's notation, e.g.: "{1d20+5, 1d20+3}kh1" "{2d6, 1d8} + 1".
+    // Returns None if the tokens are no group, and Some(None) if they are one, yet hold no rolls:
+    fn parse_group(tokens: &[Token]) -> Option<Option<Result<Roll, DiceError>>> {
+        let [open, rest @ ..] = tokens else {
+            return None;
+        };
+        let close = rest.iter().position(|token| token.is('}'))?;
+        let (rolls, rest) = (&rest[..close], &rest[close + 1..]);
+        if !open.is('{') || rolls.is_empty() || rolls.iter().any(|token| token.is('{')) {
+            return None;
+        }
+        // Which roll to keep, if any, e.g.: "kh1" or "k"
+        let keep = match rest {
+            [word, count, ..] if is_group_keep(word) && count.kind == Kind::Number => 2,
+            [word, ..] if is_group_keep(word) => 1,
+            _ => 0,
+        };
+        let (keep, rest) = rest.split_at(keep);
+        let bonus = trailing_bonus(rest)?;
+
+        let mut args = Vec::new();
+        let mut errors = Vec::new();
+        for roll in rolls.split(|token| token.is(',')).map(trim) {
+            match parse_roll20_argument(roll) {
+                None => return Some(None),
+                Some(Ok(arg)) => args.push(arg),
+                Some(Err(why)) => errors.push((text(roll), why)),
+            }
+        }
+        if !errors.is_empty() {
+            return Some(Some(Err(DiceError::combine(errors))));
+        }
+        let bonus = match bonus.map_or(Ok(0), |bonus| bonus.value()) {
+            Ok(bonus) => bonus,
+            Err(why) => return Some(Some(Err(why))),
+        };
+        let function = match Some(text(keep)).filter(|keep| !keep.is_empty()).as_deref() {
+            None => {
+                let mut terms = args.into_iter().map(|arg| (false, arg)).collect::<Vec<_>>();
+                terms.push((bonus < 0, Argument::Constant(bonus.abs())));
+                if !fits(&terms) {
+                    return Some(Some(Err(DiceError::ResultTooLarge(span(tokens)))));
+                }
+                return Some(Some(Ok(Roll::Sum(terms))));
+            },
+            Some("k" | "kh" | "k1" | "kh1") => Function::Max,
+            Some("kl" | "kl1") => Function::Min,
+            // Keeping several rolls of a group, or dropping some, has no counterpart:
+            Some(part) => return Some(Some(Err(DiceError::Unsupported(part.to_owned(), span(keep))))),
+        };
+        Some(Some(Ok(Roll::Function { function, args, bonus })))
+    }
+
+    /// Whether the result of the roll is only for the one who rolled it.
+    pub fn is_secret(&self) -> bool {
+        match self {
+            Roll::Secret(_) => true,
+            Roll::Seeded { roll, .. } | Roll::Compact(roll) => roll.is_secret(),
+            _ => false,
+        }
+    }
+
+    // Whether the roll is not rolled at all, but only reckoned: an average or a bound.
+    fn is_estimate(&self) -> bool {
+        match self {
+            Roll::Average(_) | Roll::Theoretical { .. } => true,
+            Roll::Seeded { roll, .. } | Roll::Compact(roll) | Roll::Secret(roll) => roll.is_estimate(),
+            _ => false,
+        }
+    }
+
+    pub async fn roll(&self) -> RollResult {
+        self.roll_with(&mut RandomOrg).await
+    }
+
+    /// Same as roll, but rolling right away with the given generator: without RANDOM.ORG nor an
+    /// async runtime. The result is always flagged as pseudo-random.
+    pub fn roll_local<G: Rng + Send>(&self, rng: &mut G) -> RollResult {
+        finish_local(self.roll_with(&mut Local(rng)))
+    }
+
+    // Same as roll, but taking the random numbers from the given roller:
+    pub async fn roll_with<R: Roller>(&self, roller: &mut R) -> RollResult {
+        match self {
+            Roll::Dice(dice) => RollResult::Dice(dice.roll_with(roller).await),
+            Roll::Function { function, args, bonus } => {
+                let mut results = Vec::new();
+                for arg in args {
+                    results.push(arg.roll_with(roller).await);
+                }
+
+                let values = results.iter().map(ArgumentResult::value);
+                let chosen = match function {
+                    Function::Min => values.min(),
+                    Function::Max => values.max(),
+                }.expect("No arguments?");
+                // Mark the first argument that yielded the chosen value:
+                let selected = results.iter().position(|r| r.value() == chosen).expect("No selection?");
+
+                RollResult::Function { function: match function { Function::Min => "min", Function::Max => "max" }, results, selected, bonus: *bonus }
+            },
+            Roll::Divide { rounding, dividend, divisor, bonus } => {
+                let dividend = dividend.roll_with(roller).await;
+                let divisor = divisor.roll_with(roller).await;
+                RollResult::Divide { rounding: *rounding, dividend, divisor, bonus: *bonus }
+            },
+            Roll::Symbolic { amount, faces } => {
+                let (seq, truly_random) = roller.gen(*amount, 1, faces.len() as i64).await;
+                RollResult::Symbolic { shown: seq.into_iter().map(|face| (face - 1) as usize).collect(), faces, truly_random }
+            },
+            Roll::Under { roll, target, gurps } => RollResult::Under { result: Box::new(Box::pin(roll.roll_with(roller)).await), target: *target, gurps: *gurps },
+            Roll::Wild { sides, bonus } => {
+                let (trait_die, trait_truly_random) = explode(*sides, roller).await;
+                let (wild_die, wild_truly_random) = explode(6, roller).await;
+                RollResult::Wild { trait_die, wild_die, bonus: *bonus, truly_random: trait_truly_random && wild_truly_random }
+            },
+            Roll::Nested { inner, outer } => {
+                let inner = inner.roll_with(roller).await;
+                let amount = inner.total().clamp(0, max_dice());
+                let capped = amount != inner.total();
+
+                let outer = match (Dice { amount, ..outer.clone() }).validate() {
+                    Ok(outer) => Ok(outer.roll_with(roller).await),
+                    Err(why) => Err(why),
+                };
+                RollResult::Nested { inner, capped, outer }
+            },
+            Roll::Sum(terms) => {
+                let mut results = Vec::new();
+                for (negative, term) in terms {
+                    results.push((*negative, term.roll_with(roller).await));
+                }
+                RollResult::Sum(results)
+            },
+            Roll::Versus { roll, dc } => RollResult::Versus { result: Box::new(Box::pin(roll.roll_with(roller)).await), dc: *dc },
+            // Every die, rerolls included, comes from the seeded PRNG rather than from the roller:
+            Roll::Seeded { seed, roll } => RollResult::Seeded { seed: *seed, result: Box::new(roll.roll_local(&mut StdRng::seed_from_u64(*seed))) },
+            Roll::Compact(roll) => RollResult::Compact(Box::new(Box::pin(roll.roll_with(roller)).await)),
+            // Only who sees the result changes:
+            Roll::Secret(roll) => Box::pin(roll.roll_with(roller)).await,
+            Roll::Average(roll) => {
+                let (value, exact) = match roll.expected() {
+                    Some((value, exact)) => (Some(value), exact),
+                    None => (None, true),
+                };
+                RollResult::Average { value, exact }
+            },
+            Roll::Theoretical { roll, maximum } => RollResult::Theoretical {
+                value: roll.bounds().map(|(lowest, highest)| if *maximum { highest } else { lowest }),
+                maximum: *maximum,
+            },
+        }
+    }
+
+    // Average value of the roll, and whether it is exact or estimated. None for rolls without a numeric value:
+    fn expected(&self) -> Option<(f64, bool)> {
+        match self {
+            Roll::Dice(dice) => Some(dice.expected()),
+            // The average of a sum is the sum of the averages:
+            Roll::Sum(terms) => terms.iter().try_fold((0.0, true), |(sum, exact), (negative, term)| {
+                let (value, term_exact) = term.expected();
+                Some((if *negative { sum - value } else { sum + value }, exact && term_exact))
+            }),
+            Roll::Versus { roll, .. } | Roll::Under { roll, .. } | Roll::Seeded { roll, .. } | Roll::Compact(roll) | Roll::Secret(roll) => roll.expected(),
+            Roll::Symbolic { .. } | Roll::Average(_) | Roll::Theoretical { .. } => None,
+            _ => {
+                let mut rng = StdRng::seed_from_u64(AVERAGE_SEED);
+                let mut roll_die = |sides| rng.gen_range(1..sides+1);
+                // Wide enough to add up many huge values without overflowing:
+                let mut sum = 0i128;
+                for _ in 0..AVERAGE_ITERATIONS {
+                    sum += i128::from(self.simulate(&mut roll_die)?);
+                }
+                Some((sum as f64 / AVERAGE_ITERATIONS as f64, false))
+            },
+        }
+    }
+
+    // Roll locally, with the given function rolling each die, only to know the value the roll would yield:
+    fn simulate(&self, roll_die: &mut dyn FnMut(i64) -> i64) -> Option<i64> {
+        match self {
+            Roll::Dice(dice) => Some(dice.simulate(roll_die)),
+            Roll::Function { function, args, bonus } => {
+                let values = args.iter().map(|arg| arg.simulate(roll_die));
+                let chosen = match function {
+                    Function::Min => values.min(),
+                    Function::Max => values.max(),
+                }?;
+                Some(chosen + bonus)
+            },
+            Roll::Divide { rounding, dividend, divisor, bonus } => {
+                let dividend = ArgumentResult::Constant(dividend.simulate(roll_die));
+                let divisor = ArgumentResult::Constant(divisor.simulate(roll_die));
+                RollResult::Divide { rounding: *rounding, dividend, divisor, bonus: *bonus }.total()
+            },
+            Roll::Nested { inner, outer } => {
+                let amount = inner.simulate(roll_die).clamp(0, max_dice());
+                match (Dice { amount, ..outer.clone() }).validate() {
+                    Ok(outer) => Some(outer.simulate(roll_die)),
+                    Err(DiceError::Trivial(value, _)) => Some(value),
+                    Err(_) => None,
+                }
+            },
+            Roll::Wild { sides, bonus } => {
+                let trait_die = explode_chain_local(roll_die(*sides), *sides, *sides, roll_die);
+                let wild_die = explode_chain_local(roll_die(6), 6, 6, roll_die);
+                Some(trait_die.iter().sum::<i64>().max(wild_die.iter().sum()) + bonus)
+            },
+            Roll::Sum(terms) => Some(terms.iter().map(|(negative, term)| {
+                if *negative { -term.simulate(roll_die) } else { term.simulate(roll_die) }
+            }).sum()),
+            Roll::Versus { roll, .. } | Roll::Under { roll, .. } | Roll::Seeded { roll, .. } | Roll::Compact(roll) | Roll::Secret(roll) => roll.simulate(roll_die),
+            Roll::Symbolic { .. } | Roll::Average(_) | Roll::Theoretical { .. } => None,
+        }
+    }
+
+    // Lowest and highest values the roll can yield, worked out from those of each of its parts. None
+    // for rolls without a numeric value:
+    fn bounds(&self) -> Option<(i64, i64)> {
+        match self {
+            Roll::Dice(dice) => Some(dice.bounds()),
+            Roll::Function { function, args, bonus } => {
+                let bounds = args.iter().map(Argument::bounds);
+                let (lowest, highest) = match function {
+                    Function::Min => bounds.reduce(|(low, high), (lowest, highest)| (low.min(lowest), high.min(highest))),
+                    Function::Max => bounds.reduce(|(low, high), (lowest, highest)| (low.max(lowest), high.max(highest))),
+                }?;
+                Some((lowest + bonus, highest + bonus))
+            },
+            // Either bound lies at a corner: the bounds of the dividend over those of the divisor, or
+            // over the divisors closest to zero on either side of it, as dividing by zero is no value:
+            Roll::Divide { rounding, dividend, divisor, bonus } => {
+                let (low, high) = dividend.bounds();
+                let (below, above) = divisor.bounds();
+                let divisors = [below, above, -1, 1].into_iter().filter(|divisor| *divisor != 0 && (below..=above).contains(divisor));
+                let values = divisors.flat_map(|divisor| [low, high].map(|dividend| {
+                    let (dividend, divisor) = (ArgumentResult::Constant(dividend), ArgumentResult::Constant(divisor));
+                    RollResult::Divide { rounding: *rounding, dividend, divisor, bonus: *bonus }.total()
+                })).flatten().collect::<Vec<i64>>();
+                Some((*values.iter().min()?, *values.iter().max()?))
+            },
+            // Every amount of dice the inner roll may call for:
+            Roll::Nested { inner, outer } => {
+                let (fewest, most) = inner.bounds();
+                (fewest.clamp(0, max_dice())..=most.clamp(0, max_dice())).filter_map(|amount| match (Dice { amount, ..outer.clone() }).validate() {
+                    Ok(outer) => Some(outer.bounds()),
+                    Err(DiceError::Trivial(value, _)) => Some((value, value)),
+                    Err(_) => None,
+                }).reduce(|(low, high), (lowest, highest)| (low.min(lowest), high.max(highest)))
+            },
+            // Both dice explode, so they grow with their faces:
+            Roll::Wild { .. } => Some((self.simulate(&mut |_| 1)?, self.simulate(&mut |sides| sides)?)),
+            // Subtracted terms are lowest when their dice are highest, and the other way around:
+            Roll::Sum(terms) => Some(terms.iter().fold((0, 0), |(low, high), (negative, term)| {
+                let (lowest, highest) = term.bounds();
+                if *negative { (low - highest, high - lowest) } else { (low + lowest, high + highest) }
+            })),
+            Roll::Versus { roll, .. } | Roll::Under { roll, .. } | Roll::Seeded { roll, .. } | Roll::Compact(roll) | Roll::Secret(roll) => roll.bounds(),
+            Roll::Symbolic { .. } | Roll::Average(_) | Roll::Theoretical { .. } => None,
+        }
+    }
+
+    /// Chances of every total the roll can yield, reckoned exactly for sums of plain dice and estimated
+    /// through local rolls otherwise. None for rolls without a numeric value.
+    pub fn distribution(&self) -> Option<Result<Distribution, DiceError>> {
+        if let Roll::Symbolic { .. } | Roll::Average(_) | Roll::Theoretical { .. } = self {
+            return None;
+        }
+        let mut budget = DISTRIBUTION_BUDGET;
+        if let Some(chances) = self.chances(&mut budget) {
+            return Some(Ok(Distribution { chances, exact: true }));
+        }
+
+        let mut rng = StdRng::seed_from_u64(AVERAGE_SEED);
+        let rolled = Cell::new(0);
+        let mut roll_die = |sides| {
+            rolled.set(rolled.get() + 1);
+            rng.gen_range(1..sides+1)
+        };
+        let mut counts = BTreeMap::new();
+        for _ in 0..DISTRIBUTION_ITERATIONS {
+            *counts.entry(self.simulate(&mut roll_die)?).or_insert(0) += 1;
+            if rolled.get() > DISTRIBUTION_BUDGET {
+                return Some(Err(DiceError::TooComplex(Span::default())));
+            }
+        }
+        let chances = counts.into_iter().map(|(total, count)| (total, count as f64 / DISTRIBUTION_ITERATIONS as f64)).collect();
+        Some(Ok(Distribution { chances, exact: false }))
+    }
+
+    // Exact chances of every total, for plain dice and sums of them, if they can be reckoned within the budget:
+    fn chances(&self, budget: &mut u64) -> Option<BTreeMap<i64, f64>> {
+        match self {
+            Roll::Dice(dice) => dice.chances(budget),
+            Roll::Sum(terms) => terms.iter().try_fold(BTreeMap::from([(0, 1.0)]), |chances, (negative, term)| {
+                let term = match term {
+                    Argument::Dice(dice) => dice.chances(budget)?,
+                    Argument::Constant(value) => BTreeMap::from([(*value, 1.0)]),
+                };
+                let term = if *negative { term.into_iter().map(|(total, chance)| (-total, chance)).collect() } else { term };
+                convolve(&chances, &term, budget)
+            }),
+            Roll::Versus { roll, .. } | Roll::Under { roll, .. } | Roll::Seeded { roll, .. } | Roll::Compact(roll) | Roll::Secret(roll) => roll.chances(budget),
+            _ => None,
+        }
+    }
+
+    /// What the roll does, in plain words and without rolling it, e.g.: "roll 2 dice of 6 sides and add them
+    /// up, then add 3". Parts of the roll are described on lines of their own.
+    pub fn describe(&self) -> String {
+        match self {
+            Roll::Dice(dice) => dice.describe(),
+            Roll::Function { function, args, bonus } => {
+                let chosen = match function {
+                    Function::Min => "lowest",
+                    Function::Max => "highest",
+                };
+                let args = args.iter().map(|arg| format!("\n• {}", arg.describe())).collect::<String>();
+                format!("take the {} of the following{}:{}", chosen, describe_bonus(*bonus), args)
+            },
+            Roll::Divide { rounding, dividend, divisor, bonus } => {
+                let rounding = match rounding {
+                    Rounding::Floor => "down",
+                    Rounding::Ceil => "up",
+                    Rounding::Round => "to the nearest whole number",
+                };
+                format!("divide the first of the following by the second, rounding {}{}:\n• {}\n• {}", rounding, describe_bonus(*bonus), dividend.describe(), divisor.describe())
+            },
+            Roll::Nested { inner, outer } => format!(
+                "{}\nThen roll as many dice of {} sides as that total, and {}{}.", inner.describe(), outer.sides, outer.kind.describe(), describe_bonus(outer.bonus),
+            ),
+            Roll::Versus { roll, dc } => format!("{}\nThen compare the total against a difficulty class of {}: meeting or beating it is a success.", roll.describe(), dc),
+            Roll::Symbolic { amount, faces } => {
+                let mut symbols = faces.to_vec();
+                symbols.dedup();
+                format!("roll {} {} showing symbols instead of numbers: {}", amount, if *amount == 1 { "die" } else { "dice" }, symbols.join(", "))
+            },
+            Roll::Under { roll, target, gurps } => {
+                let rules = if *gurps { ", following GURPS rules for criticals" } else { "" };
+                format!("{}\nThen check that the total does not exceed {}{}.", roll.describe(), target, rules)
+            },
+            Roll::Wild { sides, bonus } => format!("roll an exploding {}-sided trait die and an exploding 6-sided wild die, and keep the higher{}", sides, describe_bonus(*bonus)),
+            Roll::Sum(terms) => {
+                let terms = terms.iter().map(|(negative, term)| format!("\n{} {}", if *negative { "−" } else { "+" }, term.describe())).collect::<String>();
+                format!("add up the following:{}", terms)
+            },
+            Roll::Average(roll) => format!("do not roll, but tell the average total of:\n{}", roll.describe()),
+            Roll::Theoretical { roll, maximum } => format!("do not roll, but tell the {} possible total of:\n{}", if *maximum { "highest" } else { "lowest" }, roll.describe()),
+            Roll::Seeded { seed, roll } => format!("{}\nEvery die comes from the seed {}, so the same result comes out every time.", roll.describe(), seed),
+            Roll::Compact(roll) => format!("{}\nOnly the result is shown, not every die.", roll.describe()),
+            Roll::Secret(roll) => format!("{}\nThe result is only sent to whoever rolled it.", roll.describe()),
+        }
+    }
+}
+
+// A bonus as shown after what it is added to (or subtracted from), e.g.: " + 3" or " - 3"
+fn show_bonus(bonus: i64) -> String {
+    if bonus < 0 {
+        format!(" - {}", bonus.unsigned_abs())
+    } else {
+        format!(" + {}", bonus)
+    }
+}
+
+// How a bonus changes a result, in plain words, e.g.: ", then add 3"
+fn describe_bonus(bonus: i64) -> String {
+    match bonus.cmp(&0) {
+        Ordering::Greater => format!(", then add {}", bonus),
+        Ordering::Less => format!(", then subtract {}", bonus.unsigned_abs()),
+        Ordering::Equal => String::new(),
+    }
+}
+
+/// Chances of every total a roll can yield.
+pub struct Distribution {
+    chances: BTreeMap<i64, f64>,
+    // Whether the chances were reckoned exactly, rather than estimated:
+    exact: bool,
+}
+
+impl Distribution {
+    pub fn minimum(&self) -> i64 {
+        *self.chances.keys().next().expect("No totals?")
+    }
+
+    pub fn maximum(&self) -> i64 {
+        *self.chances.keys().next_back().expect("No totals?")
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.chances.iter().map(|(&total, chance)| total as f64 * chance).sum()
+    }
+
+    /// Whether the chances were reckoned exactly, rather than estimated through local rolls.
+    pub fn exact(&self) -> bool {
+        self.exact
+    }
+
+    /// Chance of a total of at least the given value, from 0 to 1.
+    pub fn at_least(&self, value: i64) -> f64 {
+        self.chances.range(value..).map(|(_, chance)| chance).sum()
+    }
+
+    /// Chances of the totals within (up to) the given amount of ranges of equal width, from the minimum
+    /// to the maximum, e.g.: (2, 4, 0.167) for the totals from 2 to 4.
+    pub fn histogram(&self, buckets: usize) -> Vec<(i64, i64, f64)> {
+        let (minimum, maximum) = (self.minimum() as i128, self.maximum() as i128);
+        let span = maximum - minimum + 1;
+        let width = (span + buckets.max(1) as i128 - 1) / buckets.max(1) as i128;
+        (0..(span + width - 1) / width).map(|bucket| {
+            let low = minimum + bucket * width;
+            let high = (low + width - 1).min(maximum);
+            (low as i64, high as i64, self.chances.range(low as i64..=high as i64).map(|(_, chance)| chance).sum())
+        }).collect()
+    }
+}
+
+// Chances of the sum of two independent totals, if they can be added up within the budget:
+fn convolve(first: &BTreeMap<i64, f64>, second: &BTreeMap<i64, f64>, budget: &mut u64) -> Option<BTreeMap<i64, f64>> {
+    *budget = budget.checked_sub((first.len() * second.len()) as u64)?;
+    let mut chances = BTreeMap::new();
+    for (&a, &a_chance) in first {
+        for (&b, &b_chance) in second {
+            *chances.entry(a.checked_add(b)?).or_insert(0.0) += a_chance * b_chance;
+        }
+    }
+    Some(chances)
+}
+
+#[derive(Serialize)]
+pub enum ArgumentResult {
+    Dice(DiceResult),
+    Constant(i64),
+}
+
+impl Argument {
+    async fn roll_with<R: Roller>(&self, roller: &mut R) -> ArgumentResult {
+        match self {
+            Argument::Dice(dice) => ArgumentResult::Dice(dice.roll_with(roller).await),
+            Argument::Constant(value) => ArgumentResult::Constant(*value),
+        }
+    }
+
+    fn simulate(&self, roll_die: &mut dyn FnMut(i64) -> i64) -> i64 {
+        match self {
+            Argument::Dice(dice) => dice.simulate(roll_die),
+            Argument::Constant(value) => *value,
+        }
+    }
+
+    fn bounds(&self) -> (i64, i64) {
+        match self {
+            Argument::Dice(dice) => dice.bounds(),
+            Argument::Constant(value) => (*value, *value),
+        }
+    }
+
+    fn expected(&self) -> (f64, bool) {
+        match self {
+            Argument::Dice(dice) => dice.expected(),
+            Argument::Constant(value) => (*value as f64, true),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Argument::Dice(dice) => dice.describe(),
+            Argument::Constant(value) => format!("the number {}", value),
+        }
+    }
+}
+
+impl ArgumentResult {
+    fn value(&self) -> i64 {
+        match self {
+            ArgumentResult::Dice(result) => result.total(),
+            ArgumentResult::Constant(value) => *value,
+        }
+    }
+
+    fn dice(&self) -> Option<&DiceResult> {
+        match self {
+            ArgumentResult::Dice(result) => Some(result),
+            ArgumentResult::Constant(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ArgumentResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgumentResult::Dice(result) => write!(f, "{}", result),
+            ArgumentResult::Constant(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// A roll as told outside Discord, e.g.: as JSON by the command line or the HTTP API.
+#[derive(Serialize)]
+pub struct Report<'a> {
+    pub expression: &'a str,
+    pub total: Option<i64>,
+    /// The result as shown in chat, without its flourishes, e.g.: "4, 2 + 3 = 9"
+    pub shown: String,
+    pub truly_random: bool,
+    pub result: &'a RollResult,
+}
+
+#[derive(Serialize)]
+pub enum RollResult {
+    Dice(DiceResult),
+    // Results of all the arguments of a function, with the position of the chosen one:
+    Function { function: &'static str, results: Vec<ArgumentResult>, selected: usize, bonus: i64 },
+    // Roll deciding the amount of dice (and whether it had to be capped), followed by the roll of those dice:
+    Divide { rounding: Rounding, dividend: ArgumentResult, divisor: ArgumentResult, bonus: i64 },
+    Nested { inner: DiceResult, capped: bool, outer: Result<DiceResult, DiceError> },
+    Versus { result: Box<RollResult>, dc: i64 },
+    Under { result: Box<RollResult>, target: i64, gurps: bool },
+    // Index of the face shown by each die, within the die's faces:
+    Symbolic { shown: Vec<usize>, faces: &'static [&'static str], truly_random: bool },
+    // Explosion chains of the trait and wild dice:
+    Wild { trait_die: Vec<i64>, wild_die: Vec<i64>, bonus: i64, truly_random: bool },
+    Sum(Vec<(bool, ArgumentResult)>),
+    // Average value of a roll (None if it has none), and whether it is exact or estimated:
+    Average { value: Option<f64>, exact: bool },
+    // Lowest or highest possible value of a roll (None if it has none):
+    Theoretical { value: Option<i64>, maximum: bool },
+    Seeded { seed: u64, result: Box<RollResult> },
+    Compact(Box<RollResult>),
+}
+
+impl RollResult {
+    /// Report of the roll of the given expression, shown in plain text.
+    pub fn report<'a>(&'a self, expression: &'a str) -> Report<'a> {
+        Report {
+            expression,
+            total: self.total(),
+            shown: styled(Style::plain(), || self.to_string()),
+            truly_random: self.is_truly_random(),
+            result: self,
+        }
+    }
+
+    // Final value of the roll, if it could be made:
+    pub fn total(&self) -> Option<i64> {
+        match self {
+            RollResult::Dice(result) => Some(result.total()),
+            RollResult::Function { results, selected, bonus, .. } => Some(results[*selected].value() + bonus),
+            RollResult::Divide { rounding, dividend, divisor, bonus } => {
+                if divisor.value() == 0 {
+                    return None;
+                }
+                let quotient = dividend.value() as f64 / divisor.value() as f64;
+                let rounded = match rounding {
+                    Rounding::Floor => quotient.floor(),
+                    Rounding::Ceil => quotient.ceil(),
+                    Rounding::Round => quotient.round(),
+                };
+                Some(rounded as i64 + bonus)
+            },
+            RollResult::Nested { outer, .. } => outer.as_ref().ok().map(DiceResult::total),
+            RollResult::Versus { result, .. } | RollResult::Under { result, .. } | RollResult::Seeded { result, .. } | RollResult::Compact(result) => result.total(),
+            // Symbols are not numbers:
+            RollResult::Symbolic { .. } | RollResult::Average { .. } | RollResult::Theoretical { .. } => None,
+            RollResult::Wild { trait_die, wild_die, bonus, .. } => Some(trait_die.iter().sum::<i64>().max(wild_die.iter().sum()) + bonus),
+            RollResult::Sum(results) => Some(results.iter().map(|(negative, result)| {
+                if *negative { -result.value() } else { result.value() }
+            }).sum()),
+        }
+    }
+
+    /// Results of every set of dice making up the roll, in the order they were rolled.
+    ///
+    /// Constants, symbolic dice and Savage Worlds trait and wild dice are not included.
+    pub fn individuals(&self) -> Vec<&DiceResult> {
+        match self {
+            RollResult::Dice(result) => vec![result],
+            RollResult::Function { results, .. } => results.iter().filter_map(ArgumentResult::dice).collect(),
+            RollResult::Divide { dividend, divisor, .. } => [dividend, divisor].into_iter().filter_map(ArgumentResult::dice).collect(),
+            RollResult::Nested { inner, outer, .. } => std::iter::once(inner).chain(outer.as_ref().ok()).collect(),
+            RollResult::Versus { result, .. } | RollResult::Under { result, .. } | RollResult::Seeded { result, .. } | RollResult::Compact(result) => result.individuals(),
+            RollResult::Sum(results) => results.iter().filter_map(|(_, result)| result.dice()).collect(),
+            RollResult::Symbolic { .. } | RollResult::Wild { .. } | RollResult::Average { .. } | RollResult::Theoretical { .. } => Vec::new(),
+        }
+    }
+
+    /// Whether every die of the roll was rolled by RANDOM.ORG, rather than by the fallback PRNG.
+    pub fn is_truly_random(&self) -> bool {
+        match self {
+            RollResult::Symbolic { truly_random, .. } | RollResult::Wild { truly_random, .. } => *truly_random,
+            RollResult::Seeded { .. } => false,
+            RollResult::Compact(result) => result.is_truly_random(),
+            _ => self.individuals().iter().all(|dice| dice.is_truly_random()),
+        }
+    }
+
+    /// Whether the roll succeeded, if it was compared against a difficulty class or target number.
+    pub fn success(&self) -> Option<bool> {
+        match self {
+            RollResult::Versus { result, dc } => Some(result.total()? >= *dc),
+            RollResult::Under { result, target, gurps } => {
+                let total = result.total()?;
+                if *gurps {
+                    if let Some(critical) = gurps_critical(total, *target) {
+                        return Some(critical);
+                    }
+                    if total == 17 {
+                        return Some(false);
+                    }
+                }
+                Some(total <= *target)
+            },
+            RollResult::Seeded { result, .. } | RollResult::Compact(result) => result.success(),
+            _ => None,
+        }
+    }
+
+    /// Numeric value of the roll, if it has one: its total, or that of a roll that is no actual roll,
+    /// e.g.: 6.5 for [avg 2d6] or 12 for [max 2d6].
+    pub fn value(&self) -> Option<f64> {
+        match self {
+            RollResult::Average { value, .. } => *value,
+            RollResult::Theoretical { value, .. } => value.map(|value| value as f64),
+            RollResult::Versus { result, .. } | RollResult::Under { result, .. } | RollResult::Seeded { result, .. } | RollResult::Compact(result) => result.value(),
+            _ => self.total().map(|total| total as f64),
+        }
+    }
+
+    /// Whether the roll meets the given difficulty class (ties do), with the verdict shown for it,
+    /// e.g.: "— **Success** (beat DC 15 by 4)". Fractional values are rounded down first, saying so.
+    /// None for rolls without a numeric value.
+    pub fn against(&self, dc: i64) -> Option<(bool, String)> {
+        let value = self.value()?;
+        let total = value.floor() as i64;
+        let margin = total.saturating_sub(dc);
+        let verdict = match margin.cmp(&0) {
+            Ordering::Greater => format!("beat DC {} by {}", dc, margin),
+            Ordering::Equal => format!("met DC {} exactly", dc),
+            Ordering::Less => format!("missed DC {} by {}", dc, margin.saturating_neg()),
+        };
+        let verdict = if value.fract() == 0.0 { verdict } else { format!("{} rounded down to {}, {}", format_average(value), total, verdict) };
+        let success = margin >= 0;
+        Some((success, format!("— **{}** ({})", if success { "Success" } else { "Failure" }, verdict)))
+    }
+
+    /// Value of the lone d20 the roll is made of, and the modifier added to it, e.g.: (14, 5) for
+    /// [1d20+5]. None for any other roll, as only such rolls can be made with advantage afterwards.
+    pub fn lone_d20(&self) -> Option<(i64, i64)> {
+        let additive = match self {
+            RollResult::Dice(_) => true,
+            RollResult::Sum(terms) => terms.iter().all(|(subtracted, term)| !subtracted || matches!(term, ArgumentResult::Constant(_))),
+            RollResult::Compact(result) => return result.lone_d20(),
+            _ => false,
+        };
+        match self.individuals()[..] {
+            [dice] if additive && dice.sides == 20 && dice.seq.len() == 1 && matches!(dice.outcome, Outcome::Sum) => Some((dice.seq[0], self.total()? - dice.seq[0])),
+            _ => None,
+        }
+    }
+
+    /// Roll a second d20 for a lone d20 (see [`RollResult::lone_d20`]), keeping the higher of both
+    /// with advantage or the lower with disadvantage, e.g.: "~~7~~, 12 + 5 = 17".
+    pub async fn advantage(first: i64, modifier: i64, advantage: bool, truly_random: bool) -> RollResult {
+        RollResult::advantage_with(first, modifier, advantage, truly_random, &mut RandomOrg).await
+    }
+
+    // Same as advantage, but taking the second d20 from the given roller:
+    pub async fn advantage_with<R: Roller>(first: i64, modifier: i64, advantage: bool, truly_random: bool, roller: &mut R) -> RollResult {
+        let (second, second_truly_random) = roller.gen(1, 1, 20).await;
+        let second = second[0];
+        let keep_first = if advantage { first >= second } else { first <= second };
+        RollResult::Dice(DiceResult {
+            sides: 20,
+            seq: vec![first, second],
+            outcome: Outcome::Kept(vec![keep_first, !keep_first]),
+            bonus: modifier,
+            truly_random: truly_random && second_truly_random,
+        })
+    }
+
+    /// Whether only the result of the roll is shown: when asked to, or when there are too many dice
+    /// to read through (as long as there is a result to show instead).
+    pub fn is_compact(&self) -> bool {
+        matches!(self, RollResult::Compact(_))
+            || (self.total().is_some() && self.individuals().iter().any(|dice| dice.rolls().len() > compact_dice()))
+    }
+
+    // Show only the result of the roll, e.g.: "**140**"
+    fn fmt_compact(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RollResult::Compact(result) => result.fmt_compact(f),
+            RollResult::Seeded { seed, result } => {
+                write!(f, "Seeded roll (seed {}; not random, not for actual play): ", seed)?;
+                result.fmt_compact(f)
+            },
+            _ => match self.total() {
+                Some(total) => {
+                    write!(f, "{}", bold(f.alternate(), total))?;
+                    if let Some(note) = randomorg::note(self.is_truly_random()) {
+                        write!(f, " {}", note)?;
+                    }
+                    Ok(())
+                },
+                // Nothing to sum up, e.g.: symbols, which are shown as usual:
+                None => fmt::Display::fmt(self, f),
+            },
+        }
+    }
+}
+
+// The alternate form ("{:#}") is meant for chat, and shows the final result of the roll in bold:
+impl fmt::Display for RollResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_compact() {
+            return self.fmt_compact(f);
+        }
+
+        let markdown = f.alternate();
+        match self {
+            RollResult::Dice(result) => result.fmt(f),
+            RollResult::Function { function, results, selected, bonus } => {
+                let args = results.iter().enumerate().map(|(i, result)| {
+                    if i == *selected { format!("{} ✓", result) } else { result.to_string() }
+                }).collect::<Vec<String>>();
+                write!(f, "{}({})", function, args.join(" | "))?;
+
+                let total = bold(markdown, results[*selected].value() + bonus);
+                if *bonus != 0 {
+                    write!(f, "{} = {}", show_bonus(*bonus), total)
+                } else {
+                    write!(f, " = {}", total)
+                }
+            },
+            RollResult::Divide { rounding, dividend, divisor, bonus } => {
+                let show = |result: &ArgumentResult| match result {
+                    ArgumentResult::Dice(result) => format!("({})", result),
+                    ArgumentResult::Constant(value) => value.to_string(),
+                };
+                let rounding = match rounding {
+                    Rounding::Floor => "floor",
+                    Rounding::Ceil => "ceil",
+                    Rounding::Round => "round",
+                };
+                write!(f, "{}({} / {})", rounding, show(dividend), show(divisor))?;
+                if *bonus != 0 {
+                    write!(f, "{}", show_bonus(*bonus))?;
+                }
+                match self.total() {
+                    Some(total) => write!(f, " = {}", bold(markdown, total)),
+                    // The divisor was rolled as a zero:
+                    None => write!(f, " — {}", DiceError::DivisionByZero(Span::default())),
+                }
+            },
+            RollResult::Nested { inner, capped, outer } => {
+                if *capped {
+                    write!(f, "({}, capped to {}) ⇒ ", inner, max_dice())?;
+                } else {
+                    write!(f, "({}) ⇒ ", inner)?;
+                }
+                match outer {
+                    Ok(outer) => outer.fmt(f),
+                    Err(why) => write!(f, "{}", why),
+                }
+            },
+            RollResult::Sum(results) => {
+                for (i, (negative, result)) in results.iter().enumerate() {
+                    match (i, negative) {
+                        (0, false) => {},
+                        (0, true) => write!(f, "-")?,
+                        (_, false) => write!(f, " + ")?,
+                        (_, true) => write!(f, " - ")?,
+                    }
+                    match result {
+                        ArgumentResult::Dice(result) => write!(f, "({})", result)?,
+                        ArgumentResult::Constant(value) => write!(f, "{}", value)?,
+                    }
+                }
+
+                // A lone number is its own result, e.g.: [-2]
+                if results.len() > 1 || matches!(results[0].1, ArgumentResult::Dice(_)) {
+                    write!(f, " = {}", bold(markdown, self.total().expect("No sum?")))?;
+                }
+                Ok(())
+            },
+            RollResult::Versus { result, dc } => {
+                result.fmt(f)?;
+                // Meeting the DC is enough to succeed:
+                match result.total() {
+                    Some(total) if total >= *dc => {
+                        write!(f, " — Success!")?;
+                        // Savage Worlds counts a raise for every 4 points over the target number:
+                        let raises = (total - dc) / 4;
+                        if matches!(**result, RollResult::Wild { .. }) && raises > 0 {
+                            write!(f, " ({} {})", raises, if raises == 1 { "raise" } else { "raises" })?;
+                        }
+                        Ok(())
+                    },
+                    Some(_) => write!(f, " — Failure."),
+                    None => Ok(()),
+                }
+            },
+            RollResult::Average { value: Some(value), exact: true } => write!(f, "Average: {} (this is not a roll)", format_average(*value)),
+            RollResult::Average { value: Some(value), exact: false } => write!(f, "Estimated average: ≈ {} (this is not a roll)", format_average(*value)),
+            RollResult::Average { value: None, .. } => write!(f, "Symbols have no average, traveller. Only numerals do!"),
+            RollResult::Theoretical { value: Some(value), maximum: true } => write!(f, "Theoretical maximum: {} (this is not a roll)", value),
+            RollResult::Theoretical { value: Some(value), maximum: false } => write!(f, "Theoretical minimum: {} (this is not a roll)", value),
+            RollResult::Theoretical { value: None, .. } => write!(f, "Symbols have no bounds, traveller. Only numerals do!"),
+            RollResult::Seeded { seed, result } => {
+                write!(f, "Seeded roll (seed {}; not random, not for actual play): ", seed)?;
+                result.fmt(f)
+            },
+            // Compact rolls are shown above:
+            RollResult::Compact(result) => result.fmt_compact(f),
+            RollResult::Symbolic { shown, faces, truly_random } => {
+                write!(f, "{}", shown.iter().map(|&face| faces[face]).collect::<Vec<&str>>().join(", "))?;
+
+                // Tally of each symbol, in order of first appearance:
+                let mut tally: Vec<(&str, usize)> = Vec::new();
+                for &face in shown {
+                    match tally.iter_mut().find(|(symbol, _)| *symbol == faces[face]) {
+                        Some((_, count)) => *count += 1,
+                        None => tally.push((faces[face], 1)),
+                    }
+                }
+                let tally = tally.iter().map(|(symbol, count)| format!("{}× {}", count, symbol)).collect::<Vec<String>>();
+                write!(f, " → {}", tally.join(", "))?;
+
+                if let Some(note) = randomorg::note(*truly_random) {
+                    write!(f, " {}", note)?;
+                }
+                Ok(())
+            },
+            RollResult::Under { result, target, gurps } => {
+                result.fmt(f)?;
+                let Some(total) = result.total() else {
+                    return Ok(());
+                };
+                let margin = (target - total).abs();
+
+                if *gurps {
+                    match gurps_critical(total, *target) {
+                        Some(true) => return write!(f, " — Critical success! (by {})", margin),
+                        Some(false) => return write!(f, " — Critical failure! (by {})", margin),
+                        None => {},
+                    }
+                    if total == 17 {
+                        return write!(f, " — Failure. (by {})", margin);
+                    }
+                }
+
+                // Rolling the target number exactly is enough to succeed:
+                if total <= *target {
+                    write!(f, " — Success! (by {})", margin)
+                } else {
+                    write!(f, " — Failure. (by {})", margin)
+                }
+            },
+            RollResult::Wild { trait_die, wild_die, bonus, truly_random } => {
+                let kept = trait_die.iter().sum::<i64>().max(wild_die.iter().sum());
+                if *bonus != 0 {
+                    write!(f, "trait: {} | wild: {} → {}", join_rolls(trait_die), join_rolls(wild_die), kept)?;
+                    write!(f, "{} = {}", show_bonus(*bonus), bold(markdown, self.total().expect("No wild total?")))?;
+                } else {
+                    write!(f, "trait: {} | wild: {} → {}", join_rolls(trait_die), join_rolls(wild_die), bold(markdown, kept))?;
+                }
+                if exploded_too_much(trait_die) || exploded_too_much(wild_die) {
+                    write!(f, " {}", EXPLOSIONS_CAPPED)?;
+                }
+                if let Some(note) = randomorg::note(*truly_random) {
+                    write!(f, " {}", note)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+// Operation applied to the dice after rolling them:
+#[derive(Clone)]
+pub enum DieKind {
+    // Sum all the dice, e.g.: [3d6]
+    Regular,
+    // Keep only the middle dice, e.g.: [3d20km1]
+    KeepMiddle(i64),
+    // Drop the lowest dice, e.g.: 4d6 dropping the lowest 1 for ability scores
+    Drop(i64),
+    // Count the faces that show up at least the given amount of times, e.g.: [10d6m] [10d6m3]
+    Match(i64),
+    // Count the hits (5 or more) of a Shadowrun pool, watching out for glitches, e.g.: [12d6sr]
+    Shadowrun,
+    // Count the successes (8 or more) of a World of Darkness pool, rerolling dice that show at least
+    // the given value, e.g.: [7d10wod] (10-again) [7d109a] (9-again)
+    Again(i64),
+    // Drop the highest dice, e.g.: 2d20 keeping the lowest 1 for disadvantage in Roll20's notation
+    DropHighest(i64),
+    // Sum all the dice, rolling another one for every die that shows at least the given value, e.g.:
+    // [8d6!] in Roll20's notation
+    Explode(i64),
+    // Same as Explode, but adding the extra dice to the one that exploded, e.g.: [4d6!!] in Roll20's notation
+    Compound(i64),
+    // Sum all the dice, rerolling once those that show at most the given value, e.g.: [1d20ro<2] in
+    // Roll20's notation, [1d20r<3] in Foundry VTT's
+    RerollOnce(i64),
+    // Sum all the dice, counting those below the given value as that value, e.g.: [4d6min3]
+    LowerBound(i64),
+    // Sum all the dice, counting those above the given value as that value, e.g.: [4d6max4]
+    UpperBound(i64),
+    // Sum dice that show -1, 0 or +1, e.g.: [4dF]
+    Fudge,
+    // Sum all the dice as the STUN of Hero System's normal damage, also counting their BODY, e.g.: [5d6h]
+    HeroStun,
+    // Count the BODY of Hero System's normal damage: none for a 1, 2 for the highest face and 1 for
+    // the rest, e.g.: [5d6b]
+    HeroBody,
+}
+
+impl DieKind {
+    /// What is done with the dice once rolled, in plain words, e.g.: "keep the middle 1 of them".
+    pub fn describe(&self) -> String {
+        match self {
+            DieKind::Regular => "add them up".to_string(),
+            DieKind::KeepMiddle(keep) => format!("keep the middle {} of them and add those up", keep),
+            DieKind::Drop(drop) => format!("drop the lowest {} of them and add up the rest", drop),
+            DieKind::Match(at_least) => format!("count the faces that show up at least {} times", at_least),
+            DieKind::Shadowrun => "count the hits (5 or more) of the Shadowrun pool, watching out for glitches".to_string(),
+            DieKind::Again(again) => format!("count the successes (8 or more) of the World of Darkness pool, rolling again every die that shows {} or more", again),
+            DieKind::DropHighest(drop) => format!("drop the highest {} of them and add up the rest", drop),
+            DieKind::Explode(at_least) => format!("add them up, rolling one more die for every die that shows {} or more", at_least),
+            DieKind::Compound(at_least) => format!("add them up, rolling again every die that shows {} or more and adding it to that die", at_least),
+            DieKind::RerollOnce(at_most) => format!("reroll once every die that shows {} or less, and add them up", at_most),
+            DieKind::LowerBound(min) => format!("add them up, counting every die below {} as {}", min, min),
+            DieKind::UpperBound(max) => format!("add them up, counting every die above {} as {}", max, max),
+            DieKind::Fudge => "add them up".to_string(),
+            DieKind::HeroStun => "add them up as STUN, counting their BODY too (none for a 1, 2 for the highest face, 1 otherwise)".to_string(),
+            DieKind::HeroBody => "count their BODY (none for a 1, 2 for the highest face, 1 otherwise)".to_string(),
+        }
+    }
+
+    // Value from which dice are rolled again, for those that keep rolling:
+    fn explodes_at(&self) -> Option<i64> {
+        match self {
+            DieKind::Again(at_least) | DieKind::Explode(at_least) | DieKind::Compound(at_least) => Some(*at_least),
+            _ => None,
+        }
+    }
+}
+
+// Order in which to show the rolled dice:
+#[derive(Clone)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+// A single group of dice with a bonus, e.g.: [4d6km2sa+3]
+#[derive(Clone)]
+pub struct Dice {
+    amount: i64,
+    sides: i64,
+    kind: DieKind,
+    sort: Option<SortOrder>,
+    bonus: i64,
+    written: Written,
+}
+
+// Where each part of some dice was written, so errors can point at it, e.g.: the "km3" of [2d6km3].
+// Parts that were left out point at the die instead, e.g.: at the "d" of [d6] for its amount:
+#[derive(Clone, Copy, Default)]
+struct Written {
+    dice: Span,
+    amount: Span,
+    sides: Span,
+    operation: Span,
+    bonus: Span,
+}
+
+impl Dice {
+    // Dice without bonus nor sorting, for rolls that are not parsed from an expression:
+    pub fn new(amount: i64, sides: i64, kind: DieKind) -> Result<Dice, DiceError> {
+        Dice { amount, sides, kind, sort: None, bonus: 0, written: Written::default() }.validate()
+    }
+
+    // Parse dice in MapTool's notation, or with the aliases of Foundry VTT, e.g.: "2d20+5" "3d" "d40"
+    // "4d6km2sa - 1", taking the given amount instead of a written one (e.g.: for the "d6" of [(1d4)d6]).
+    // Returns None if the tokens are not dice at all:
+    fn parse(tokens: &[Token], amount: Option<(i64, Span)>) -> Option<Result<Dice, DiceError>> {
+        Dice::read(tokens, amount, true).or_else(|| Dice::not_supported(tokens))
+    }
+
+    // Read dice in MapTool's notation, followed by a bonus only if it may be, e.g.: not within a sum:
+    fn read(tokens: &[Token], amount: Option<(i64, Span)>, bonused: bool) -> Option<Result<Dice, DiceError>> {
+        let mut scanner = Scanner::new(tokens);
+        let written_amount = if amount.is_none() { scanner.digits() } else { None };
+        let die = scanner.eat("d")?;
+        let mut sides = scanner.digits();
+        // The last digits of the size may be those of a World of Darkness "again", e.g.: the "9" of
+        // "7d109a" (d10s, 9-again), the size being 20 if no digits are left for it:
+        let mut again = None;
+        if let Some(digits) = sides.filter(|_| scanner.rest().starts_with('a')) {
+            let size = scanner.text(digits);
+            let length = if size.ends_with(['8', '9']) { 1 } else if size.ends_with("10") { 2 } else { 0 };
+            if length > 0 {
+                let split = digits.end - length;
+                sides = (split > digits.start).then(|| Span::new(digits.start, split));
+                again = scanner.eat("a").map(|a| Span::new(split, a.end));
+            }
+        }
+        let start = scanner.position();
+        let operation = match again {
+            Some(_) => None,
+            None => DICE_OPS.iter().find_map(|&(name, digits)| {
+                scanner.eat(name)?;
+                let argument = match digits {
+                    Digits::None => None,
+                    Digits::One => scanner.eat("1"),
+                    Digits::Optional | Digits::Required => scanner.digits(),
+                };
+                if digits == Digits::Required && argument.is_none() {
+                    scanner.reset(start);
+                    return None;
+                }
+                Some((name, argument))
+            }),
+        };
+        let operation_span = again.unwrap_or(scanner.since(start));
+        let sort = if scanner.eat("sa").is_some() {
+            Some(SortOrder::Ascending)
+        } else if scanner.eat("sd").is_some() {
+            Some(SortOrder::Descending)
+        } else {
+            None
+        };
+        let bonus = if bonused { scanner.bonus(false) } else { None };
+        if !scanner.is_done() {
+            return None;
+        }
+
+        let written = Written {
+            dice: span(tokens),
+            amount: amount.map(|(_, span)| span).or(written_amount).unwrap_or(die),
+            sides: sides.unwrap_or(die),
+            operation: operation_span,
+            bonus: bonus.map_or(Span::default(), |(_, _, span)| span),
+        };
+        let numbers = || -> Result<Dice, DiceError> {
+            let amount = match (amount, written_amount) {
+                (Some((amount, _)), _) => amount,
+                (None, Some(digits)) => scanner.number(digits)?,
+                (None, None) => 1,
+            };
+            let sides = sides.map_or(Ok(20), |digits| scanner.number(digits))?;
+            let bonus = match bonus {
+                // A subtracted bonus is read along with its sign, e.g.: "-5" of [2d6 - 5]
+                Some((true, digits, _)) => read_number(&format!("-{}", scanner.text(digits)), digits)?,
+                Some((false, digits, _)) => scanner.number(digits)?,
+                None => 0,
+            };
+            let argument = operation.and_then(|(_, argument)| argument).map(|digits| scanner.number(digits)).transpose()?;
+            // Keeping one die unless told how many, e.g.: [2d20kh]
+            let dropped = |kind: fn(i64) -> DieKind| match amount.checked_sub(argument.unwrap_or(1)) {
+                Some(drop) if drop >= 0 => Ok(kind(drop)),
+                _ => Err(DiceError::KeepTooMany(operation_span)),
+            };
+            let counted = || argument.expect("No argument?");
+            let kind = match (operation.map(|(name, _)| name), again) {
+                (_, Some(again)) => DieKind::Again(match scanner.text(again) {
+                    "9a" => 9,
+                    "8a" => 8,
+                    _ => 10,
+                }),
+                (Some("km"), _) => DieKind::KeepMiddle(counted()),
+                (Some("kh" | "k"), _) => dropped(DieKind::Drop)?,
+                (Some("kl"), _) => dropped(DieKind::DropHighest)?,
+                (Some("dh"), _) => DieKind::DropHighest(counted()),
+                (Some("d"), _) => DieKind::Drop(counted()),
+                (Some("min"), _) => DieKind::LowerBound(counted()),
+                (Some("max"), _) => DieKind::UpperBound(counted()),
+                // Matches need at least a pair of equal faces by default:
+                (Some("m"), _) => DieKind::Match(argument.unwrap_or(2)),
+                (Some("x" | "e"), _) => DieKind::Explode(sides),
+                (Some("h"), _) => DieKind::HeroStun,
+                (Some("b"), _) => DieKind::HeroBody,
+                (Some("r<"), _) => DieKind::RerollOnce(counted() - 1),
+                (Some("r"), _) => DieKind::RerollOnce(1),
+                (Some("sr"), _) => DieKind::Shadowrun,
+                (Some("wod"), _) => DieKind::Again(10),
+                _ => DieKind::Regular,
+            };
+            Ok(Dice { amount, sides, kind, sort, bonus, written })
+        };
+        Some(numbers().and_then(Dice::validate))
+    }
+
+    // Parse dice in Roll20's notation, with their modifiers but without a bonus, e.g.: "2d20kh1" "8d6!"
+    // "1d20ro<2". Returns None if the tokens are not dice at all. Each modifier is checked as the dice
+    // are, since only one of them (besides sorting) can be applied at once:
+    fn parse_roll20(tokens: &[Token]) -> Option<Result<Dice, DiceError>> {
+        let mut scanner = Scanner::new(tokens);
+        let written_amount = scanner.digits();
+        let die = scanner.eat("d")?;
+        let written_sides = scanner.digits()?;
+
+        let amount = match written_amount.map(|digits| scanner.parse(digits)) {
+            Some(Ok(amount)) => amount,
+            Some(Err(why)) => return Some(Err(why)),
+            None => 1,
+        };
+        let sides = match scanner.parse(written_sides) {
+            Ok(sides) => sides,
+            Err(why) => return Some(Err(why)),
+        };
+
+        let mut kind = None;
+        let mut sort = None;
+        let mut operation = Span::default();
+        while !scanner.is_done() {
+            let start = scanner.position();
+            let modified = if scanner.eat("!").is_some() {
+                let compound = scanner.eat("!").is_some();
+                let at_least = match scanner.eat(">").and_then(|_| scanner.digits()) {
+                    Some(digits) => match scanner.parse(digits) {
+                        Ok(at_least) => at_least,
+                        Err(why) => return Some(Err(why)),
+                    },
+                    None => {
+                        scanner.reset(start + if compound { 2 } else { 1 });
+                        sides
+                    },
+                };
+                // Dice that always explode would never stop:
+                if at_least < 2 {
+                    let token = scanner.since(start);
+                    return Some(Err(DiceError::Unsupported(scanner.text(token).to_owned(), token)));
+                }
+                Some(if compound { DieKind::Compound(at_least) } else { DieKind::Explode(at_least) })
+            } else if let Some(digits) = scanner.eat("ro<").and_then(|_| scanner.digits()) {
+                match scanner.parse(digits) {
+                    Ok(at_most) => Some(DieKind::RerollOnce(at_most)),
+                    Err(why) => return Some(Err(why)),
+                }
+            } else if let Some(keep) = scanner.reset(start).eat_first(&["kh", "kl", "dh", "dl", "k", "d"]) {
+                let count = scanner.digits();
+                // Only "kh" and "kl" may leave out how many, e.g.: not the "k" of "km1":
+                if keep.len() == 1 && count.is_none() {
+                    let rest = scanner.rest_from(start);
+                    return Some(Err(DiceError::Unsupported(scanner.text(rest).to_owned(), rest)));
+                }
+                let count = match count.map_or(Ok(1), |digits| scanner.parse(digits)) {
+                    Ok(count) if count > amount => return Some(Err(DiceError::KeepTooMany(scanner.since(start)))),
+                    Ok(count) => count,
+                    Err(why) => return Some(Err(why)),
+                };
+                Some(match keep {
+                    "k" | "kh" => DieKind::Drop(amount - count),
+                    "kl" => DieKind::DropHighest(amount - count),
+                    "dh" => DieKind::DropHighest(count),
+                    _ => DieKind::Drop(count),
+                })
+            } else if scanner.eat("s").is_some() {
+                sort = Some(if scanner.eat("d").is_some() {
+                    SortOrder::Descending
+                } else {
+                    scanner.eat("a");
+                    SortOrder::Ascending
+                });
+                None
+            } else {
+                let rest = scanner.rest_from(start);
+                return Some(Err(DiceError::Unsupported(scanner.text(rest).to_owned(), rest)));
+            };
+            // Modifiers cannot be combined (e.g.: exploding dice, keeping the highest of them):
+            if let Some(modified) = modified {
+                let token = scanner.since(start);
+                if kind.is_some() {
+                    return Some(Err(DiceError::Unsupported(scanner.text(token).to_owned(), token)));
+                }
+                kind = Some(modified);
+                operation = token;
+            }
+        }
+
+        let written = Written { dice: span(tokens), amount: written_amount.unwrap_or(die), sides: written_sides, operation, bonus: Span::default() };
+        Some(Ok(Dice { amount, sides, kind: kind.unwrap_or(DieKind::Regular), sort, bonus: 0, written }))
+    }
+
+    // Point out the operations of MapTool that are known but not rolled here, e.g.: "o" in [5d10o], or
+    // "U" in [3dU]. Anything else is no roll at all:
+    fn not_supported(tokens: &[Token]) -> Option<Result<Dice, DiceError>> {
+        let mut scanner = Scanner::new(tokens);
+        scanner.digits();
+        scanner.eat("d")?;
+        let mut start = scanner.position();
+        if scanner.eat_first(&["u", "U"]).is_none() {
+            scanner.digits()?;
+            start = scanner.position();
+            if scanner.eat("o").is_none() {
+                let counted = scanner.eat_first(&["es", "s", "rk", "rc"]).is_some();
+                if !counted {
+                    scanner.eat("r")?;
+                }
+                let digits = scanner.digits()?;
+                // Rerolling the ones is rolled, e.g.: [4d6r1]
+                if !counted && matches!(scanner.text(digits), "0" | "1") {
+                    return None;
+                }
+            }
+        }
+        let operation = scanner.since(start);
+        scanner.eat_first(&["sa", "sd"]);
+        scanner.bonus(true);
+        if !scanner.is_done() {
+            return None;
+        }
+        Some(Err(DiceError::NotSupported(scanner.text(operation).to_owned(), operation)))
+    }
+
+    // Check that the roll can be (sensibly) made:
+    fn validate(self) -> Result<Dice, DiceError> {
+        let Dice { amount: rolls, sides: size, bonus, written, .. } = self;
+
+        match self.kind {
+            DieKind::KeepMiddle(keep) | DieKind::Drop(keep) | DieKind::DropHighest(keep) if keep > rolls => return Err(DiceError::KeepTooMany(written.operation)),
+            DieKind::Match(at_least) if at_least < 2 => return Err(DiceError::MatchTooFew(written.operation)),
+            DieKind::Match(at_least) if at_least > rolls => return Err(DiceError::MatchTooMany(written.operation)),
+            _ => {},
+        }
+
+        if matches!(self.kind, DieKind::Again(_)) && size != 10 {
+            return Err(DiceError::AgainNotD10(written.sides));
+        }
+
+        if size > 1 && rolls > 0 {
+            // Arbitrary limits check, so only reasonable amounts of numbers of reasonable size are returned:
+            if rolls > max_dice() {
+                return Err(DiceError::TooManyDice(written.amount));
+            }
+            if size > max_sides() {
+                return Err(DiceError::TooManySides(written.sides));
+            }
+            if bonus > rolls * size * 10 {
+                return Err(DiceError::BonusTooLarge(written.bonus));
+            }
+        } else {
+            // Smug answer for d1s, d0s, and 0 rolls:
+            if rolls > 1_000_000_000 || size > 1_000_000_000 || bonus > 1_000_000_000 {
+                return Err(DiceError::Jest(written.dice));
+            }
+            let value = match self.kind {
+                DieKind::KeepMiddle(keep) => keep * size + bonus,
+                DieKind::Drop(drop) | DieKind::DropHighest(drop) => (rolls - drop) * size + bonus,
+                DieKind::Match(_) => (size == 1 && rolls > 0) as i64 + bonus,
+                DieKind::Shadowrun | DieKind::Again(_) => bonus,
+                DieKind::Explode(_) | DieKind::Compound(_) | DieKind::RerollOnce(_) => rolls * size + bonus,
+                DieKind::LowerBound(min) => rolls * size.max(min) + bonus,
+                DieKind::UpperBound(max) => rolls * size.min(max) + bonus,
+                DieKind::Regular | DieKind::HeroStun => rolls * size + bonus,
+                DieKind::Fudge | DieKind::HeroBody => bonus,
+            };
+            return Err(DiceError::Trivial(value, written.dice));
+        }
+
+        Ok(self)
+    }
+
+    pub async fn roll(&self) -> DiceResult {
+        self.roll_with(&mut RandomOrg).await
+    }
+
+    /// Same as roll, but rolling right away with the given generator: without RANDOM.ORG nor an
+    /// async runtime. The result is always flagged as pseudo-random.
+    pub fn roll_local<G: Rng + Send>(&self, rng: &mut G) -> DiceResult {
+        finish_local(self.roll_with(&mut Local(rng)))
+    }
+
+    // Same as roll, but taking the random numbers from the given roller:
+    pub async fn roll_with<R: Roller>(&self, roller: &mut R) -> DiceResult {
+        roller.rolling(&self.kind);
+        let (seq, mut truly_random) = roller.gen(self.amount, 1, self.sides).await;
+
+        // Dice that are rolled again, with all their rerolls:
+        let mut chains = Vec::new();
+        if let Some(at_least) = self.kind.explodes_at() {
+            for &die in &seq {
+                let (chain, chain_truly_random) = explode_chain(die, self.sides, at_least, roller).await;
+                truly_random &= chain_truly_random;
+                chains.push(chain);
+            }
+        }
+        if let DieKind::RerollOnce(at_most) = self.kind {
+            for &die in &seq {
+                let mut chain = vec![die];
+                if die <= at_most {
+                    let (reroll, reroll_truly_random) = roller.gen(1, 1, self.sides).await;
+                    truly_random &= reroll_truly_random;
+                    chain.extend(reroll);
+                }
+                chains.push(chain);
+            }
+        }
+
+        self.result(seq, chains, truly_random)
+    }
+
+    // Roll the dice locally, with the given function rolling each die, only to know the value they would yield:
+    fn simulate(&self, roll_die: &mut dyn FnMut(i64) -> i64) -> i64 {
+        let seq = (0..self.amount).map(|_| roll_die(self.sides)).collect::<Vec<i64>>();
+
+        let mut chains = Vec::new();
+        if let Some(at_least) = self.kind.explodes_at() {
+            for &die in &seq {
+                chains.push(explode_chain_local(die, self.sides, at_least, roll_die));
+            }
+        }
+        if let DieKind::RerollOnce(at_most) = self.kind {
+            for &die in &seq {
+                chains.push(if die <= at_most { vec![die, roll_die(self.sides)] } else { vec![die] });
+            }
+        }
+
+        self.result(seq, chains, false).total()
+    }
+
+    // Lowest and highest values the dice can yield:
+    fn bounds(&self) -> (i64, i64) {
+        match self.kind {
+            // No face needs to match while every face can show up too few times to; otherwise, all
+            // the spare dice can go to a single face. At most, every face matches, or every group of
+            // dice big enough to:
+            DieKind::Match(at_least) => {
+                let at_least = at_least.max(1);
+                let lowest = if self.amount <= self.sides.saturating_mul(at_least - 1) { 0 } else { 1 };
+                (lowest + self.bonus, self.sides.min(self.amount / at_least) + self.bonus)
+            },
+            // The rest yield more the higher their faces, so either bound is every die at its lowest
+            // or highest face:
+            _ => (self.simulate(&mut |_| 1), self.simulate(&mut |sides| sides)),
+        }
+    }
+
+    // Exact chances of every total of plain dice, if they can be reckoned within the budget:
+    fn chances(&self, budget: &mut u64) -> Option<BTreeMap<i64, f64>> {
+        let DieKind::Regular = self.kind else {
+            return None;
+        };
+        let die = (1..=self.sides).map(|face| (face, 1.0 / self.sides as f64)).collect::<BTreeMap<_, _>>();
+        (0..self.amount).try_fold(BTreeMap::from([(self.bonus, 1.0)]), |chances, _| convolve(&chances, &die, budget))
+    }
+
+    /// What the dice do, in plain words, e.g.: "roll 4 dice of 6 sides and keep the middle 2 of them and add
+    /// those up, then add 3".
+    pub fn describe(&self) -> String {
+        let mut text = match (self.amount, &self.kind) {
+            (1, DieKind::Regular) => format!("roll 1 die of {} sides", self.sides),
+            (1, DieKind::Fudge) => "roll 1 Fudge die (showing −1, 0 or +1)".to_string(),
+            (amount, DieKind::Fudge) => format!("roll {} Fudge dice (each showing −1, 0 or +1) and add them up", amount),
+            (amount, kind) => format!("roll {} dice of {} sides and {}", amount, self.sides, kind.describe()),
+        };
+        match self.sort {
+            Some(SortOrder::Ascending) => text.push_str(", showing them from lowest to highest"),
+            Some(SortOrder::Descending) => text.push_str(", showing them from highest to lowest"),
+            None => {},
+        }
+        text + &describe_bonus(self.bonus)
+    }
+
+    // Average value of the roll, and whether it is exact or estimated:
+    fn expected(&self) -> (f64, bool) {
+        match self.kind {
+            DieKind::Regular => ((self.amount * (self.sides + 1)) as f64 / 2.0 + self.bonus as f64, true),
+            _ => {
+                let mut rng = StdRng::seed_from_u64(AVERAGE_SEED);
+                let mut roll_die = |sides| rng.gen_range(1..sides+1);
+                let sum = (0..AVERAGE_ITERATIONS).map(|_| i128::from(self.simulate(&mut roll_die))).sum::<i128>();
+                (sum as f64 / AVERAGE_ITERATIONS as f64, false)
+            },
+        }
+    }
+
+    // Apply the operation of the dice to the rolled values (and the chains of rerolls, if any):
+    fn result(&self, mut seq: Vec<i64>, chains: Vec<Vec<i64>>, truly_random: bool) -> DiceResult {
+        let outcome = match self.kind {
+            DieKind::Regular => Outcome::Sum,
+            DieKind::KeepMiddle(keep) => Outcome::Kept(keep_middle(&seq, keep as usize)),
+            DieKind::Drop(drop) => Outcome::Kept(drop_lowest(&seq, drop as usize)),
+            DieKind::Match(at_least) => Outcome::Matches(matching_faces(&seq, at_least as usize)),
+            DieKind::Shadowrun => {
+                let hits = seq.iter().filter(|&&die| die >= 5).count();
+                // More than half of the dice showing 1s is a glitch:
+                let glitch = seq.iter().filter(|&&die| die == 1).count() * 2 > seq.len();
+                Outcome::Hits { hits, glitch }
+            },
+            DieKind::Again(_) => {
+                let successes = chains.iter().flatten().filter(|&&die| die >= 8).count();
+                Outcome::Successes { chains, successes }
+            },
+            DieKind::DropHighest(drop) => Outcome::Kept(drop_highest(&seq, drop as usize)),
+            DieKind::Explode(_) => Outcome::Exploded { chains, compound: false },
+            DieKind::Compound(_) => Outcome::Exploded { chains, compound: true },
+            DieKind::LowerBound(min) => Outcome::Clamped(seq.iter().map(|&die| die.max(min)).collect()),
+            DieKind::UpperBound(max) => Outcome::Clamped(seq.iter().map(|&die| die.min(max)).collect()),
+            // Fudge dice are rolled as d3s, each face standing for -1, 0 or +1:
+            DieKind::Fudge => {
+                seq.iter_mut().for_each(|die| *die -= 2);
+                Outcome::Fudge
+            },
+            DieKind::HeroStun | DieKind::HeroBody => {
+                let body = seq.iter().map(|&die| if die == 1 { 0 } else if die == self.sides { 2 } else { 1 }).sum();
+                Outcome::Hero { body, stun: matches!(self.kind, DieKind::HeroStun) }
+            },
+            // Rerolled dice are shown discarded, right before their rerolls:
+            DieKind::RerollOnce(_) => {
+                let kept = chains.iter().flat_map(|chain| (0..chain.len()).map(move |i| i + 1 == chain.len())).collect();
+                seq = chains.into_iter().flatten().collect();
+                Outcome::Kept(kept)
+            },
+        };
+
+        let mut result = DiceResult { sides: self.sides, seq, outcome, bonus: self.bonus, truly_random };
+        if let Some(order) = &self.sort {
+            result.sort(order);
+        }
+        result
+    }
+}
+
+// Parse a single roll or number, e.g.: "2d6+3" or "-10". Returns None if the tokens are neither:
+fn parse_argument(tokens: &[Token]) -> Option<Result<Argument, DiceError>> {
+    match tokens {
+        [constant] if constant.kind == Kind::Number => Some(number(constant).map(Argument::Constant)),
+        [minus, constant] if minus.is('-') && constant.kind == Kind::Number => {
+            Some(read_number(&text(tokens), span(tokens)).map(Argument::Constant))
+        },
+        _ => Dice::parse(tokens, None).map(|dice| dice.map(Argument::Dice)),
+    }
+}
+
+// Same as parse_argument, but in Roll20's notation, e.g.: "2d20kh1" or "10":
+fn parse_roll20_argument(tokens: &[Token]) -> Option<Result<Argument, DiceError>> {
+    if let [constant] = tokens {
+        if constant.kind == Kind::Number {
+            return Some(number(constant).map(Argument::Constant));
+        }
+    }
+    // A roll of a group may carry its own bonus, e.g.: the "1d20+5" in {1d20+5, 1d20+3}kh1
+    let sign = tokens.iter().position(|token| token.is('+') || token.is('-'));
+    if let Some(sign) = sign.filter(|&sign| !trim(&tokens[..sign]).is_empty()) {
+        let (dice, bonus) = (trim(&tokens[..sign]), trim(&tokens[sign + 1..]));
+        let [bonus] = bonus else {
+            return Some(Err(DiceError::Unsupported(text(tokens), span(tokens))));
+        };
+        if bonus.kind != Kind::Number {
+            return Some(Err(DiceError::Unsupported(text(tokens), span(tokens))));
+        }
+        let (negative, written) = (tokens[sign].is('-'), tokens[sign].span.to(bonus.span));
+        let bonus = match number(bonus) {
+            Ok(bonus) => if negative { -bonus } else { bonus },
+            Err(why) => return Some(Err(why)),
+        };
+        return Dice::parse_roll20(dice).map(|dice| {
+            dice.and_then(|dice| Dice { bonus, written: Written { bonus: written, ..dice.written }, ..dice }.validate()).map(Argument::Dice)
+        });
+    }
+    Dice::parse_roll20(tokens).map(|dice| dice.and_then(Dice::validate).map(Argument::Dice))
+}
+
+// Where some tokens were written, from the first to the last:
+fn span(tokens: &[Token]) -> Span {
+    match tokens {
+        [first, .., last] => first.span.to(last.span),
+        [only] => only.span,
+        [] => Span::default(),
+    }
+}
+
+// Where some parts of a roll were written, from the first to the last, if any were:
+fn span_between(spans: &[Span]) -> Option<Span> {
+    Some(spans.first()?.to(*spans.last()?))
+}
+
+// Text of some tokens, as written:
+fn text(tokens: &[Token]) -> String {
+    tokens.iter().map(|token| token.text).collect()
+}
+
+// Whether the token is whitespace, e.g.: a space or a tab:
+fn is_blank(token: &Token) -> bool {
+    token.kind == Kind::Space || token.text.chars().all(char::is_whitespace)
+}
+
+// Tokens without the whitespace they start and end with, if any:
+fn trim<'a>(tokens: &'a [Token<'a>]) -> &'a [Token<'a>] {
+    let start = tokens.iter().position(|token| !is_blank(token)).unwrap_or(tokens.len());
+    let end = tokens.iter().rposition(|token| !is_blank(token)).map_or(start, |end| end + 1);
+    &tokens[start..end]
+}
+
+// Tokens without the single space they start with, if any:
+fn skip_space<'a>(tokens: &'a [Token<'a>]) -> &'a [Token<'a>] {
+    match tokens {
+        [space, rest @ ..] if space.kind == Kind::Space => rest,
+        _ => tokens,
+    }
+}
+
+// Tokens without the single space they end with, if any:
+fn skip_trailing_space<'a>(tokens: &'a [Token<'a>]) -> &'a [Token<'a>] {
+    match tokens {
+        [rest @ .., space] if space.kind == Kind::Space => rest,
+        _ => tokens,
+    }
+}
+
+// Whatever follows the given word and a space, if anything does, e.g.: the "2d6" of "gm 2d6":
+fn after_word<'a>(tokens: &'a [Token<'a>], word: &str) -> Option<&'a [Token<'a>]> {
+    match tokens {
+        [first, space, rest @ ..] if first.is_word(word) && space.kind == Kind::Space && !rest.is_empty() => Some(rest),
+        _ => None,
+    }
+}
+
+// A number that may be left out, e.g.: the "3" of "3dF". Returns None if the tokens are something else:
+fn optional_number<'a>(tokens: &'a [Token<'a>]) -> Option<Option<&'a Token<'a>>> {
+    match tokens {
+        [] => Some(None),
+        [number] if number.kind == Kind::Number => Some(Some(number)),
+        _ => None,
+    }
+}
+
+// Value of a number token:
+fn number(token: &Token) -> Result<i64, DiceError> {
+    read_number(token.text, token.span)
+}
+
+// Value of the given numeral, written at the given span:
+fn read_number(numeral: &str, span: Span) -> Result<i64, DiceError> {
+    // Avoid an i64-parse error:
+    // (2**63 is 19 characters long.)
+    if numeral.chars().count() > 18 {
+        return Err(DiceError::NumberTooLarge(span));
+    }
+    Ok(numeral.parse::<i64>().expect("No number?"))
+}
+
+// A number along with its sign, if it has one, e.g.: the "+ 5" of [sw8 + 5], or the "-2" of [1d20 vs -2]:
+struct Signed<'a> {
+    negative: bool,
+    number: Token<'a>,
+    span: Span,
+}
+
+impl Signed<'_> {
+    // Value of the number, with its sign, whose length counts as well:
+    fn value(&self) -> Result<i64, DiceError> {
+        let sign = if self.span == self.number.span { "" } else if self.negative { "-" } else { "+" };
+        read_number(&format!("{}{}", sign, self.number.text), self.span)
+    }
+}
+
+// A bonus the tokens end with, if any, e.g.: the " - 2" of "sw8 - 2". Returns None if the tokens are
+// something else:
+fn trailing_bonus<'a>(tokens: &'a [Token<'a>]) -> Option<Option<Signed<'a>>> {
+    let [sign, rest @ ..] = skip_space(tokens) else {
+        return tokens.is_empty().then_some(None);
+    };
+    match skip_space(rest) {
+        [number] if (sign.is('+') || sign.is('-')) && number.kind == Kind::Number => {
+            Some(Some(Signed { negative: sign.is('-'), number: *number, span: sign.span.to(number.span) }))
+        },
+        _ => None,
+    }
+}
+
+// The number the tokens end with, possibly negative, along with the tokens before it, e.g.: "-2"
+// and "1d20 vs " out of "1d20 vs -2":
+fn trailing_number<'a>(tokens: &'a [Token<'a>]) -> Option<(&'a [Token<'a>], Signed<'a>)> {
+    match tokens {
+        [rest @ .., minus, number] if minus.is('-') && number.kind == Kind::Number => {
+            Some((rest, Signed { negative: true, number: *number, span: minus.span.to(number.span) }))
+        },
+        [rest @ .., number] if number.kind == Kind::Number => Some((rest, Signed { negative: false, number: *number, span: number.span })),
+        _ => None,
+    }
+}
+
+// The roll before "vs" or "dc" at the end of the tokens, along with where the comparison was written,
+// e.g.: "1d20+7" out of "1d20+7 vs". The comparison may be glued to the roll, e.g.: "4d6sa" out of
+// "4d6savs", and the single space before it is not part of the roll:
+fn before_comparison<'a>(tokens: &[Token<'a>]) -> Option<(Vec<Token<'a>>, Span)> {
+    let [rest @ .., word] = tokens else {
+        return None;
+    };
+    if word.kind != Kind::Word {
+        return None;
+    }
+    let length = word.text.len().checked_sub(2)?;
+    let comparison = &word.text[length..];
+    if !comparison.eq_ignore_ascii_case("vs") && !comparison.eq_ignore_ascii_case("dc") {
+        return None;
+    }
+    let mut roll = rest.to_vec();
+    if length > 0 {
+        roll.push(Token { kind: Kind::Word, text: &word.text[..length], span: Span::new(word.span.start, word.span.start + length) });
+    } else if roll.len() > 1 && roll.last().is_some_and(|space| space.kind == Kind::Space) {
+        roll.pop();
+    }
+    (!roll.is_empty()).then_some((roll, Span::new(word.span.start + length, word.span.end)))
+}
+
+// A call to a function, as its name, its arguments and the bonus it is followed by, if any, e.g.:
+// "max", "1d20, 1d20" and "+ 5" out of "max(1d20, 1d20) + 5":
+fn function_call<'a>(tokens: &'a [Token<'a>]) -> Option<(&'a Token<'a>, &'a [Token<'a>], Option<Signed<'a>>)> {
+    let [name, rest @ ..] = tokens else {
+        return None;
+    };
+    let [open, rest @ ..] = skip_space(rest) else {
+        return None;
+    };
+    let close = rest.iter().position(|token| token.is(')'))?;
+    let (args, rest) = (&rest[..close], &rest[close + 1..]);
+    if name.kind != Kind::Word || !open.is('(') || args.is_empty() || args.iter().any(|token| token.is('(')) {
+        return None;
+    }
+    Some((name, args, trailing_bonus(rest)?))
+}
+
+// Faces of the symbolic die the token is, if it is one, e.g.: those of the "dH" of [3dH]:
+fn symbolic_faces(token: &Token) -> Option<&'static [&'static str]> {
+    let letter = token.text.strip_prefix('d').filter(|_| token.kind == Kind::Word)?;
+    SYMBOLIC_DICE.iter().find(|(symbol, _)| letter.len() == 1 && letter.starts_with(*symbol)).map(|(_, faces)| *faces)
+}
+
+// Whether the token at the given index is a symbolic die, along with its amount, on its own rather than
+// within a longer word, e.g.: the "dH" of [1dH+2], but not that of [x1dH]:
+fn is_lone_symbolic_die(tokens: &[Token], index: usize) -> bool {
+    // Letters, digits and underscores make up words:
+    let is_word = |token: Option<&Token>| token.is_some_and(|token| token.text.chars().all(|c| c.is_alphanumeric() || c == '_'));
+    let before = match index.checked_sub(1).map(|before| &tokens[before]) {
+        Some(amount) if amount.kind == Kind::Number => index.checked_sub(2).map(|before| &tokens[before]),
+        before => before,
+    };
+    symbolic_faces(&tokens[index]).is_some() && !is_word(before) && !is_word(tokens.get(index + 1))
+}
+
+// Whether the token keeps (or drops) rolls of a group in Roll20's notation, e.g.: the "kh" of {…}kh1
+fn is_group_keep(token: &Token) -> bool {
+    token.kind == Kind::Word && matches!(token.text, "k" | "kh" | "kl" | "d" | "dh" | "dl")
+}
+
+// How many digits may follow an operation of a roll, e.g.: the "2" of the "km2" of [4d6km2]:
+#[derive(Clone, Copy, PartialEq)]
+enum Digits {
+    None,
+    Optional,
+    Required,
+    // Only a "1", e.g.: that of [4d6r1]
+    One,
+}
+
+// Reads dice and their operations out of the text of their tokens, as one word may hold several of
+// them, e.g.: the "hsa" of [3d6hsa] (HERO STUN, shown ascending), and one number may be split
+// between them, e.g.: the "109" of [7d109a] (d10s, 9-again):
+struct Scanner {
+    text: String,
+    // Byte of the expression the text starts at:
+    start: usize,
+    at: usize,
+}
+
+impl Scanner {
+    fn new(tokens: &[Token]) -> Scanner {
+        Scanner { text: text(tokens), start: span(tokens).start, at: 0 }
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.at..]
+    }
+
+    fn is_done(&self) -> bool {
+        self.at == self.text.len()
+    }
+
+    // Where the scanner is at, within the text, so it can be reset to it:
+    fn position(&self) -> usize {
+        self.at
+    }
+
+    fn reset(&mut self, at: usize) -> &mut Scanner {
+        self.at = at;
+        self
+    }
+
+    // What was read since the given position:
+    fn since(&self, at: usize) -> Span {
+        Span::new(self.start + at, self.start + self.at)
+    }
+
+    // What is left from the given position to the end:
+    fn rest_from(&self, at: usize) -> Span {
+        Span::new(self.start + at, self.start + self.text.len())
+    }
+
+    // Text of the expression within the span:
+    fn text(&self, span: Span) -> &str {
+        &self.text[span.start - self.start..span.end - self.start]
+    }
+
+    // Read the given text, if it comes next:
+    fn eat(&mut self, literal: &str) -> Option<Span> {
+        let start = self.at;
+        self.rest().starts_with(literal).then(|| {
+            self.at += literal.len();
+            self.since(start)
+        })
+    }
+
+    // Read the first of the given texts that comes next, if any:
+    fn eat_first<'a>(&mut self, literals: &[&'a str]) -> Option<&'a str> {
+        literals.iter().copied().find(|literal| self.eat(literal).is_some())
+    }
+
+    // Read the digits that come next, if any:
+    fn digits(&mut self) -> Option<Span> {
+        let start = self.at;
+        self.at += self.rest().bytes().take_while(u8::is_ascii_digit).count();
+        (self.at > start).then(|| self.since(start))
+    }
+
+    // Value of the digits within the span:
+    fn number(&self, digits: Span) -> Result<i64, DiceError> {
+        read_number(self.text(digits), digits)
+    }
+
+    // Value of the digits within the span, as long as it fits, as Roll20 reads them:
+    fn parse(&self, digits: Span) -> Result<i64, DiceError> {
+        self.text(digits).parse::<i64>().map_err(|_| DiceError::NumberTooLarge(digits))
+    }
+
+    // Read the bonus that comes next, if any, e.g.: "+3" " - 3" "+ -3", as whether it is subtracted,
+    // its digits and where it was written. Subtracted bonuses may only be negative themselves (e.g.:
+    // "- -3") if told so:
+    fn bonus(&mut self, twice_negative: bool) -> Option<(bool, Span, Span)> {
+        let start = self.at;
+        self.eat(" ");
+        let sign = self.at;
+        let Some(subtracted) = self.eat_first(&["+", "-"]).map(|sign| sign == "-") else {
+            self.reset(start);
+            return None;
+        };
+        self.eat(" ");
+        let negative = (!subtracted || twice_negative) && self.eat("-").is_some();
+        match self.digits() {
+            Some(digits) => Some((subtracted != negative, digits, self.since(sign))),
+            None => {
+                self.reset(start);
+                None
+            },
+        }
+    }
+}
+
+// Whether the largest magnitude a sum could reach fits in an i64, so it can always be totalled:
+fn fits(terms: &[(bool, Argument)]) -> bool {
+    let bound = terms.iter().try_fold(0i64, |bound, (_, term)| {
+        let magnitude = match term {
+            Argument::Dice(dice) => dice.amount.checked_mul(dice.sides)?.checked_mul(MAX_EXPLOSIONS as i64 + 1)?.checked_add(dice.bonus.checked_abs()?)?,
+            Argument::Constant(value) => value.checked_abs()?,
+        };
+        bound.checked_add(magnitude)
+    });
+    bound.is_some()
+}
+
+// Longest snippet of an expression quoted in a response:
+const MAX_QUOTE: usize = 40;
+// Longest delimiters rolls may be written within, e.g.: "{{" and "}}"
+const MAX_DELIMITER: usize = 4;
+/// Most rolls taken from a single chat message, so a wall of brackets cannot flood RANDOM.ORG (or the channel).
+pub const MAX_EXPRESSIONS: usize = 20;
+/// Note given when a chat message holds more rolls than [`MAX_EXPRESSIONS`].
+pub const EXPRESSIONS_CAPPED: &str = "Thy missive holds more rolls than mine hands can cast at once, traveller; only the first score of them were rolled.";
+/// Longest message Discord accepts, in characters; messages are measured in bytes, never fewer.
+pub const MESSAGE_LENGTH: usize = 2000;
+
+/// A roll found within a chat message, with the bracketed text it was written as.
+/// The expression is always given within brackets, whatever delimited it in the message.
+pub type Scanned = (String, Result<Roll, DiceError>);
+
+/// Values the variables of a roll stand for, by name, e.g.: 3 for the "$dex" of [1d20 + $dex]
+pub type Variables = HashMap<String, i64>;
+
+/// Parse a bracketed roll, as [`Roll::parse_with`] does, once its variables (e.g.: "$dex") are
+/// replaced by their values; the expression is returned as rolled, e.g.: "[1d20 + 3]" for
+/// "[1d20 + $dex]". Variables are an error if there are none to take their values from, or if any is
+/// unknown, but only when the expression would otherwise be a roll.
+pub fn parse_bound(expr: &str, syntax: Syntax, variables: Option<&Variables>) -> (String, Option<Result<Roll, DiceError>>) {
+    // A variable, with the sign it is added or subtracted with (if any), e.g.: "+ $dex"
+    static VARIABLE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:(?<sign>[+-])(?<space> ?))?\$(?<name>[A-Za-z_]\w*)").expect("No variable regex?"));
+    if !VARIABLE.is_match(expr) {
+        return (expr.to_owned(), Roll::parse_with(expr, syntax));
+    }
+
+    let mut unbound = None;
+    let bound = VARIABLE.replace_all(expr, |captures: &regex::Captures| {
+        let (name, written) = (&captures["name"], captures.get(0).expect("No variable?"));
+        let value = match variables {
+            Some(variables) => variables.get(&name.to_lowercase()).copied(),
+            None => None,
+        };
+        let value = value.unwrap_or_else(|| {
+            let (name, span) = (format!("${}", name), Span::new(written.end() - name.len() - 1, written.end()));
+            unbound.get_or_insert(if variables.is_some() { DiceError::UnknownVariable(name, span) } else { DiceError::NoCharacter(name, span) });
+            0
+        });
+        // Negative values turn the sign before them, e.g.: "+ -1" into "- 1", which is what sums take:
+        match captures.name("sign").map(|sign| sign.as_str()) {
+            Some(sign) => {
+                let value = if sign == "-" { -value } else { value };
+                format!("{}{}{}", if value < 0 { "-" } else { "+" }, &captures["space"], value.abs())
+            },
+            None => value.to_string(),
+        }
+    }).into_owned();
+
+    let roll = Roll::parse_with(&bound, syntax);
+    match (unbound, roll) {
+        (Some(why), Some(_)) => (expr.to_owned(), Some(Err(why))),
+        (Some(_), None) => (expr.to_owned(), None),
+        (None, roll) => (bound, roll),
+    }
+}
+
+/// What rolls are written within in chat, e.g.: [2d20+5], {{2d20+5}}; or what they are prefixed
+/// with, e.g.: r!2d20+5
+pub struct Delimiters {
+    open: String,
+    close: String,
+    regex: Regex,
+}
+
+impl Delimiters {
+    /// Delimiters opening and closing the rolls, or only opening them if `close` is empty (the roll
+    /// then lasting until the next whitespace). Markdown characters and whitespace are not allowed,
+    /// as they cannot be told apart reliably from the rest of the message.
+    pub fn new(open: &str, close: &str) -> Result<Delimiters, String> {
+        if open.is_empty() {
+            return Err("A roll must be opened by something, traveller.".to_string());
+        }
+        if open.chars().count() > MAX_DELIMITER || close.chars().count() > MAX_DELIMITER {
+            return Err(format!("Delimiters may be no longer than {} characters.", MAX_DELIMITER));
+        }
+        if open.chars().chain(close.chars()).any(|c| c.is_whitespace() || "`*_~|\\".contains(c)) {
+            return Err("Delimiters may hold no whitespace, nor any of ` * _ ~ | \\, lest they be mistaken for markdown.".to_string());
+        }
+
+        let (open_regex, close_regex) = (regex::escape(open), regex::escape(close));
+        let regex = if close.is_empty() {
+            // Only at the start of a word, e.g.: not in "bar!2d6" for "r!"
+            format!(r"(?:^|\s){}(?<expr>\S+)", open_regex)
+        } else if open.chars().count() == 1 && close.chars().count() == 1 {
+            // Innermost, as in "[[1d20]", where [1d20] is the roll:
+            format!(r"{}(?<expr>[^{}{}]+){}", open_regex, open_regex, close_regex, close_regex)
+        } else {
+            format!(r"{}(?<expr>.+?){}", open_regex, close_regex)
+        };
+        let regex = Regex::new(&regex).map_err(|why| why.to_string())?;
+        Ok(Delimiters { open: open.to_string(), close: close.to_string(), regex })
+    }
+
+    /// Whether these are the usual brackets.
+    pub fn is_default(&self) -> bool {
+        self.open == "[" && self.close == "]"
+    }
+
+    /// Delimiters around an example roll, e.g.: "{{1d20}}"
+    pub fn around(&self, expr: &str) -> String {
+        format!("{}{}{}", self.open, expr, self.close)
+    }
+}
+
+impl Default for Delimiters {
+    fn default() -> Self {
+        Delimiters::new("[", "]").expect("No brackets?")
+    }
+}
+
+/// Find the rolls within a chat message, e.g.: "Flogg takes [2d8+2] dmg". Bracketed text that is no
+/// roll is skipped, and only the first [`MAX_EXPRESSIONS`] rolls (or mistaken rolls) are taken; whether
+/// any were left out is returned as well.
+pub fn scan(content: &str) -> (Vec<Scanned>, bool) {
+    static BRACKETS: LazyLock<Delimiters> = LazyLock::new(Delimiters::default);
+    scan_with(content, &BRACKETS, false, Syntax::MapTool, None)
+}
+
+/// Find the rolls within a chat message, as [`scan`] does, but within the given delimiters and
+/// written in the given notation, with their variables bound to the given values (see
+/// [`parse_bound`]). When `strict`, text within them that is no roll is not skipped, but taken as a
+/// [`DiceError::IllFormed`] roll.
+pub fn scan_with(content: &str, delimiters: &Delimiters, strict: bool, syntax: Syntax, variables: Option<&Variables>) -> (Vec<Scanned>, bool) {
+    // Nothing to roll without delimiters:
+    if !content.contains(&delimiters.open) {
+        return (Vec::new(), false);
+    }
+
+    let mut rolls = Vec::new();
+    for captures in plain_text(content).into_iter().flat_map(|text| delimiters.regex.captures_iter(text)) {
+        let (expr, roll) = parse_bound(&format!("[{}]", &captures["expr"]), syntax, variables);
+        let roll = match roll {
+            Some(roll) => roll,
+            None if strict => Err(DiceError::IllFormed(Span::default())),
+            None => continue,
+        };
+        if rolls.len() == MAX_EXPRESSIONS {
+            return (rolls, true);
+        }
+        rolls.push((expr, roll));
+    }
+    (rolls, false)
+}
+
+/// Quote (part of) an expression written by a user, so it can be echoed safely: shortened, and
+/// unable to break out of its code span or to mention anyone.
+pub fn quote(expr: &str) -> String {
+    let mut snippet = expr.chars().take(MAX_QUOTE).collect::<String>();
+    if expr.chars().count() > MAX_QUOTE {
+        snippet.push('…');
+    }
+    format!("`{}`", snippet.replace('`', "ˋ").replace('@', "@\u{200B}"))
+}
+
+/// How rolls are shown.
+#[derive(Clone, Copy)]
+pub struct Style {
+    /// Whether natural 20s and 1s of a d20 stand out, e.g.: "**20** 🎉"
+    pub crits: bool,
+    /// Whether d6s are shown by their faces, e.g.: "⚀, ⚄, ⚂ = 9"
+    pub pips: bool,
+    /// Whether pseudo-random rolls say so, e.g.: "4 *[pseudo-random]*"
+    pub pseudo_random: bool,
+}
+
+impl Style {
+    /// Style without flourishes, e.g.: for a terminal.
+    pub fn plain() -> Style {
+        Style { crits: false, pips: false, pseudo_random: false }
+    }
+}
+
+impl Default for Style {
+    /// Crits and pips are shown unless the DENEDE_HIGHLIGHT_CRITS or DENEDE_DICE_PIPS environment
+    /// variables are 0, and pseudo-random rolls say so as [`randomorg::note_pseudo_random`] tells.
+    fn default() -> Self {
+        static DEFAULT: OnceLock<Style> = OnceLock::new();
+        *DEFAULT.get_or_init(|| Style {
+            crits: randomorg::flag("DENEDE_HIGHLIGHT_CRITS").unwrap_or(true),
+            pips: randomorg::flag("DENEDE_DICE_PIPS").unwrap_or(true),
+            pseudo_random: randomorg::note_pseudo_random(),
+        })
+    }
+}
+
+/// Show rolls with the given style rather than the default one, e.g.:
+/// `styled(style, || result.to_string())`
+pub fn styled<T>(style: Style, show: impl FnOnce() -> T) -> T {
+    let previous = STYLE.replace(Some(style));
+    let shown = show();
+    STYLE.set(previous);
+    shown
+}
+
+/// Parts of a chat message that are neither code (```fenced``` or `inline`) nor ||spoilers||, where
+/// rolls are looked for. A fence left open hides the rest of the message, while a lone ` or || is
+/// shown as is by Discord, and so is plain text.
+pub fn plain_text(content: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < content.len() {
+        let rest = &content[i..];
+        // Marker opening the region, and where the region ends (if it does):
+        let (opening, end) = if let Some(code) = rest.strip_prefix("```") {
+            match code.find("```") {
+                Some(end) => (3, Some(3 + end + 3)),
+                None => (3, Some(rest.len())),
+            }
+        } else if rest.starts_with('`') {
+            // Inline code may be delimited by several backticks, so it can hold single ones:
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            (ticks, rest[ticks..].find(&rest[..ticks]).map(|end| ticks + end + ticks))
+        } else if let Some(spoiler) = rest.strip_prefix("||") {
+            (2, spoiler.find("||").map(|end| 2 + end + 2))
+        } else {
+            (rest.chars().next().expect("No character?").len_utf8(), None)
+        };
+
+        match end {
+            Some(end) => {
+                parts.push(&content[start..i]);
+                i += end;
+                start = i;
+            },
+            None => i += opening,
+        }
+    }
+    parts.push(&content[start..]);
+    parts
+}
+
+/// Split a line too long for a single message into parts of at most `limit` bytes, at the last space
+/// before the limit (between numbers or groups of dice) so no number is split in two. Only text
+/// without any space to split at is cut where it must.
+pub fn split_line(line: &str, limit: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = line;
+    while rest.len() > limit {
+        let mut cut = limit;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        // At least a character goes in each part, however small the limit:
+        if cut == 0 {
+            cut = rest.chars().next().map_or(1, char::len_utf8);
+        }
+        let at = rest[..cut].rfind(' ').filter(|&at| at > 0).unwrap_or(cut);
+        parts.push(rest[..at].trim_end().to_string());
+        rest = rest[at..].trim_start();
+    }
+    parts.push(rest.to_string());
+    parts
+}
+
+/// Join the responses to a chat message (one per line) into as few messages as Discord allows.
+/// Responses too long for a message of their own are split between numbers, see [`split_line`].
+pub fn chunks(lines: &[String]) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    for line in lines.iter().flat_map(|line| split_line(line, MESSAGE_LENGTH - 1)) {
+        // Every line takes its newline too:
+        if !chunk.is_empty() && chunk.len() + line.len() + 1 > MESSAGE_LENGTH {
+            chunks.push(std::mem::take(&mut chunk));
+        }
+        chunk.push_str(&line);
+        chunk.push('\n');
+    }
+    // Nothing is left to send once the last chunk was, or if there was nothing at all:
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// Escape text written by a user (e.g.: a label) so it is shown as written in chat: without Markdown,
+/// and unable to mention anyone.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '~' | '`' | '|' | '>' | '#' | '-' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        if c == '@' {
+            escaped.push('\u{200B}');
+        }
+    }
+    escaped
+}
+
+/// Check the dice limits set through the environment, so a bad configuration is noticed on startup
+/// rather than on the first roll.
+pub fn check_limits() -> Result<(), String> {
+    read_limits().map(|_| ())
+}
+
+fn max_dice() -> i64 {
+    limits().0
+}
+
+fn max_sides() -> i64 {
+    limits().1
+}
+
+fn limits() -> (i64, i64) {
+    static LIMITS: OnceLock<(i64, i64)> = OnceLock::new();
+    *LIMITS.get_or_init(|| read_limits().unwrap_or((DEFAULT_MAX_DICE, DEFAULT_MAX_SIDES)))
+}
+
+// Amount of dice and sides allowed, as set through the environment:
+fn read_limits() -> Result<(i64, i64), String> {
+    let read = |name: &str, default: i64, highest: i64| match env::var(name) {
+        Err(_) => Ok(default),
+        Ok(value) => match value.parse::<i64>() {
+            Ok(limit) if (1..=highest).contains(&limit) => Ok(limit),
+            _ => Err(format!("{} must be a whole number from 1 to {}, not \"{}\".", name, highest, value)),
+        },
+    };
+    Ok((read("MAX_DICE_AMOUNT", DEFAULT_MAX_DICE, HIGHEST_MAX_DICE)?, read("MAX_DICE_SIDES", DEFAULT_MAX_SIDES, HIGHEST_MAX_SIDES)?))
+}
+
+// What was made of the rolled dice, according to their DieKind:
+#[derive(Serialize)]
+enum Outcome {
+    Sum,
+    // Whether each of the rolled dice is kept, the rest being discarded:
+    Kept(Vec<bool>),
+    // Faces that matched, with the amount of times each one showed up:
+    Matches(Vec<(i64, usize)>),
+    Hits { hits: usize, glitch: bool },
+    // Every die with its rerolls, and the amount of successes among all of them:
+    Successes { chains: Vec<Vec<i64>>, successes: usize },
+    // Every die with the extra dice it exploded into, all of them added up; compounded ones are shown
+    // as a single die:
+    Exploded { chains: Vec<Vec<i64>>, compound: bool },
+    // What each of the rolled dice counts as, once kept within its bounds:
+    Clamped(Vec<i64>),
+    // Dice showing -1, 0 or +1, added up:
+    Fudge,
+    // BODY of the dice, and whether their total is their STUN rather than it:
+    Hero { body: i64, stun: bool },
+}
+
+#[derive(Serialize)]
+pub struct DiceResult {
+    sides: i64,
+    seq: Vec<i64>,
+    outcome: Outcome,
+    bonus: i64,
+    truly_random: bool,
+}
+
+impl DiceResult {
+    // Reorder the shown dice. This does not change the result of the roll:
+    fn sort(&mut self, order: &SortOrder) {
+        let sort = |values: &mut Vec<i64>| match order {
+            SortOrder::Ascending => values.sort(),
+            SortOrder::Descending => values.sort_by(|a, b| b.cmp(a)),
+        };
+
+        match &mut self.outcome {
+            Outcome::Sum => sort(&mut self.seq),
+            // Discarded dice move along with their values:
+            Outcome::Kept(kept) => {
+                let mut dice = self.seq.iter().copied().zip(kept.iter().copied()).collect::<Vec<(i64, bool)>>();
+                match order {
+                    SortOrder::Ascending => dice.sort_by_key(|&(value, _)| value),
+                    SortOrder::Descending => dice.sort_by_key(|&(value, _)| -value),
+                }
+                (self.seq, *kept) = dice.into_iter().unzip();
+            },
+            Outcome::Matches(matches) => {
+                sort(&mut self.seq);
+                match order {
+                    SortOrder::Ascending => matches.sort(),
+                    SortOrder::Descending => matches.sort_by(|a, b| b.cmp(a)),
+                }
+            },
+            Outcome::Hits { .. } | Outcome::Fudge | Outcome::Hero { .. } => sort(&mut self.seq),
+            // Clamped dice move along with their values:
+            Outcome::Clamped(counted) => {
+                let mut dice = self.seq.iter().copied().zip(counted.iter().copied()).collect::<Vec<(i64, i64)>>();
+                match order {
+                    SortOrder::Ascending => dice.sort(),
+                    SortOrder::Descending => dice.sort_by(|a, b| b.cmp(a)),
+                }
+                (self.seq, *counted) = dice.into_iter().unzip();
+            },
+            Outcome::Successes { chains, .. } | Outcome::Exploded { chains, .. } => match order {
+                SortOrder::Ascending => chains.sort(),
+                SortOrder::Descending => chains.sort_by(|a, b| b.cmp(a)),
+            },
+        }
+    }
+
+    /// Final value of the roll: the sum of the kept dice, or the amount of matches, hits or
+    /// successes, plus the bonus.
+    pub fn total(&self) -> i64 {
+        let value = match &self.outcome {
+            Outcome::Sum | Outcome::Fudge | Outcome::Hero { stun: true, .. } => self.seq.iter().sum(),
+            Outcome::Hero { body, .. } => *body,
+            Outcome::Kept(kept) => self.seq.iter().zip(kept).filter(|(_, &kept)| kept).map(|(value, _)| value).sum(),
+            Outcome::Matches(matches) => matches.len() as i64,
+            Outcome::Hits { hits, .. } => *hits as i64,
+            Outcome::Successes { successes, .. } => *successes as i64,
+            Outcome::Exploded { chains, .. } => chains.iter().flatten().sum(),
+            Outcome::Clamped(counted) => counted.iter().sum(),
+        };
+        value + self.bonus
+    }
+
+    /// The dice as rolled, in the order they are shown (without the rerolls of exploding dice).
+    pub fn rolls(&self) -> &[i64] {
+        &self.seq
+    }
+
+    /// Dice rolled but left out of the total, e.g.: the lowest ones when dropping some.
+    pub fn discarded(&self) -> Vec<i64> {
+        match &self.outcome {
+            Outcome::Kept(kept) => self.seq.iter().zip(kept).filter(|(_, &kept)| !kept).map(|(&value, _)| value).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether the dice were rolled by RANDOM.ORG, rather than by the fallback PRNG.
+    pub fn is_truly_random(&self) -> bool {
+        self.truly_random
+    }
+
+    /// Whether any of the dice was stopped from exploding further by [`MAX_EXPLOSIONS`].
+    pub fn capped(&self) -> bool {
+        match &self.outcome {
+            Outcome::Successes { chains, .. } | Outcome::Exploded { chains, .. } => chains.iter().any(|chain| exploded_too_much(chain)),
+            _ => false,
+        }
+    }
+
+    /// Amount of sides of the rolled dice.
+    pub fn sides(&self) -> i64 {
+        self.sides
+    }
+
+    // Mark of a natural 20 or 1 of a d20, if the die is one:
+    fn natural(&self, value: i64) -> Option<&'static str> {
+        if self.sides != 20 || !style().crits {
+            return None;
+        }
+        match value {
+            20 => Some(NATURAL_20),
+            1 => Some(NATURAL_1),
+            _ => None,
+        }
+    }
+
+    // Whether the dice are d6s shown by their faces, e.g.: "⚀, ⚄, ⚂ = 9"
+    fn pips(&self) -> bool {
+        self.sides == 6 && style().pips
+    }
+
+    // Show a die that counts towards the total:
+    fn show(&self, value: i64) -> String {
+        match self.natural(value) {
+            Some(natural) => natural.to_string(),
+            None => self.show_discarded(value),
+        }
+    }
+
+    // Show a die, whether or not it counts towards the total:
+    fn show_discarded(&self, value: i64) -> String {
+        if self.pips() { PIPS[value as usize - 1].to_string() } else { value.to_string() }
+    }
+}
+
+// The alternate form ("{:#}") is meant for chat, and shows the total of the dice in bold:
+impl fmt::Display for DiceResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let markdown = f.alternate();
+        // Comma-separated sequence of random numbers:
+        match &self.outcome {
+            // Show rerolled dice grouped with their rerolls, e.g.: "(10, 7), 3, 8":
+            Outcome::Successes { chains, .. } => {
+                let chains = chains.iter().map(|chain| {
+                    if chain.len() == 1 { chain[0].to_string() } else { format!("({})", join_rolls(chain)) }
+                }).collect::<Vec<String>>();
+                write!(f, "{}", chains.join(", "))?;
+            },
+            // Show exploded dice grouped with the dice they exploded into, e.g.: "(6, 6, 2), 3", or
+            // compounded ones added up, e.g.: "(6+6+2), 3":
+            Outcome::Exploded { chains, compound } => {
+                let chains = chains.iter().map(|chain| match chain.as_slice() {
+                    [die] => self.show(*die),
+                    _ if *compound => format!("({})", chain.iter().map(i64::to_string).collect::<Vec<String>>().join("+")),
+                    _ => format!("({})", join_rolls(chain)),
+                }).collect::<Vec<String>>();
+                write!(f, "{}", chains.join(", "))?;
+            },
+            // Strike through the clamped dice, showing what they count as instead, e.g.: "~~1~~→3, 5":
+            Outcome::Clamped(counted) => {
+                let dice = self.seq.iter().zip(counted).map(|(&value, &counted)| {
+                    if value == counted { self.show(value) } else { format!("~~{}~~→{}", self.show_discarded(value), counted) }
+                }).collect::<Vec<String>>();
+                write!(f, "{}", dice.join(", "))?;
+            },
+            // Strike the discarded dice through, e.g.: "~~1~~, 5, 3, 6":
+            Outcome::Kept(kept) => {
+                let dice = self.seq.iter().zip(kept).map(|(&value, &kept)| {
+                    if kept { self.show(value) } else { format!("~~{}~~", self.show_discarded(value)) }
+                }).collect::<Vec<String>>();
+                write!(f, "{}", dice.join(", "))?;
+            },
+            // Fudge dice are shown by their signs, e.g.: "+, −, 0, +":
+            Outcome::Fudge => {
+                let dice = self.seq.iter().map(|&value| match value {
+                    1 => "+",
+                    -1 => "−",
+                    _ => "0",
+                }).collect::<Vec<&str>>();
+                write!(f, "{}", dice.join(", "))?;
+            },
+            // A single die is its own total, e.g.: [1d20]
+            Outcome::Sum if self.seq.len() == 1 && self.bonus == 0 && self.show(self.seq[0]) == self.seq[0].to_string() => {
+                write!(f, "{}", bold(markdown, self.seq[0]))?
+            },
+            Outcome::Sum => write!(f, "{}", self.seq.iter().map(|&value| self.show(value)).collect::<Vec<String>>().join(", "))?,
+            _ => write!(f, "{}", join_rolls(&self.seq))?,
+        }
+
+        // Amount of values making up the total, to avoid showing "N = N" for single values:
+        let values = match &self.outcome {
+            // The faces of a die are not its value:
+            Outcome::Sum if self.pips() => usize::MAX,
+            Outcome::Sum => self.seq.len(),
+            // The discarded dice are shown too, so the total is never obvious:
+            Outcome::Kept(kept) => kept.len(),
+            Outcome::Matches(matches) => {
+                let plural = if matches.len() == 1 { "match" } else { "matches" };
+                write!(f, " → {} {}", bold(markdown, matches.len()), plural)?;
+                if !matches.is_empty() {
+                    let groups = matches.iter().map(|(face, count)| format!("{}×{}", face, count)).collect::<Vec<String>>();
+                    write!(f, " ({})", groups.join(", "))?;
+                }
+                1
+            },
+            Outcome::Hits { hits, .. } => {
+                write!(f, " → {} {}", bold(markdown, hits), if *hits == 1 { "hit" } else { "hits" })?;
+                1
+            },
+            Outcome::Successes { successes, .. } => {
+                write!(f, " → {} {}", bold(markdown, successes), if *successes == 1 { "success" } else { "successes" })?;
+                1
+            },
+            Outcome::Exploded { chains, .. } => chains.iter().map(Vec::len).sum(),
+            Outcome::Clamped(counted) => counted.len(),
+            // Signs are not the values they stand for:
+            Outcome::Fudge => usize::MAX,
+            Outcome::Hero { stun: true, .. } => self.seq.len(),
+            Outcome::Hero { body, stun: false } => {
+                write!(f, " → {} BODY", bold(markdown, body))?;
+                1
+            },
+        };
+
+        if self.bonus != 0 {
+            write!(f, "{} = {}", show_bonus(self.bonus), bold(markdown, self.total()))?;
+        } else if values != 1 && !matches!(self.outcome, Outcome::Matches(_) | Outcome::Hits { .. } | Outcome::Successes { .. } | Outcome::Hero { stun: false, .. }) {
+            write!(f, " = {}", bold(markdown, self.total()))?;
+        }
+        if let Outcome::Hero { body, stun: true } = self.outcome {
+            write!(f, " STUN, {} BODY", body)?;
+        }
+
+        match self.outcome {
+            Outcome::Hits { hits: 0, glitch: true } => write!(f, " — Critical glitch!")?,
+            Outcome::Hits { glitch: true, .. } => write!(f, " — Glitch!")?,
+            _ => {},
+        }
+
+        if self.capped() {
+            write!(f, " {}", EXPLOSIONS_CAPPED)?;
+        }
+
+        if let Some(note) = randomorg::note(self.truly_random) {
+            write!(f, " {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Source of the random numbers the dice are rolled with.
+///
+/// Rolls use [`RandomOrg`] unless another roller is given, e.g.: to make them reproducible.
+pub trait Roller {
+    /// Generate `num` random integers from `min` to `max` (both included), and tell whether they
+    /// are truly random.
+    fn gen(&mut self, num: i64, min: i64, max: i64) -> impl Future<Output = (Vec<i64>, bool)> + Send;
+
+    /// Note that a group of dice of the given kind is about to be rolled, e.g.: to count them.
+    fn rolling(&mut self, _kind: &DieKind) {}
+}
+
+/// Rolls dice with RANDOM.ORG, falling back to a local PRNG if it does not answer.
+pub struct RandomOrg;
+
+impl Roller for RandomOrg {
+    async fn gen(&mut self, num: i64, min: i64, max: i64) -> (Vec<i64>, bool) {
+        let (seq, truly_random) = call_randomorg(num, max - min + 1).await;
+        (seq.into_iter().map(|n| n + min - 1).collect(), truly_random)
+    }
+
+    // Only dice rolled for someone are counted, not those of simulations or seeded rolls:
+    fn rolling(&mut self, kind: &DieKind) {
+        metrics::get().die_kind(kind);
+    }
+}
+
+// Rolls dice with a local generator, never having to wait for them:
+struct Local<'a, G: Rng + Send>(&'a mut G);
+
+impl<G: Rng + Send> Roller for Local<'_, G> {
+    async fn gen(&mut self, num: i64, min: i64, max: i64) -> (Vec<i64>, bool) {
+        ((0..num).map(|_| self.0.gen_range(min..=max)).collect(), false)
+    }
+}
+
+// Run a roll with a Local roller to completion, which takes a single poll as nothing is awaited:
+fn finish_local<T>(roll: impl Future<Output = T>) -> T {
+    match pin!(roll).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(result) => result,
+        Poll::Pending => panic!("Local roll waiting?"),
+    }
+}
+
+// Roll `rolls` dice of `size` sides, returning the results and whether they are truly random:
+pub async fn call_randomorg(rolls: i64, size: i64) -> (Vec<i64>, bool) {
+    // Serve the dice from the pool of pre-fetched numbers, if it holds enough of them:
+    if let Some(seq) = pool::take(rolls, size) {
+        return (seq, true);
+    }
+
+    let url = randomorg::url(&format!("/integers/?num={}&min=1&max={}&col=1&base=10&format=plain&rnd=new", rolls, size));
+    if let Some(seq) = randomorg::request(&url).await {
+        (seq, true)
+    } else {
+        // Fallback in case random.org does not work for some reason (has happened):
+        use rand::prelude::*;
+
+        let mut rng = thread_rng();
+
+        ((0..rolls).map(|_| rng.gen_range(1..size+1)).collect(), false)
+    }
+}
+
+// Random permutation of the numbers from 1 to `length`, and whether it is truly random:
+pub async fn call_randomorg_sequence(length: i64) -> (Vec<i64>, bool) {
+    let url = randomorg::url(&format!("/sequences/?min=1&max={}&col=1&format=plain&rnd=new", length));
+    if let Some(sequence) = randomorg::request(&url).await {
+        (sequence, true)
+    } else {
+        // Fallback in case random.org does not work for some reason (has happened):
+        use rand::prelude::*;
+
+        let mut sequence = (1..length+1).collect::<Vec<i64>>();
+        sequence.shuffle(&mut thread_rng());
+
+        (sequence, false)
+    }
+}
+
+// Roll a single die, rolling it again and again while it shows its highest face, up to MAX_EXPLOSIONS times:
+async fn explode<R: Roller>(sides: i64, roller: &mut R) -> (Vec<i64>, bool) {
+    let (first, truly_random) = roller.gen(1, 1, sides).await;
+    let (chain, chain_truly_random) = explode_chain(first[0], sides, sides, roller).await;
+    (chain, truly_random && chain_truly_random)
+}
+
+// Starting from an already rolled die, roll it again and again while it shows at least `at_least`, up to
+// MAX_EXPLOSIONS times:
+async fn explode_chain<R: Roller>(first: i64, sides: i64, at_least: i64, roller: &mut R) -> (Vec<i64>, bool) {
+    let mut chain = vec![first];
+    let mut truly_random = true;
+    while chain.last().is_some_and(|&last| last >= at_least) && chain.len() <= MAX_EXPLOSIONS {
+        let (reroll, reroll_truly_random) = roller.gen(1, 1, sides).await;
+        chain.extend(reroll);
+        truly_random &= reroll_truly_random;
+    }
+    (chain, truly_random)
+}
+
+// Whether a chain of rerolls was cut short by MAX_EXPLOSIONS:
+fn exploded_too_much(chain: &[i64]) -> bool {
+    chain.len() > MAX_EXPLOSIONS
+}
+
+// Same as explode_chain, but rolling locally with the given function rolling each die:
+fn explode_chain_local(first: i64, sides: i64, at_least: i64, roll_die: &mut dyn FnMut(i64) -> i64) -> Vec<i64> {
+    let mut chain = vec![first];
+    while chain.last().is_some_and(|&last| last >= at_least) && chain.len() <= MAX_EXPLOSIONS {
+        chain.push(roll_die(sides));
+    }
+    chain
+}
+
+// Show whole averages as such, and the rest with two decimals:
+fn format_average(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+thread_local! {
+    // Style the rolls being shown on this thread are shown with, if not the default one:
+    static STYLE: Cell<Option<Style>> = const { Cell::new(None) };
+}
+
+/// Style the rolls being shown on this thread are shown with.
+pub fn style() -> Style {
+    STYLE.get().unwrap_or_default()
+}
+
+// Whether a GURPS 3d6 roll against the target number is a critical success (true) or failure (false):
+// 3 and 4 are always critical successes, and so are 5 and 6 for high enough skills;
+// 18 is always a critical failure, and so are 17 for skills up to 15 and anything 10 over the skill.
+fn gurps_critical(total: i64, target: i64) -> Option<bool> {
+    if total <= 4 || (total == 5 && target >= 15) || (total == 6 && target >= 16) {
+        Some(true)
+    } else if total == 18 || (total == 17 && target <= 15) || total >= target + 10 {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+// Most dice a single group may have for a roll to be shown in full:
+fn compact_dice() -> usize {
+    static COMPACT_DICE: OnceLock<usize> = OnceLock::new();
+    *COMPACT_DICE.get_or_init(|| env::var("DENEDE_COMPACT_DICE").ok().and_then(|dice| dice.parse().ok()).unwrap_or(DEFAULT_COMPACT_DICE))
+}
+
+// Final result of a roll, in bold when shown in chat:
+fn bold(markdown: bool, value: impl fmt::Display) -> String {
+    if markdown { format!("**{}**", value) } else { value.to_string() }
+}
+
+fn join_rolls(seq: &[i64]) -> String {
+    seq.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(", ")
+}
+
+/// Which values of `seq` to keep, so only the `keep` middle ones are.
+///
+/// When the dice left out cannot be split evenly between the low and high ends (e.g. keeping 1 out
+/// of 4 dice), the lower of the two central candidates is kept, i.e. one more high die is discarded.
+fn keep_middle(seq: &[i64], keep: usize) -> Vec<bool> {
+    let mut indices: Vec<usize> = (0..seq.len()).collect();
+    indices.sort_by_key(|&i| seq[i]);
+
+    let start = (seq.len() - keep) / 2;
+    let mut kept = vec![false; seq.len()];
+    for &i in &indices[start..start + keep] {
+        kept[i] = true;
+    }
+    kept
+}
+
+/// Which values of `seq` to keep, so the `drop` lowest ones are dropped.
+fn drop_lowest(seq: &[i64], drop: usize) -> Vec<bool> {
+    drop_sorted(seq, drop, false)
+}
+
+/// Which values of `seq` to keep, so the `drop` highest ones are dropped.
+fn drop_highest(seq: &[i64], drop: usize) -> Vec<bool> {
+    drop_sorted(seq, drop, true)
+}
+
+// Which values of `seq` to keep, dropping the first `drop` ones in ascending (or descending) order:
+fn drop_sorted(seq: &[i64], drop: usize, descending: bool) -> Vec<bool> {
+    let mut indices: Vec<usize> = (0..seq.len()).collect();
+    if descending {
+        indices.sort_by_key(|&i| std::cmp::Reverse(seq[i]));
+    } else {
+        indices.sort_by_key(|&i| seq[i]);
+    }
+
+    let mut kept = vec![true; seq.len()];
+    for &i in &indices[..drop] {
+        kept[i] = false;
+    }
+    kept
+}
+
+/// Faces of `seq` that show up at least `at_least` times, in order of first appearance, together
+/// with how many times each of them showed up.
+fn matching_faces(seq: &[i64], at_least: usize) -> Vec<(i64, usize)> {
+    let mut counts: Vec<(i64, usize)> = Vec::new();
+    for &face in seq {
+        match counts.iter_mut().find(|(f, _)| *f == face) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((face, 1)),
+        }
+    }
+    counts.retain(|&(_, count)| count >= at_least);
+    counts
+}
@@ -0,0 +1,144 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::{CreateEmbed, CreateEmbedAuthor};
+use serenity::model::prelude::*;
+
+use denede_discord_bot::dice::RollResult;
+use denede_discord_bot::{randomorg, verify};
+
+// Limits of a Discord embed:
+const MAX_TITLE: usize = 256;
+const MAX_DESCRIPTION: usize = 4096;
+const MAX_FIELDS: usize = 25;
+const MAX_FIELD_VALUE: usize = 1024;
+// ...and of all the embeds of a single message:
+const MAX_MESSAGE_EMBEDS: usize = 10;
+const MAX_MESSAGE_CHARS: usize = 6000;
+// Room kept for the field telling how many groups of dice were left out:
+const LEFT_OUT_CHARS: usize = 50;
+
+/// An embed, with how many of the characters allowed in a message it takes up.
+pub struct Embed {
+    embed: CreateEmbed,
+    chars: usize,
+    fields: usize,
+}
+
+impl Embed {
+    fn new(user: &User, title: &str, description: &str) -> Embed {
+        let name = user.global_name.as_deref().unwrap_or(&user.name);
+        let title = truncate(title, MAX_TITLE);
+        let description = truncate(description, MAX_DESCRIPTION);
+        let chars = name.chars().count() + title.chars().count() + description.chars().count();
+        let embed = CreateEmbed::new().author(CreateEmbedAuthor::new(name).icon_url(user.face())).title(title).description(description);
+        Embed { embed, chars, fields: 0 }
+    }
+
+    fn field(mut self, name: &str, value: &str) -> Embed {
+        let value = truncate(value, MAX_FIELD_VALUE);
+        self.chars += name.chars().count() + value.chars().count();
+        self.fields += 1;
+        self.embed = self.embed.field(name, value, true);
+        self
+    }
+}
+
+/// Embed showing a roll: its result in big, every group of dice, and whether it succeeded.
+pub fn roll(user: &User, title: &str, expression: &str, result: &RollResult) -> Embed {
+    let mut description = match result.total() {
+        Some(total) => format!("# {}", total),
+        // Nothing to sum up, e.g.: symbols:
+        None => result.to_string(),
+    };
+    if let RollResult::Seeded { seed, .. } = result {
+        description.push_str(&format!("\nSeeded roll (seed {}; not random, not for actual play)", seed));
+    } else if let Some(note) = randomorg::note(result.is_truly_random()) {
+        description.push_str(&format!("\n{}", note));
+    }
+    if let Some(code) = verify::sign(expression, result) {
+        description.push_str(&format!("\nverify: `{}`", code));
+    }
+    let mut embed = Embed::new(user, title, &description);
+
+    // Every group of dice, as long as they fit:
+    let groups = if result.is_compact() { Vec::new() } else { result.individuals() };
+    for (shown, dice) in groups.iter().enumerate() {
+        let name = format!("{}d{}", dice.rolls().len(), dice.sides());
+        let value = truncate(&dice.to_string(), MAX_FIELD_VALUE);
+        let left = groups.len() - shown;
+        if left > 1 && (embed.fields == MAX_FIELDS - 1 || embed.chars + name.len() + value.chars().count() + LEFT_OUT_CHARS > MAX_MESSAGE_CHARS) {
+            embed = embed.field("…", &format!("and {} more groups of dice", left));
+            break;
+        }
+        embed = embed.field(&name, &value);
+    }
+
+    embed.embed = match result.success() {
+        Some(true) => embed.embed.colour(Colour::DARK_GREEN),
+        Some(false) => embed.embed.colour(Colour::RED),
+        None => embed.embed,
+    };
+    embed
+}
+
+impl Embed {
+    /// Add whether the roll met a difficulty class, e.g.: "— **Success** (beat DC 15 by 4)", coloring
+    /// the embed accordingly.
+    pub fn verdict(mut self, success: bool, verdict: &str) -> Embed {
+        self.embed = self.embed.colour(if success { Colour::DARK_GREEN } else { Colour::RED });
+        if self.fields == MAX_FIELDS {
+            return self;
+        }
+        self.field("Verdict", verdict.trim_start_matches("— "))
+    }
+}
+
+/// Embed showing why something could not be rolled.
+pub fn error(user: &User, title: &str, message: &str) -> Embed {
+    Embed::new(user, title, message)
+}
+
+/// Group embeds into as few messages as Discord allows.
+pub fn messages(embeds: Vec<Embed>) -> Vec<Vec<CreateEmbed>> {
+    let mut messages = Vec::new();
+    let mut message = Vec::new();
+    let mut chars = 0;
+    for embed in embeds {
+        if message.len() == MAX_MESSAGE_EMBEDS || (!message.is_empty() && chars + embed.chars > MAX_MESSAGE_CHARS) {
+            messages.push(message);
+            message = Vec::new();
+            chars = 0;
+        }
+        chars += embed.chars;
+        message.push(embed.embed);
+    }
+    if !message.is_empty() {
+        messages.push(message);
+    }
+    messages
+}
+
+// Shorten text to the given amount of characters, ellipsis included:
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated = text.chars().take(max - 1).collect::<String>();
+    truncated.push('…');
+    truncated
+}
@@ -0,0 +1,61 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Whether the bot is connected to Discord, for /health on the metrics server. The shards are
+// looked at every now and then rather than on each request, so probes never wait on the gateway.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serenity::gateway::{ConnectionStage, ShardManager};
+use tokio::task::JoinHandle;
+
+// How often the shards are looked at:
+const INTERVAL: Duration = Duration::from_secs(15);
+// How long a look at the shards is trusted, in case the task looking at them stops:
+const STALE: Duration = Duration::from_secs(60);
+
+// Whether ready() has completed at least once:
+static READY: AtomicBool = AtomicBool::new(false);
+// When every shard was last seen connected and heartbeating:
+static CONNECTED: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Note that the bot got ready, its commands being registered.
+pub fn ready() {
+    READY.store(true, Ordering::Relaxed);
+}
+
+/// Whether the bot got ready and every shard was recently seen connected, with its heartbeats
+/// acknowledged by Discord.
+pub fn healthy() -> bool {
+    let connected = *CONNECTED.lock().expect("No health?");
+    READY.load(Ordering::Relaxed) && connected.is_some_and(|seen| seen.elapsed() < STALE)
+}
+
+/// Look at the shards every now and then, for as long as the returned task runs.
+pub fn watch(shard_manager: Arc<ShardManager>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(INTERVAL);
+        loop {
+            interval.tick().await;
+            let runners = shard_manager.runners.lock().await;
+            // A shard has a latency once Discord has acknowledged one of its heartbeats:
+            let connected = !runners.is_empty() && runners.values().all(|runner| runner.stage == ConnectionStage::Connected && runner.latency.is_some());
+            *CONNECTED.lock().expect("No health?") = connected.then(Instant::now);
+        }
+    })
+}
@@ -0,0 +1,107 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serde::Serialize;
+
+/// Bytes of an expression something was written in, e.g.: 1..3 for the "21" of "[21d6]". Empty
+/// for what was not written at all, e.g.: the dice of [gurps12].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// From the start of this span to the end of the other, e.g.: "2d6" from "2" and "6".
+    pub fn to(self, other: Span) -> Span {
+        Span { start: self.start, end: other.end }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    /// Text of the expression within the span, if the span lies within it.
+    pub fn of(self, expr: &str) -> Option<&str> {
+        expr.get(self.start..self.end)
+    }
+}
+
+/// What a token is made of. Digits and letters are read in runs, e.g.: "20" or "kh", whereas
+/// spaces and the rest of symbols are read one by one, e.g.: "  " as two spaces, "<=" as "<" and "=".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Number,
+    Word,
+    Space,
+    Symbol,
+}
+
+/// A piece of an expression, with where it was written, e.g.: the "kh" of "[2d20kh1]" at 4..6
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: Kind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+impl Token<'_> {
+    /// Whether the token is the given symbol, e.g.: "+"
+    pub fn is(&self, symbol: char) -> bool {
+        self.kind == Kind::Symbol && self.text.starts_with(symbol) && self.text.len() == symbol.len_utf8()
+    }
+
+    /// Whether the token is the given word, whatever its case, e.g.: "GM" for "gm"
+    pub fn is_word(&self, word: &str) -> bool {
+        self.kind == Kind::Word && self.text.eq_ignore_ascii_case(word)
+    }
+}
+
+/// Split an expression into its tokens, e.g.: "[2d6 + 3]" into "[", "2", "d", "6", " ", "+", " ",
+/// "3" and "]". Only ASCII digits and letters make numbers and words, so anything else (e.g.: "٣")
+/// is a symbol no roll is made of.
+pub fn tokenize(expr: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let kind = if c.is_ascii_digit() {
+            Kind::Number
+        } else if c.is_ascii_alphabetic() {
+            Kind::Word
+        } else if c == ' ' {
+            Kind::Space
+        } else {
+            Kind::Symbol
+        };
+        let mut end = start + c.len_utf8();
+        if matches!(kind, Kind::Number | Kind::Word) {
+            while let Some(&(next, c)) = chars.peek() {
+                if (kind == Kind::Number && !c.is_ascii_digit()) || (kind == Kind::Word && !c.is_ascii_alphabetic()) {
+                    break;
+                }
+                end = next + c.len_utf8();
+                chars.next();
+            }
+        }
+        tokens.push(Token { kind, text: &expr[start..end], span: Span::new(start, end) });
+    }
+    tokens
+}
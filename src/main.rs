@@ -15,179 +15,621 @@
  *  You should have received a copy of the GNU Affero General Public License
  *  along with this program. If not, see <https://www.gnu.org/licenses/>.
  */
+mod api;
+mod audit;
+mod characters;
+mod cli;
 mod commands;
+mod config;
+mod database;
+mod embed;
+mod health;
+mod presence;
+mod recent;
+mod replies;
+mod server;
+mod tally;
+mod welcome;
 
-use std::env;
-use regex::Regex;
+use std::collections::HashMap;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use denede_discord_bot::dice::{self, styled, DiceError, Roll};
+use denede_discord_bot::metrics;
+use denede_discord_bot::retry::{self, with_retries, ALREADY_ACKNOWLEDGED};
+use denede_discord_bot::ratelimit::{Limit, RateLimiter};
+use denede_discord_bot::defer::patiently;
+use denede_discord_bot::{pool, verify};
 extern crate reqwest;
-use serenity::builder::{CreateInteractionResponse, CreateInteractionResponseMessage};
+use serenity::builder::{Builder, CreateAllowedMentions, CreateAutocompleteResponse, CreateButton, CreateInteractionResponse, CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse, ExecuteWebhook};
 use serenity::model::prelude::*;
 use serenity::model::application::{Command, Interaction};
 use serenity::prelude::*;
+use clap::Parser;
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
+use tracing_subscriber::EnvFilter;
 
-struct Bot;
+use cli::Cli;
+use commands::mystats::Unrecorded;
+use commands::Response;
+use presence::Presence;
+use recent::Recent;
+use replies::Replies;
+use server::Server;
+use tally::Tally;
+
+// How long a roll may take before its response is deferred, well within the 3 seconds Discord waits:
+const ROLL_PATIENCE: Duration = Duration::from_secs(2);
+
+// Most suggestions Discord takes for an option being typed:
+const MAX_SUGGESTIONS: usize = 25;
+
+// Note for users whose secret rolls could not be sent to them, e.g.: not accepting direct messages:
+const SECRET_UNSENT: &str = "I could not whisper thy secret roll unto thee, traveller. Are thy missives sealed to me?";
+
+// Note for users rolling faster than they may:
+const RATE_LIMITED: &str = "Prithee, slow thy casting, traveller! Mine hands can only roll so fast.";
+
+struct Bot {
+    limiter: RateLimiter,
+    replies: Mutex<Replies>,
+    recent: Mutex<Recent>,
+    tally: Arc<Tally>,
+    // Status lines shown in turn by every shard, since each last connected:
+    presence: Mutex<HashMap<ShardId, Presence>>,
+    // Whether the slash commands were registered, which only the first shard to be ready does:
+    registered: AtomicBool,
+    // Ids of the bot's application and user, known once it is connected:
+    application_id: OnceLock<ApplicationId>,
+    bot_id: OnceLock<UserId>,
+}
+
+// Answer to a message with rolls, so it is clear whose rolls they are. Nobody is mentioned by it,
+// not even through the echoed expressions or labels:
+fn reply(msg: &Message) -> CreateMessage {
+    CreateMessage::new().reference_message(msg).allowed_mentions(CreateAllowedMentions::new())
+}
+
+// Message of the response to an interaction, with the given buttons:
+fn interaction_message(result: Response, ephemeral: bool, buttons: Vec<CreateButton>) -> CreateInteractionResponseMessage {
+    match result {
+        Response::Content(content) => CreateInteractionResponseMessage::new().content(content),
+        Response::Embeds(note, embeds) if note.is_empty() => CreateInteractionResponseMessage::new().embeds(embeds),
+        Response::Embeds(note, embeds) => CreateInteractionResponseMessage::new().content(note).embeds(embeds),
+        Response::File(note, file) => CreateInteractionResponseMessage::new().content(note).add_file(file),
+        // Split beforehand, so only the first part answers:
+        Response::Parts(parts) => CreateInteractionResponseMessage::new().content(parts.into_iter().next().unwrap_or_default()),
+    }.ephemeral(ephemeral).components(commands::roll::row(buttons))
+}
+
+// Edit of a deferred response to an interaction, with the given buttons:
+fn interaction_edit(result: Response, buttons: Vec<CreateButton>) -> EditInteractionResponse {
+    match result {
+        Response::Content(content) => EditInteractionResponse::new().content(content),
+        Response::Embeds(note, embeds) if note.is_empty() => EditInteractionResponse::new().embeds(embeds),
+        Response::Embeds(note, embeds) => EditInteractionResponse::new().content(note).embeds(embeds),
+        Response::File(note, file) => EditInteractionResponse::new().content(note).new_attachment(file),
+        Response::Parts(parts) => EditInteractionResponse::new().content(parts.into_iter().next().unwrap_or_default()),
+    }.components(commands::roll::row(buttons))
+}
+
+// Messages following up the response to an interaction, e.g.: the rest of a long roll. The buttons
+// go below the last one:
+fn followups(parts: Vec<String>, ephemeral: bool, buttons: Vec<CreateButton>) -> Vec<CreateInteractionResponseFollowup> {
+    let mut followups = parts.into_iter().map(|part| CreateInteractionResponseFollowup::new().content(part).ephemeral(ephemeral).allowed_mentions(CreateAllowedMentions::new())).collect::<Vec<_>>();
+    if let Some(last) = followups.pop() {
+        followups.push(last.components(commands::roll::row(buttons)));
+    }
+    followups
+}
+
+// Response to an interaction, and the messages following it up; the buttons go below the last one:
+fn split(result: Response, buttons: Vec<CreateButton>, ephemeral: bool) -> (Response, Vec<CreateButton>, Vec<CreateInteractionResponseFollowup>) {
+    let (result, parts) = result.split();
+    if parts.is_empty() {
+        (result, buttons, Vec::new())
+    } else {
+        (result, Vec::new(), followups(parts, ephemeral, buttons))
+    }
+}
+
+// Send a response to a message with rolls, keeping track of it:
+async fn respond(ctx: &Context, msg: &Message, response: CreateMessage, sent: &mut Vec<MessageId>) {
+    match with_retries(|| msg.channel_id.send_message(&ctx.http, response.clone())).await {
+        Ok(response) => sent.push(response.id),
+        Err(why) => {
+            warn!("Could not respond to message {} in channel {} of {:?}: {why}", msg.id, msg.channel_id, msg.guild_id);
+            metrics::get().send_failure("message");
+        },
+    }
+}
+
+// Message delivered through a webhook:
+fn hooked() -> ExecuteWebhook {
+    ExecuteWebhook::new().allowed_mentions(CreateAllowedMentions::new())
+}
+
+// Deliver a response to a message with rolls through the channel's webhook (if it has one), under
+// the name of its author, falling back to sending it as usual:
+async fn deliver(ctx: &Context, msg: &Message, webhook: Option<&(WebhookId, String)>, message: CreateMessage, hooked: ExecuteWebhook, sent: &mut Vec<MessageId>) {
+    if let Some((webhook_id, token)) = webhook {
+        let name = msg.member.as_ref().and_then(|member| member.nick.clone()).unwrap_or_else(|| msg.author.global_name.clone().unwrap_or(msg.author.name.clone()));
+        let hooked = hooked.username(name);
+        match with_retries(|| hooked.clone().execute(&ctx.http, (*webhook_id, token.as_str(), true))).await {
+            Ok(delivered) => {
+                sent.extend(delivered.map(|delivered| delivered.id));
+                return;
+            },
+            Err(why) => {
+                warn!("Could not deliver the response to message {} through the webhook of channel {}: {why}", msg.id, msg.channel_id);
+                metrics::get().send_failure("webhook");
+            },
+        }
+    }
+    respond(ctx, msg, message, sent).await;
+}
+
+/// Where a roll was made, as recorded in the roll statistics: a link to its
+/// response when there is one anyone can see, or else a mention of its channel.
+fn place(response: Option<MessageId>, channel_id: ChannelId, guild_id: Option<GuildId>) -> String {
+    response.map_or_else(|| channel_id.mention().to_string(), |response| response.link(channel_id, guild_id))
+}
+
+// Respond to a slash command. Should it have been responded to already (e.g.: by an attempt whose
+// answer got lost on the way back), the response is set anew instead:
+async fn answer(ctx: &Context, command: &CommandInteraction, result: Response, ephemeral: bool, buttons: Vec<CreateButton>) -> Result<(), SerenityError> {
+    let (result, buttons, followups) = split(result, buttons, ephemeral);
+    let builder = CreateInteractionResponse::Message(interaction_message(result.clone(), ephemeral, buttons.clone()));
+    match with_retries(|| command.create_response(&ctx.http, builder.clone())).await {
+        Err(why) if retry::code(&why) == Some(ALREADY_ACKNOWLEDGED) => {
+            with_retries(|| command.edit_response(&ctx.http, interaction_edit(result.clone(), buttons.clone()))).await?;
+        },
+        answered => answered?,
+    }
+    for followup in followups {
+        with_retries(|| command.create_followup(&ctx.http, followup.clone())).await?;
+    }
+    Ok(())
+}
 
 #[serenity::async_trait]
 impl EventHandler for Bot {
     // Process slash commands:
+    #[instrument(skip_all, fields(shard = %ctx.shard_id, interaction_id = %interaction.id()))]
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
         if let Interaction::Command(ref command) = interaction {
             let cmd_response = match command.data.name.as_str() {
-                "ping" => commands::ping::run(&command.data.options(), &ctx, &interaction).await,
-                "license" => commands::license::run(&command.data.options()),
-                "code" => commands::code::run(&command.data.options()),
-                _ => None,
+                "roll" | "r" => {
+                    if let Some(expression) = command.data.options().iter().find_map(|option| match option {
+                        ResolvedOption { name: "expression", value: ResolvedValue::String(expression), .. } => Some(*expression),
+                        _ => None,
+                    }) {
+                        self.recent.lock().expect("No recent rolls?").push(command.user.id, expression);
+                    }
+                    // Slow rolls (e.g.: exploding dice asking RANDOM.ORG time and again) are deferred,
+                    // so Discord does not give up on them. Whether they are hidden cannot change later:
+                    let options = command.data.options();
+                    let defer = async {
+                        let builder = CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new().ephemeral(commands::roll::hidden(&options, command.guild_id)));
+                        if let Err(why) = with_retries(|| command.create_response(&ctx.http, builder.clone())).await {
+                            warn!("Could not defer slash command in channel {} of {:?}: {why}", command.channel_id, command.guild_id);
+                            metrics::get().send_failure("slash_command");
+                        }
+                    };
+                    let roll = commands::roll::run(&options, &ctx, &command.user, command.channel_id, command.guild_id, &self.tally);
+                    match patiently(roll, ROLL_PATIENCE, defer).await {
+                        (Some((result, ephemeral, buttons, unrecorded)), true) => {
+                            let (result, buttons, followups) = split(result, buttons, ephemeral);
+                            let answered = async {
+                                let message = with_retries(|| command.edit_response(&ctx.http, interaction_edit(result.clone(), buttons.clone()))).await?;
+                                for followup in followups {
+                                    with_retries(|| command.create_followup(&ctx.http, followup.clone())).await?;
+                                }
+                                Ok::<_, SerenityError>(message)
+                            };
+                            let message = answered.await.map_err(|why| {
+                                warn!("Could not respond to deferred slash command in channel {} of {:?}: {why}", command.channel_id, command.guild_id);
+                                metrics::get().send_failure("slash_command");
+                            }).ok();
+                            unrecorded.record(&place(message.filter(|_| !ephemeral).map(|message| message.id), command.channel_id, command.guild_id));
+                            return;
+                        },
+                        (response, _) => response.map(|(result, ephemeral, buttons, unrecorded)| (result, ephemeral, buttons, Some(unrecorded))),
+                    }
+                },
+                "check" => commands::check::run(&command.data.options(), &command.user, command.channel_id, command.guild_id, &self.tally).await
+                    .map(|(content, ephemeral, unrecorded)| (Response::Content(content), ephemeral, Vec::new(), Some(unrecorded))),
+                "license" => commands::license::run(&command.data.options()).map(|(response, ephemeral)| (response, ephemeral, Vec::new(), None)),
+                name => match name {
+                    "auditinfo" => commands::auditinfo::run(&command.data.options()),
+                    "character" => commands::character::run(&command.data.options(), command.user.id, command.guild_id),
+                    "dicechannel" => commands::dicechannel::run(&command.data.options(), command.guild_id, command.channel_id),
+                    "embeds" => commands::embeds::run(&command.data.options(), command.guild_id),
+                    "explain" => commands::explain::run(&command.data.options(), command.guild_id),
+                    "importaliases" => commands::importaliases::run(&command.data.options(), command.user.id, command.guild_id).await,
+                    "invite" => commands::invite::run(&command.data.options(), self.application_id.get().copied()),
+                    "ping" => commands::ping::run(&command.data.options(), &ctx, &interaction).await,
+                    "preferences" => commands::preferences::run(&command.data.options(), command.user.id),
+                    "mystats" => commands::mystats::run(&command.data.options(), command.user.id, command.guild_id),
+                    "code" => commands::code::run(&command.data.options()),
+                    "flip" => commands::flip::run(&command.data.options()).await,
+                    "probability" => commands::probability::run(&command.data.options(), command.guild_id),
+                    "settings" => commands::settings::run(&command.data.options(), command.guild_id, command.channel_id),
+                    "shuffle" => commands::shuffle::run(&command.data.options(), command.user.id).await,
+                    "statroll" => commands::statroll::run(&command.data.options()).await,
+                    "uptime" => commands::uptime::run(&command.data.options(), &self.tally),
+                    "table" => commands::table::run(&command.data.options(), command.user.id, command.guild_id).await,
+                    "verify" => commands::verify::run(&command.data.options()),
+                    "version" => commands::version::run(&command.data.options()),
+                    _ => None,
+                }.map(|(content, ephemeral)| (Response::Content(content), ephemeral, Vec::new(), None)),
+            };
+
+            if let Some((result, ephemeral, buttons, unrecorded)) = cmd_response {
+                let answered = answer(&ctx, command, result, ephemeral, buttons).await;
+                if let Err(why) = &answered {
+                    warn!("Could not respond to slash command /{} in channel {} of {:?}: {why}", command.data.name, command.channel_id, command.guild_id);
+                    metrics::get().send_failure("slash_command");
+                }
+                // Rolls are counted once their response is sent, so it can be linked to:
+                if let Some(unrecorded) = unrecorded {
+                    let message = match answered {
+                        Ok(()) if !ephemeral => command.get_response(&ctx.http).await.map_err(|why| warn!("Could not get the response to /{}: {why}", command.data.name)).ok(),
+                        _ => None,
+                    };
+                    unrecorded.record(&place(message.map(|message| message.id), command.channel_id, command.guild_id));
+                }
+            }
+        }
+
+        // Suggest the latest expressions of the user, and then their macros, only from memory and the
+        // local database so it is fast enough for Discord:
+        if let Interaction::Autocomplete(ref autocomplete) = interaction {
+            let Some(typed) = autocomplete.data.autocomplete().filter(|option| option.name == "expression").map(|option| option.value) else {
+                return;
             };
+            let mut suggestions = self.recent.lock().expect("No recent rolls?").suggest(autocomplete.user.id, typed);
+            suggestions.extend(commands::importaliases::suggest(autocomplete.user.id, typed));
+            suggestions.truncate(MAX_SUGGESTIONS);
+            let choices = suggestions.into_iter().fold(CreateAutocompleteResponse::new(), |choices, expression| choices.add_string_choice(expression.clone(), expression));
+            if let Err(why) = autocomplete.create_response(&ctx.http, CreateInteractionResponse::Autocomplete(choices)).await {
+                warn!("Could not suggest expressions: {why}");
+                metrics::get().send_failure("autocomplete");
+            }
+        }
 
-            if let Some((result, ephemeral)) = cmd_response {
-                let data = CreateInteractionResponseMessage::new().content(result).ephemeral(ephemeral);
-                let builder = CreateInteractionResponse::Message(data);
-                if let Err(why) = command.create_response(&ctx.http, builder).await {
-                    println!("Could not respond to slash command: {why}");
+        // Process buttons => Re-rolls, answered with a new message so the first result remains, and
+        // rolls with advantage or disadvantage, shown along with the first result:
+        if let Interaction::Component(ref component) = interaction {
+            // Line too long to be added to the message, sent on its own instead:
+            let mut followup = None;
+            // Rest of a re-roll too long for a single message:
+            let mut rest = Vec::new();
+            // Rolls of a re-roll, counted once it is sent:
+            let mut rerolled = None;
+            let builder = if let Some((result, ephemeral, buttons, unrecorded)) = commands::roll::reroll(component, &self.tally).await {
+                let (result, buttons, followups) = split(result, buttons, ephemeral);
+                rest = followups;
+                rerolled = Some((unrecorded, ephemeral));
+                CreateInteractionResponse::Message(interaction_message(result, ephemeral, buttons))
+            } else {
+                match commands::roll::advantage(component, &self.tally).await {
+                    Some(Ok(line)) => {
+                        // Embeds are left as they were, below the line:
+                        let content = match component.message.content.as_str() {
+                            "" => line.clone(),
+                            shown => format!("{}\n{}", shown, line),
+                        };
+                        let data = CreateInteractionResponseMessage::new().components(commands::roll::spent(&component.message));
+                        if content.chars().count() > dice::MESSAGE_LENGTH {
+                            followup = Some(line);
+                            CreateInteractionResponse::UpdateMessage(data)
+                        } else {
+                            CreateInteractionResponse::UpdateMessage(data.content(content))
+                        }
+                    },
+                    Some(Err(refusal)) => CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(refusal).ephemeral(true)),
+                    None => return,
+                }
+            };
+            let responded = with_retries(|| component.create_response(&ctx.http, builder.clone())).await;
+            if let Err(why) = &responded {
+                warn!("Could not respond to button in channel {} of {:?}: {why}", component.channel_id, component.guild_id);
+                metrics::get().send_failure("button");
+            }
+            if let Some((unrecorded, ephemeral)) = rerolled {
+                let message = match responded {
+                    Ok(()) if !ephemeral => component.get_response(&ctx.http).await.map_err(|why| warn!("Could not get the response to a re-roll: {why}")).ok(),
+                    _ => None,
+                };
+                unrecorded.record(&place(message.map(|message| message.id), component.channel_id, component.guild_id));
+            }
+            for followup in rest {
+                if let Err(why) = with_retries(|| component.create_followup(&ctx.http, followup.clone())).await {
+                    warn!("Could not follow up on button in channel {} of {:?}: {why}", component.channel_id, component.guild_id);
+                    metrics::get().send_failure("button");
+                    break;
+                }
+            }
+            if let Some(line) = followup {
+                let builder = CreateInteractionResponseFollowup::new().content(line).allowed_mentions(CreateAllowedMentions::new());
+                if let Err(why) = with_retries(|| component.create_followup(&ctx.http, builder.clone())).await {
+                    warn!("Could not follow up on button in channel {} of {:?}: {why}", component.channel_id, component.guild_id);
+                    metrics::get().send_failure("button");
                 }
             }
         }
     }
 
     // Process text messages => Dice rolls:
+    #[instrument(skip_all, fields(shard = %ctx.shard_id, guild_id = ?msg.guild_id, channel_id = %msg.channel_id, message_id = %msg.id))]
     async fn message(&self, ctx: Context, msg: Message) {
         // Ignore messages from other bots:
         if msg.author.bot {
             return;
         }
 
-        let mut content = msg.content;
-        let mut response = Vec::new();
-
-        // Shortcut roll message, e.g.: [d] [3d] [d40]
-        let dice_shortcut = Regex::new(r"\[d(?<bonus> ?[+-] ?-?\d+)?\]").expect("No shortcut regex?");
-        content = dice_shortcut.replace_all(&content, "[1d20$bonus]").into_owned();
-        let dice_shortcut_amount = Regex::new(r"\[(?<amount>\d+)d(?<bonus> ?[+-] ?-?\d+)?\]").expect("No amount shortcut regex?");
-        content = dice_shortcut_amount.replace_all(&content, "[${amount}d20$bonus]").into_owned();
-        let dice_shortcut_size = Regex::new(r"\[d(?<size>\d+)(?<bonus> ?[+-] ?-?\d+)?\]").expect("No size shortcut regex?");
-        content = dice_shortcut_size.replace_all(&content, "[1d$size$bonus]").into_owned();
-
-        // Regular roll message, e.g.: [2d20]
-        let dice = Regex::new(r"(?<roll>\[\d+d\d+)\]").expect("No un-bonused regex?");
-        content = dice.replace_all(&content, "$roll+0]").into_owned();
-
-        // Negative bonus roll message, e.g.: [2d20-5]
-        let dice_and_neg_bonus = Regex::new(r"(?<roll>\[\d+d\d+) ?- ?(?<bonus>\d+\])").expect("No negative-bonused regex?");
-        content = dice_and_neg_bonus.replace_all(&content, "$roll+-$bonus").into_owned();
-
-        // Bonus roll message, e.g.: [2d20+5]
-        let dice_and_bonus = Regex::new(r"\[(\d+)d(\d+) ?\+ ?(-?\d+)\]").expect("No regex?");
-        for (_, [rolls_str, size_str, bonus_str]) in dice_and_bonus.captures_iter(&content).map(|c| c.extract()) {
-            // Avoid an i64-parse error:
-            // (2**63 is 19 characters long.)
-            if rolls_str.chars().count() > 18 || size_str.chars().count() > 18 || bonus_str.chars().count() > 18 {
-                let _ = msg.channel_id.say(&ctx.http, "That numeral is overlarge for mine ancient, fatigued orbs to even peruse. I am apprehensive thou shalt require another's aid. Should thou seek assistance with lesser matters, I am at thy service!").await;
-                continue;
-            }
-
-            let rolls = rolls_str.parse::<i64>().expect("No rolls?");
-            let size = size_str.parse::<i64>().expect("No size?");
-            let bonus = bonus_str.parse::<i64>().expect("No bonus?");
-            
-            if size > 1 && rolls > 0 {
-                // Arbitrary limits check, so only reasonable amounts of numbers of reasonable size are returned:
-                if rolls > 20i64 {
-                    response.push("Inquired for overmuch rolls. I may only proffer up to twain score!".to_owned());
-                    continue;
-                }
-                if size > 1_000i64 {
-                    response.push("Entreaded for an excessive sum. I can only reckon unto a thousand!".to_owned());
-                    continue;
-                }
-                if bonus > rolls * size * 10 {
-                    response.push("Besought an excessive boon. Be not so covetous, traveller!".to_owned());
-                    continue;
-                }
-
-                let url = format!("https://www.random.org/integers/?num={}&min=1&max={}&col=1&base=10&format=plain&rnd=new", rolls, size);
-                let res = reqwest::get(url).await.expect("No random?");
-                let body = res.text().await.expect("No numbers?");
-
-                // Comma-separated sequence of random numbers:
-                let mut sequence: String = "".to_owned();
-                let is_truly_random: bool;
-                if body.chars().nth(0).expect("No webpage body?").is_digit(10) {
-                    sequence = format!("{}", body.replace("\n", ", "));
-                    is_truly_random = true;
-                } else {
-                    // Fallback in case random.org does not work for some reason (has happened):
-                    use rand::prelude::*;
+        // Some channels are not meant for rolls, e.g.: "[art] new piece"
+        if commands::dicechannel::disabled(msg.guild_id, msg.channel_id) {
+            return;
+        }
 
-                    let mut rng = thread_rng();
+        // Characters are only looked up for rolls that may have variables, e.g.: [1d20 + $dex]
+        let variables = msg.content.contains('$').then(|| characters::variables(msg.author.id, msg.guild_id)).flatten();
+        let (rolls, capped) = dice::scan_with(&msg.content, &commands::settings::delimiters(msg.guild_id), commands::settings::strict(msg.guild_id), commands::settings::syntax(msg.guild_id), variables.as_ref());
+        if rolls.is_empty() {
+            return;
+        }
+        let mut sent = Vec::new();
+        match self.limiter.check(msg.author.id.get(), Instant::now()) {
+            Limit::Allowed => {},
+            Limit::Limited { notify: true } => {
+                respond(&ctx, &msg, reply(&msg).content(RATE_LIMITED), &mut sent).await;
+                self.replies.lock().expect("No replies?").insert(msg.id, sent);
+                return;
+            },
+            Limit::Limited { notify: false } => return,
+        }
 
-                    for _ in 0..rolls {
-                        sequence.push_str(&format!("{}, ", rng.gen_range(1..size+1)));
+        let as_embeds = commands::embeds::enabled(msg.guild_id);
+        let preferences = commands::preferences::of(msg.author.id);
+        let mut response = Vec::new();
+        let mut embeds = Vec::new();
+        // Expressions rolled, and their results, for the buttons that follow them:
+        let mut rolled = Vec::new();
+        let mut results = Vec::new();
+        // Results only for the author, e.g.: [gm 2d6]
+        let mut secrets = Vec::new();
+        // Rolls are counted once their responses are sent, so they can be linked to (but not secret ones):
+        let mut unrecorded = Unrecorded::new(msg.author.id, msg.guild_id);
+        let mut unrecorded_secrets = Unrecorded::new(msg.author.id, msg.guild_id);
+        for (expr, roll) in rolls {
+            let expr = expr.as_str();
+            if let Err(why) = &roll {
+                metrics::get().parse_error(why);
+            }
+            match roll {
+                Err(why @ DiceError::NumberTooLarge(_)) => {
+                    respond(&ctx, &msg, reply(&msg).content(why.quoting(expr)), &mut sent).await;
+                },
+                Err(why) if as_embeds => embeds.push(embed::error(&msg.author, expr, &why.quoting(expr))),
+                Err(why) => response.push(why.quoting(expr)),
+                Ok(roll) => {
+                    let secret = roll.is_secret();
+                    let roll = if preferences.compact() { Roll::Compact(Box::new(roll)) } else { roll };
+                    let started = Instant::now();
+                    let result = roll.roll().instrument(info_span!("roll", expression = expr)).await;
+                    debug!(expression = expr, total = ?result.total(), truly_random = result.is_truly_random(), latency_ms = started.elapsed().as_millis() as u64, "Rolled");
+                    audit::record(&msg.author, msg.channel_id, expr, &result);
+                    self.tally.count(&result);
+                    if secret {
+                        unrecorded_secrets.add(expr, &result);
+                        secrets.push(styled(preferences.style(), || verify::with_code(expr, &result)));
+                        continue;
                     }
-                    is_truly_random = false;
-                }
-                sequence.pop(); // Remove trailing space
-                sequence.pop(); // Remove trailing comma
-
-                let sum = sequence.split(", ").map(|n| n.parse::<i64>().expect("No random number?")).reduce(|a, b| a + b).expect("No reduction?");
-
-                if bonus == 0 {
-                    if rolls == 1 {
-                        response.push(format!("{}", sequence));
+                    if as_embeds {
+                        embeds.push(styled(preferences.style(), || embed::roll(&msg.author, expr, expr, &result)));
                     } else {
-                        response.push(format!("{} = {}", sequence, sum + bonus));
+                        response.push(styled(preferences.style(), || verify::with_code(expr, &result)));
                     }
-                } else {
-                    response.push(format!("{} + {} = {}", sequence, bonus, sum + bonus));
-                }
-
-                if !is_truly_random {
-                    // If denedé used the fallback PRNG, indicate it in the response message:
-                    response.last_mut().unwrap().push_str(" [pseudo-random]");
-                }
-            } else {
-                // Smug answer for d1s, d0s, and 0 rolls:
-                if rolls > 1_000_000_000 || size > 1_000_000_000 || bonus > 1_000_000_000 {
-                   response.push(format!("Deem me not a fool, traveller. Be earnest and cease thy jesting with me!"));
-                } else {
-                   response.push(format!("I deem thy sagacity to be not especially lofty, thus I shall provide a rejoinder to thy entreaty, as a gesture of courtesy: {}", rolls * size + bonus));
+                    unrecorded.add(expr, &result);
+                    rolled.push(expr.to_string());
+                    // Without its brackets, as written for /roll:
+                    self.recent.lock().expect("No recent rolls?").push(msg.author.id, &expr[1..expr.len() - 1]);
+                    results.push(result);
+                },
+            }
+        }
+        if !secrets.is_empty() {
+            let mut whispered = true;
+            for chunk in dice::chunks(&secrets) {
+                if let Err(why) = with_retries(|| msg.author.direct_message(&ctx, CreateMessage::new().content(chunk.clone()))).await {
+                    warn!("Could not send a secret roll to {}: {why}", msg.author.id);
+                    metrics::get().send_failure("secret_roll");
+                    whispered = false;
+                    break;
                 }
             }
+            if !whispered {
+                response.push(SECRET_UNSENT.to_string());
+            } else if config::get().secret_note {
+                response.push(commands::roll::secret_note(&msg.author));
+            }
+        }
+        unrecorded_secrets.record(&place(None, msg.channel_id, msg.guild_id));
+        if capped {
+            response.push(dice::EXPRESSIONS_CAPPED.to_string());
         }
-        // Join all rolls in the corresponding amount of messages:
-        let mut response_str = "".to_owned();
-        for roll in &response {
-            if response_str.len() + roll.len() > 2000 {
-                let _ = msg.channel_id.say(&ctx.http, response_str).await;
-                response_str = "".to_owned();
+
+        // Each message, as sent by the bot and as delivered through a webhook:
+        let mut messages = Vec::new();
+        if as_embeds {
+            messages.extend(embed::messages(embeds).into_iter().map(|embeds| (reply(&msg).embeds(embeds.clone()), hooked().embeds(embeds))));
+        }
+        // Join all rolls in the corresponding amount of messages (with embeds, only the note of too
+        // many rolls is left, if any):
+        if !response.is_empty() {
+            messages.extend(dice::chunks(&response).into_iter().map(|chunk| (reply(&msg).content(chunk.clone()), hooked().content(chunk))));
+        }
+        // The last message holds the buttons of the rolls (which webhooks cannot carry):
+        if let Some((last, hooked)) = messages.pop() {
+            messages.push((last.components(commands::roll::row(commands::roll::buttons(msg.author.id, &rolled.join(" "), &results))), hooked));
+        }
+        let webhook = commands::settings::webhook(msg.guild_id, msg.channel_id);
+        let first = sent.len();
+        for (message, hooked) in messages {
+            deliver(&ctx, &msg, webhook.as_ref(), message, hooked, &mut sent).await;
+        }
+        unrecorded.record(&place(sent.get(first).copied(), msg.channel_id, msg.guild_id));
+        self.replies.lock().expect("No replies?").insert(msg.id, sent);
+    }
+
+    // Delete the responses to a message with rolls along with it, so they are not left orphaned:
+    async fn message_delete(&self, ctx: Context, channel_id: ChannelId, deleted_message_id: MessageId, _guild_id: Option<GuildId>) {
+        let responses = self.replies.lock().expect("No replies?").remove(deleted_message_id);
+        for response in responses {
+            if let Err(why) = channel_id.delete_message(&ctx.http, response).await {
+                warn!("Could not delete the response {response} to {deleted_message_id}: {why}");
             }
-            response_str.push_str(&format!("{}\n", roll));
         }
-        // Send last response:
-        let _ = msg.channel_id.say(&ctx.http, response_str).await;
+    }
+
+    async fn message_delete_bulk(&self, ctx: Context, channel_id: ChannelId, multiple_deleted_messages_ids: Vec<MessageId>, guild_id: Option<GuildId>) {
+        for deleted_message_id in multiple_deleted_messages_ids {
+            self.message_delete(ctx.clone(), channel_id, deleted_message_id, guild_id).await;
+        }
+    }
+
+    // Servers are known from the moment the bot connects, so those that are not were just joined,
+    // rather than becoming available again on reconnecting:
+    async fn guild_create(&self, ctx: Context, guild: Guild, is_new: Option<bool>) {
+        let joined = self.tally.join(guild.id) && is_new != Some(false);
+        match self.bot_id.get() {
+            Some(&bot_id) if joined => welcome::greet(&ctx, &guild, bot_id).await,
+            _ => {},
+        }
+    }
+
+    // Servers that are merely unavailable for a while are still served:
+    async fn guild_delete(&self, _ctx: Context, incomplete: UnavailableGuild, _full: Option<Guild>) {
+        if !incomplete.unavailable {
+            self.tally.leave(incomplete.id);
+        }
     }
 
     async fn ready(&self, ctx: Context, ready: Ready) {
+        let shard = ready.shard.map_or(ctx.shard_id.to_string(), |shard| format!("{}/{}", shard.id, shard.total));
         match ready.user.discriminator {
-            Some(discriminator) => println!("{}#{discriminator:#?} is connected.", ready.user.name),
-            None => println!("{} is connected.", ready.user.name),
+            Some(discriminator) => info!("{}#{discriminator:#?} is connected (shard {shard}).", ready.user.name),
+            None => info!("{} is connected (shard {shard}).", ready.user.name),
         }
+        self.tally.connect(ready.guilds.iter().map(|guild| guild.id));
+        self.application_id.get_or_init(|| ready.application.id);
+        self.bot_id.get_or_init(|| ready.user.id);
+        // Replacing the shard's previous status lines stops them:
+        self.presence.lock().expect("No presence?").insert(ctx.shard_id, Presence::start(ctx.clone(), Arc::clone(&self.tally)));
 
-        // Register slash commands:
-        let commands = Command ::set_global_commands(&ctx.http, vec![
+        health::ready();
+
+        // Slash commands are global, so they are registered only once, not by every shard (nor on
+        // every reconnection):
+        if self.registered.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let mut global = vec![
+            commands::auditinfo::register(),
+            commands::character::register(),
+            commands::check::register(),
+            commands::dicechannel::register(),
+            commands::embeds::register(),
+            commands::explain::register(),
+            commands::importaliases::register(),
+            commands::invite::register(),
             commands::ping::register(),
+            commands::preferences::register(),
             commands::license::register(),
+            commands::mystats::register(),
             commands::code::register(),
-        ]).await.unwrap();
+            commands::flip::register(),
+            commands::probability::register(),
+            commands::settings::register(),
+            commands::shuffle::register(),
+            commands::statroll::register(),
+            commands::table::register(),
+            commands::uptime::register(),
+            commands::verify::register(),
+            commands::version::register(),
+        ];
+        // /roll comes along with its alias, /r:
+        global.extend(commands::roll::register());
+        match Command::set_global_commands(&ctx.http, global).await {
+            Ok(commands) => info!("Registered the following commands: {:?}", commands.into_iter().map(|cmd| cmd.name).collect::<Vec<String>>()),
+            Err(why) => {
+                error!("Could not register the slash commands: {why}");
+                // Let the next shard to be ready (or this one, on reconnecting) try again:
+                self.registered.store(false, Ordering::SeqCst);
+            },
+        }
+    }
+}
 
-        println!("Registered the following commands: {:?}", commands.into_iter().map(|cmd| cmd.name).collect::<Vec<String>>());
+fn main() -> ExitCode {
+    match Cli::parse().command {
+        Some(cli::Command::Roll { expression, times, json }) => cli::roll(&expression, times, json),
+        None => bot(),
     }
 }
 
 #[tokio::main]
-async fn main() {
-    let token = env::var("DISCORD_TOKEN").expect("No tokens?");
-    let mut client = Client::builder(&token, GatewayIntents::default() | GatewayIntents::MESSAGE_CONTENT).event_handler(Bot).await.expect("No clients?");
+async fn bot() -> ExitCode {
+    // Every mistake in the settings is told at once, before connecting to Discord:
+    let config = match config::load() {
+        Ok((config, report)) => {
+            // RUST_LOG (or the configuration file) decides what is logged, e.g.: RUST_LOG=denede_discord_bot=debug
+            tracing_subscriber::fmt().with_env_filter(EnvFilter::new(&config.log_filter)).init();
+            info!("Settings:\n{report}");
+            config
+        },
+        Err(report) => {
+            eprintln!("Denedé cannot start with these settings:\n{report}");
+            return ExitCode::FAILURE;
+        },
+    };
+    database::open().unwrap_or_else(|why| panic!("{why}"));
+    // Fetch random numbers ahead of time, so rolls need not wait for RANDOM.ORG:
+    tokio::spawn(pool::refill());
 
-    client.start().await.expect("No work?");
+    let bot = Bot {
+        limiter: RateLimiter::from_env(),
+        replies: Mutex::new(Replies::default()),
+        recent: Mutex::new(Recent::default()),
+        tally: Arc::default(),
+        presence: Mutex::default(),
+        registered: AtomicBool::new(false),
+        application_id: OnceLock::new(),
+        bot_id: OnceLock::new(),
+    };
+    let mut client = Client::builder(&config.token, GatewayIntents::default() | GatewayIntents::MESSAGE_CONTENT).event_handler(bot).await.expect("No clients?");
+    // /ping tells how the gateway connection fares:
+    client.data.write().await.insert::<commands::ping::ShardManagerContainer>(client.shard_manager.clone());
+
+    let server = config.metrics_port.map(|port| Server::start(port).unwrap_or_else(|why| panic!("{why}")));
+    let api = config.api_port.map(|port| api::start(port).unwrap_or_else(|why| panic!("{why}")));
+    let watcher = health::watch(client.shard_manager.clone());
+
+    // Discord tells how many shards are needed, unless DENEDE_SHARDS (or the configuration file) says:
+    let started = match config.shards {
+        Some(shards) => client.start_shards(shards).await,
+        None => client.start_autosharded().await,
+    };
+    // The metrics server and the roll API go down along with the bot:
+    watcher.abort();
+    for server in [server, api].into_iter().flatten() {
+        server.stop().await;
+    }
+    started.expect("No work?");
+    ExitCode::SUCCESS
 }
 
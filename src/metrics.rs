@@ -0,0 +1,140 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
+
+use crate::dice::{DiceError, DieKind};
+
+/// Counters and histograms of what the bot does, exposed in Prometheus' format.
+pub struct Metrics {
+    registry: Registry,
+    /// Rolls served, in chat or through slash commands.
+    pub rolls: IntCounter,
+    /// Groups of dice rolled, by kind, e.g.: "regular" for 3d6, "drop" for 4d6 dropping the lowest.
+    pub dice: IntCounterVec,
+    /// Expressions that could not be rolled, by kind of error.
+    pub parse_errors: IntCounterVec,
+    /// Requests to RANDOM.ORG, by whether they were answered with numbers.
+    pub randomorg: IntCounterVec,
+    pub randomorg_latency: Histogram,
+    /// Messages and responses that could not be sent to Discord, by what they were.
+    pub send_failures: IntCounterVec,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(|| {
+    let counter = |name: &str, help: &str| IntCounter::new(name, help).expect("No counter?");
+    let counters = |name: &str, help: &str, label: &str| IntCounterVec::new(Opts::new(name, help), &[label]).expect("No counters?");
+    let metrics = Metrics {
+        registry: Registry::new_custom(Some("denede".to_string()), None).expect("No registry?"),
+        rolls: counter("rolls_total", "Rolls served."),
+        dice: counters("dice_total", "Groups of dice rolled, by kind.", "kind"),
+        parse_errors: counters("parse_errors_total", "Expressions that could not be rolled, by kind of error.", "kind"),
+        randomorg: counters("randomorg_requests_total", "Requests to RANDOM.ORG, by outcome.", "outcome"),
+        randomorg_latency: Histogram::with_opts(HistogramOpts::new("randomorg_latency_seconds", "Time taken by RANDOM.ORG to answer.")
+            .buckets(vec![0.05, 0.1, 0.25, 0.5, 0.75, 1.0, 1.5, 2.5, 5.0])).expect("No histogram?"),
+        send_failures: counters("send_failures_total", "Messages and responses that could not be sent to Discord, by what they were.", "what"),
+    };
+    let registered = [
+        metrics.registry.register(Box::new(metrics.rolls.clone())),
+        metrics.registry.register(Box::new(metrics.dice.clone())),
+        metrics.registry.register(Box::new(metrics.parse_errors.clone())),
+        metrics.registry.register(Box::new(metrics.randomorg.clone())),
+        metrics.registry.register(Box::new(metrics.randomorg_latency.clone())),
+        metrics.registry.register(Box::new(metrics.send_failures.clone())),
+    ];
+    registered.into_iter().collect::<Result<(), _>>().expect("No metrics?");
+    metrics
+});
+
+/// Metrics of the bot since it started.
+pub fn get() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    /// Every metric, in Prometheus' text format.
+    pub fn gather(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer).expect("No encoding?");
+        String::from_utf8(buffer).expect("No text?")
+    }
+
+    pub fn die_kind(&self, kind: &DieKind) {
+        let kind = match kind {
+            DieKind::Regular => "regular",
+            DieKind::KeepMiddle(_) => "keep_middle",
+            DieKind::Drop(_) => "drop",
+            DieKind::Match(_) => "match",
+            DieKind::Shadowrun => "shadowrun",
+            DieKind::Again(_) => "again",
+            DieKind::DropHighest(_) => "drop_highest",
+            DieKind::Explode(_) => "explode",
+            DieKind::Compound(_) => "compound",
+            DieKind::RerollOnce(_) => "reroll_once",
+            DieKind::LowerBound(_) => "lower_bound",
+            DieKind::UpperBound(_) => "upper_bound",
+            DieKind::Fudge => "fudge",
+            DieKind::HeroStun => "hero_stun",
+            DieKind::HeroBody => "hero_body",
+        };
+        self.dice.with_label_values(&[kind]).inc();
+    }
+
+    pub fn parse_error(&self, error: &DiceError) {
+        let kind = match error {
+            DiceError::NumberTooLarge(_) => "number_too_large",
+            DiceError::TooManyDice(_) => "too_many_dice",
+            DiceError::TooManySides(_) => "too_many_sides",
+            DiceError::BonusTooLarge(_) => "bonus_too_large",
+            DiceError::KeepTooMany(_) => "keep_too_many",
+            DiceError::MatchTooMany(_) => "match_too_many",
+            DiceError::MatchTooFew(_) => "match_too_few",
+            DiceError::Trivial(..) => "trivial",
+            DiceError::Jest(_) => "jest",
+            DiceError::UnknownFunction(..) => "unknown_function",
+            DiceError::DivisionByZero(_) => "division_by_zero",
+            DiceError::TooManyCoins(_) => "too_many_coins",
+            DiceError::SymbolicArithmetic(_) => "symbolic_arithmetic",
+            DiceError::SeveralComparisons(_) => "several_comparisons",
+            DiceError::NotRolled(_) => "not_rolled",
+            DiceError::AgainNotD10(_) => "again_not_d10",
+            DiceError::ResultTooLarge(_) => "result_too_large",
+            DiceError::TooComplex(_) => "too_complex",
+            DiceError::IllFormed(_) => "ill_formed",
+            DiceError::Unsupported(..) => "unsupported",
+            DiceError::NotSupported(..) => "not_supported",
+            DiceError::NoCharacter(..) => "no_character",
+            DiceError::UnknownVariable(..) => "unknown_variable",
+            DiceError::Several(_) => "several",
+        };
+        self.parse_errors.with_label_values(&[kind]).inc();
+    }
+
+    /// Note how a request to RANDOM.ORG went, and how long it took.
+    pub fn randomorg_request(&self, answered: bool, latency: Duration) {
+        self.randomorg.with_label_values(&[if answered { "answered" } else { "failed" }]).inc();
+        self.randomorg_latency.observe(latency.as_secs_f64());
+    }
+
+    /// Note that something could not be sent to Discord, e.g.: "message" or "slash_command".
+    pub fn send_failure(&self, what: &str) {
+        self.send_failures.with_label_values(&[what]).inc();
+    }
+}
@@ -0,0 +1,120 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::collections::VecDeque;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::randomorg;
+
+// Bytes kept in the pool, unless the DENEDE_POOL_SIZE environment variable says otherwise:
+const DEFAULT_POOL_SIZE: usize = 10_000;
+// Most numbers RANDOM.ORG hands out in a single request:
+const MAX_BATCH: usize = 10_000;
+
+// Truly random bytes, fetched from RANDOM.ORG ahead of time so rolls need not wait for it:
+struct Pool {
+    bytes: Mutex<VecDeque<u8>>,
+    // Wakes up the refill task when the pool runs low:
+    low: Notify,
+    size: usize,
+}
+
+fn pool() -> &'static Pool {
+    static POOL: OnceLock<Pool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        // No point in a pool that could never be filled:
+        let size = if randomorg::offline() {
+            0
+        } else {
+            env::var("DENEDE_POOL_SIZE").ok().and_then(|size| size.parse().ok()).unwrap_or(DEFAULT_POOL_SIZE)
+        };
+        Pool { bytes: Mutex::new(VecDeque::with_capacity(size)), low: Notify::new(), size }
+    })
+}
+
+/// Keep the pool topped up, fetching more bytes from RANDOM.ORG whenever it runs low.
+///
+/// Meant to be spawned as a background task; returns right away if the pool is disabled
+/// (DENEDE_POOL_SIZE=0, or offline mode).
+pub async fn refill() {
+    let pool = pool();
+    if pool.size == 0 {
+        return;
+    }
+
+    loop {
+        let missing = pool.size.saturating_sub(pool.bytes.lock().expect("No pool?").len());
+        if missing > 0 {
+            match fetch(missing.min(MAX_BATCH)).await {
+                Some(bytes) => {
+                    pool.bytes.lock().expect("No pool?").extend(bytes);
+                    continue;
+                },
+                // Try again once the pool is drawn from, rolls falling back to live requests meanwhile:
+                None => warn!("Could not refill the random pool from RANDOM.ORG."),
+            }
+        }
+        pool.low.notified().await;
+    }
+}
+
+/// Take `rolls` numbers from 1 to `size` (both included) from the pool, if it holds enough bytes
+/// for all of them. The pool is left untouched otherwise.
+pub fn take(rolls: i64, size: i64) -> Option<Vec<i64>> {
+    let pool = pool();
+    if pool.size == 0 || size < 1 {
+        return None;
+    }
+    let mut bytes = pool.bytes.lock().expect("No pool?");
+
+    // Read as few bytes as possible per number, discarding the values that would make the lowest
+    // numbers more likely than the rest (i.e. the modulo bias):
+    let size = size as u128;
+    let width = (1..=8).find(|&width| 1u128 << (8 * width) >= size).expect("No width?");
+    let span = 1u128 << (8 * width);
+    let limit = span - span % size;
+
+    let mut seq = Vec::new();
+    let mut used = 0;
+    while seq.len() < rolls as usize {
+        if used + width > bytes.len() {
+            pool.low.notify_one();
+            return None;
+        }
+        let value = bytes.range(used..used + width).fold(0u128, |value, &byte| value << 8 | byte as u128);
+        used += width;
+        if value < limit {
+            seq.push((value % size) as i64 + 1);
+        }
+    }
+
+    bytes.drain(..used);
+    if bytes.len() < pool.size / 2 {
+        pool.low.notify_one();
+    }
+    Some(seq)
+}
+
+// Request `amount` random bytes from RANDOM.ORG, if it answers with them:
+async fn fetch(amount: usize) -> Option<Vec<u8>> {
+    let url = randomorg::url(&format!("/integers/?num={}&min=0&max=255&col=1&base=10&format=plain&rnd=new", amount));
+    let numbers = randomorg::request(&url).await?;
+    numbers.into_iter().map(|n| u8::try_from(n).ok()).collect()
+}
@@ -0,0 +1,60 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::gateway::ActivityData;
+use serenity::model::user::OnlineStatus;
+use serenity::prelude::*;
+use tokio::task::JoinHandle;
+
+use crate::config;
+use crate::tally::Tally;
+
+// Status shown unless DENEDE_STATUS sets another:
+const DEFAULT_STATUS: &str = "🎲 /roll 2d6+3";
+// How long each status line is shown before the next one:
+const ROTATION: Duration = Duration::from_secs(5 * 60);
+
+/// Status lines shown in turn as the bot's activity, for as long as this lives.
+pub struct Presence(JoinHandle<()>);
+
+impl Presence {
+    /// Show the usage hint, and then the amount of servers the bot is in, one after the other.
+    pub fn start(ctx: Context, tally: Arc<Tally>) -> Presence {
+        let hint = config::get().status.clone().unwrap_or(DEFAULT_STATUS.to_string());
+        Presence(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ROTATION);
+            for turn in 0usize.. {
+                interval.tick().await;
+                let status = match turn % 2 {
+                    0 => hint.clone(),
+                    _ => format!("🎲 in {} {}", tally.guilds(), if tally.guilds() == 1 { "server" } else { "servers" }),
+                };
+                ctx.set_presence(Some(ActivityData::playing(status)), OnlineStatus::Online);
+            }
+        }))
+    }
+}
+
+// The status stops changing once the bot shuts down or starts showing it anew, e.g.: on reconnecting:
+impl Drop for Presence {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
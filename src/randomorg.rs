@@ -0,0 +1,174 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{dice, metrics};
+
+// Bits of daily quota below which RANDOM.ORG is left alone, so it is never exceeded:
+const MIN_QUOTA: i64 = 10_000;
+// How long to wait before asking RANDOM.ORG again once it is out of quota or fails:
+const COOLDOWN: Duration = Duration::from_secs(10 * 60);
+// How long the remaining quota is trusted before checking it again:
+const QUOTA_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// How long to wait for an answer, unless the RANDOMORG_TIMEOUT_MS environment variable says otherwise:
+const DEFAULT_TIMEOUT_MS: u64 = 1500;
+// Where RANDOM.ORG is, unless the RANDOMORG_URL environment variable says otherwise (e.g.: a proxy):
+const DEFAULT_URL: &str = "https://www.random.org";
+
+// Whether RANDOM.ORG is being left alone (and until when), and when its quota was last checked:
+struct State {
+    cooldown_until: Option<Instant>,
+    quota_checked: Option<Instant>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State { cooldown_until: None, quota_checked: None });
+
+// Single client for every request, so connections to RANDOM.ORG are reused:
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        let timeout = env::var("RANDOMORG_TIMEOUT_MS").ok().and_then(|ms| ms.parse().ok()).unwrap_or(DEFAULT_TIMEOUT_MS);
+        reqwest::Client::builder().timeout(Duration::from_millis(timeout)).build().expect("No client?")
+    })
+}
+
+/// Address of the given path of RANDOM.ORG, e.g.: `url("/quota/?format=plain")`.
+pub fn url(path: &str) -> String {
+    static BASE: OnceLock<String> = OnceLock::new();
+    let base = BASE.get_or_init(|| env::var("RANDOMORG_URL").map_or(DEFAULT_URL.to_string(), |url| url.trim_end_matches('/').to_string()));
+    format!("{}{}", base, path)
+}
+
+/// Whether RANDOM.ORG is never to be contacted (DENEDE_OFFLINE_RNG=1), every roll being
+/// pseudo-random.
+pub fn offline() -> bool {
+    static OFFLINE: OnceLock<bool> = OnceLock::new();
+    *OFFLINE.get_or_init(|| flag("DENEDE_OFFLINE_RNG").unwrap_or(false))
+}
+
+/// Note appended to responses made with the fallback PRNG, kept in italics so it does not stand out
+/// more than the roll itself.
+pub const PSEUDO_RANDOM_NOTE: &str = "*[pseudo-random]*";
+
+/// Whether responses should point out that a roll is pseudo-random.
+///
+/// By default they do, except in offline mode, where every roll would; DENEDE_PSEUDO_RANDOM_NOTE
+/// (0 or 1) overrides this.
+pub fn note_pseudo_random() -> bool {
+    static NOTE: OnceLock<bool> = OnceLock::new();
+    *NOTE.get_or_init(|| flag("DENEDE_PSEUDO_RANDOM_NOTE").unwrap_or(!offline()))
+}
+
+/// Note to append to a response made with numbers that are (or not) truly random: only for
+/// pseudo-random ones, unless the style it is shown with (see [`dice::styled`]) leaves it out.
+pub fn note(truly_random: bool) -> Option<&'static str> {
+    (!truly_random && dice::style().pseudo_random).then_some(PSEUDO_RANDOM_NOTE)
+}
+
+// Value of a yes/no environment variable, if it is set to one:
+pub(crate) fn flag(name: &str) -> Option<bool> {
+    match env::var(name).ok()?.to_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Request numbers (one per line) from the given RANDOM.ORG URL.
+///
+/// Returns None without contacting RANDOM.ORG in offline mode, while its quota is low or after it
+/// has failed recently, so callers fall back to a pseudo-random number generator right away.
+#[instrument(level = "debug", skip_all, fields(url = %url))]
+pub async fn request(url: &str) -> Option<Vec<i64>> {
+    if offline() || !available().await {
+        return None;
+    }
+
+    let started = Instant::now();
+    let numbers = match client().get(url).send().await {
+        Ok(res) if res.status().is_success() => res.text().await.ok().and_then(|body| parse(&body)),
+        Ok(res) => {
+            debug!(status = %res.status(), "RANDOM.ORG refused the request");
+            None
+        },
+        Err(why) => {
+            debug!("Could not reach RANDOM.ORG: {why}");
+            None
+        },
+    };
+    debug!(latency_ms = started.elapsed().as_millis() as u64, answered = numbers.is_some(), "Asked RANDOM.ORG for numbers");
+    metrics::get().randomorg_request(numbers.is_some(), started.elapsed());
+    if numbers.is_none() {
+        // E.g.: it took too long, answered 503, or with a secure connection check instead of numbers (has happened):
+        cool_down("it did not answer with random numbers");
+    }
+    numbers
+}
+
+// Whether RANDOM.ORG may be asked for numbers, checking its remaining quota every now and then:
+async fn available() -> bool {
+    let now = Instant::now();
+    let check_quota = {
+        let mut state = STATE.lock().expect("No state?");
+        match state.cooldown_until {
+            Some(until) if now < until => return false,
+            Some(_) => {
+                state.cooldown_until = None;
+                info!("Cooldown over, asking RANDOM.ORG for random numbers again.");
+            },
+            None => {},
+        }
+        state.quota_checked.is_none_or(|checked| now.duration_since(checked) >= QUOTA_CHECK_INTERVAL)
+    };
+    if !check_quota {
+        return true;
+    }
+
+    let quota = match client().get(url("/quota/?format=plain")).send().await {
+        Ok(res) if res.status().is_success() => res.text().await.ok().and_then(|body| body.trim().parse::<i64>().ok()),
+        _ => None,
+    };
+    STATE.lock().expect("No state?").quota_checked = Some(now);
+    match quota {
+        Some(bits) if bits < MIN_QUOTA => {
+            cool_down(&format!("only {} bits of quota are left", bits));
+            false
+        },
+        // Not knowing the quota is no reason not to try:
+        _ => true,
+    }
+}
+
+// Stop asking RANDOM.ORG for numbers for a while:
+fn cool_down(reason: &str) {
+    let mut state = STATE.lock().expect("No state?");
+    if state.cooldown_until.is_none() {
+        warn!("Not asking RANDOM.ORG for random numbers for {} minutes, since {}. Rolls will be pseudo-random.", COOLDOWN.as_secs() / 60, reason);
+    }
+    state.cooldown_until = Some(Instant::now() + COOLDOWN);
+}
+
+fn parse(body: &str) -> Option<Vec<i64>> {
+    if !body.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    body.lines().map(|n| n.parse::<i64>().ok()).collect()
+}
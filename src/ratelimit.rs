@@ -0,0 +1,95 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Rolls a user may make in a row, and how many more they may make every minute, unless the
+// DENEDE_RATE_BURST and DENEDE_RATE_PER_MINUTE environment variables say otherwise:
+const DEFAULT_BURST: u32 = 5;
+const DEFAULT_PER_MINUTE: u32 = 12;
+// How long to wait before telling a user again that they are rolling too fast:
+const NOTICE_COOLDOWN: Duration = Duration::from_secs(60);
+// Users tracked before forgetting those who have not rolled in a while:
+const PRUNE_AT: usize = 10_000;
+
+/// Whether a user may roll.
+#[derive(Debug, PartialEq)]
+pub enum Limit {
+    Allowed,
+    /// Rolling too fast; `notify` tells whether they should be told so, which is only once in a while.
+    Limited { notify: bool },
+}
+
+// Tokens left to a user, and when they were last counted:
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    notified: Option<Instant>,
+}
+
+/// Token bucket for each user, so nobody can make the bot roll (and ask RANDOM.ORG) endlessly.
+pub struct RateLimiter {
+    burst: f64,
+    per_second: f64,
+    buckets: Mutex<HashMap<u64, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Limiter letting each user make up to `burst` rolls in a row, and `per_minute` more every
+    /// minute. A burst of 0 lets everyone roll as much as they like.
+    pub fn new(burst: u32, per_minute: u32) -> RateLimiter {
+        RateLimiter { burst: burst as f64, per_second: per_minute as f64 / 60.0, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    /// Limiter configured through the environment.
+    pub fn from_env() -> RateLimiter {
+        let read = |name: &str, default: u32| env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default);
+        RateLimiter::new(read("DENEDE_RATE_BURST", DEFAULT_BURST), read("DENEDE_RATE_PER_MINUTE", DEFAULT_PER_MINUTE))
+    }
+
+    /// Take a roll from the user's bucket (e.g.: keyed by their id), if there is any left.
+    pub fn check(&self, key: u64, now: Instant) -> Limit {
+        if self.burst == 0.0 {
+            return Limit::Allowed;
+        }
+
+        let mut buckets = self.buckets.lock().expect("No buckets?");
+        if buckets.len() >= PRUNE_AT {
+            // Full buckets are no different from new ones:
+            let full_after = Duration::from_secs_f64(self.burst / self.per_second.max(f64::MIN_POSITIVE));
+            buckets.retain(|_, bucket| now.saturating_duration_since(bucket.updated) < full_after);
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket { tokens: self.burst, updated: now, notified: None });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Limit::Allowed;
+        }
+        let notify = bucket.notified.is_none_or(|notified| now.saturating_duration_since(notified) >= NOTICE_COOLDOWN);
+        if notify {
+            bucket.notified = Some(now);
+        }
+        Limit::Limited { notify }
+    }
+}
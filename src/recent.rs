@@ -0,0 +1,54 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::collections::{HashMap, VecDeque};
+
+use serenity::model::prelude::*;
+
+// Distinct expressions remembered for each user, the oldest being forgotten first:
+const MAX_RECENT: usize = 10;
+// Longest expression remembered, as Discord allows no longer suggestions:
+const MAX_SUGGESTION: usize = 100;
+
+/// Latest distinct expressions rolled by each user, to suggest them back to them.
+#[derive(Default)]
+pub struct Recent {
+    expressions: HashMap<UserId, VecDeque<String>>,
+}
+
+impl Recent {
+    /// Remember an expression the user just rolled, e.g.: "1d20+5" or "Attack: 1d20+5; Damage: 2d6+3".
+    pub fn push(&mut self, user_id: UserId, expression: &str) {
+        let expression = expression.trim();
+        if expression.is_empty() || expression.chars().count() > MAX_SUGGESTION {
+            return;
+        }
+        let recent = self.expressions.entry(user_id).or_default();
+        recent.retain(|rolled| rolled != expression);
+        recent.push_front(expression.to_string());
+        recent.truncate(MAX_RECENT);
+    }
+
+    /// The user's latest expressions starting with what they have typed so far, latest first.
+    pub fn suggest(&self, user_id: UserId, typed: &str) -> Vec<String> {
+        let typed = typed.trim().to_lowercase();
+        self.expressions.get(&user_id).into_iter().flatten()
+            .filter(|expression| expression.to_lowercase().starts_with(&typed))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,55 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::collections::{HashMap, VecDeque};
+
+use serenity::model::prelude::*;
+
+// Messages whose responses are remembered, the oldest being forgotten first:
+const MAX_TRACKED: usize = 1000;
+
+/// Responses to the latest messages with rolls, so they can be deleted along with those messages.
+#[derive(Default)]
+pub struct Replies {
+    responses: HashMap<MessageId, Vec<MessageId>>,
+    order: VecDeque<MessageId>,
+}
+
+impl Replies {
+    /// Remember the messages (e.g.: every chunk) sent in response to the given one.
+    pub fn insert(&mut self, message_id: MessageId, responses: Vec<MessageId>) {
+        if responses.is_empty() {
+            return;
+        }
+        if self.order.len() == MAX_TRACKED {
+            if let Some(oldest) = self.order.pop_front() {
+                self.responses.remove(&oldest);
+            }
+        }
+        self.order.push_back(message_id);
+        self.responses.insert(message_id, responses);
+    }
+
+    /// Forget the responses to the given message, returning them; none if it is too old or had none.
+    pub fn remove(&mut self, message_id: MessageId) -> Vec<MessageId> {
+        let responses = self.responses.remove(&message_id).unwrap_or_default();
+        if !responses.is_empty() {
+            self.order.retain(|id| *id != message_id);
+        }
+        responses
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+use serenity::http::HttpError;
+use serenity::Error;
+
+/// Times something is tried to be sent to Discord before giving up on it.
+pub const ATTEMPTS: u32 = 3;
+// Wait before trying again for the first time, doubled every time after that:
+const BACKOFF: Duration = Duration::from_millis(500);
+
+/// Discord's error code for a response to an interaction that was already responded to (or
+/// deferred), which can then only be edited.
+pub const ALREADY_ACKNOWLEDGED: isize = 40060;
+
+/// Whether a request that failed is worth making again.
+#[derive(Debug, PartialEq)]
+pub enum Failure {
+    /// E.g.: rate limits, Discord failing (5xx), or the network.
+    Retryable,
+    /// E.g.: missing permissions, a message too long, or an unknown channel.
+    Permanent,
+}
+
+/// Whether a request Discord refused with the given HTTP status is worth making again.
+pub fn classify_status(status: u16) -> Failure {
+    match status {
+        429 | 500..=599 => Failure::Retryable,
+        _ => Failure::Permanent,
+    }
+}
+
+/// Whether a failed request to Discord is worth making again.
+pub fn classify(error: &Error) -> Failure {
+    match error {
+        Error::Http(HttpError::UnsuccessfulRequest(response)) => classify_status(response.status_code.as_u16()),
+        // The request never got an answer, e.g.: it timed out:
+        Error::Http(HttpError::Request(why)) if !why.is_builder() => Failure::Retryable,
+        Error::Io(_) => Failure::Retryable,
+        _ => Failure::Permanent,
+    }
+}
+
+/// Discord's JSON error code of a refused request, if it was refused.
+pub fn code(error: &Error) -> Option<isize> {
+    match error {
+        Error::Http(HttpError::UnsuccessfulRequest(response)) => Some(response.error.code),
+        _ => None,
+    }
+}
+
+/// How long to wait before the given attempt (the second being 1): twice as long as before the
+/// previous one, plus up to half as much again at random, so retries from many places spread out.
+pub fn backoff(retry: u32) -> Duration {
+    let wait = BACKOFF * 2u32.saturating_pow(retry.saturating_sub(1));
+    wait.mul_f64(1.0 + rand::thread_rng().gen_range(0.0..0.5))
+}
+
+/// Send something to Discord with `send`, trying again up to ATTEMPTS times in all while it fails
+/// in ways that may not last. Returns the last failure otherwise.
+pub async fn with_retries<T, F: Future<Output = Result<T, Error>>>(mut send: impl FnMut() -> F) -> Result<T, Error> {
+    let mut retries = 0;
+    loop {
+        match send().await {
+            Err(why) if retries + 1 < ATTEMPTS && classify(&why) == Failure::Retryable => {
+                retries += 1;
+                tokio::time::sleep(backoff(retries)).await;
+            },
+            result => return result,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Sealing of what must be kept but never read by whoever gets hold of the database, e.g.: the
+// webhooks rolls are delivered through, whose URLs let anyone post as them. Things are sealed with
+// ChaCha20-Poly1305, keyed by the DENEDE_WEBHOOK_SECRET environment variable.
+use std::env;
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+
+// Key things are sealed with, if the DENEDE_WEBHOOK_SECRET environment variable sets a secret:
+fn key() -> Option<LessSafeKey> {
+    let secret = env::var("DENEDE_WEBHOOK_SECRET").ok().filter(|secret| !secret.is_empty())?;
+    // Secrets of any length make keys of the right one:
+    let key = UnboundKey::new(&CHACHA20_POLY1305, digest(&SHA256, secret.as_bytes()).as_ref()).ok()?;
+    Some(LessSafeKey::new(key))
+}
+
+/// Whether things can be sealed.
+pub fn enabled() -> bool {
+    key().is_some()
+}
+
+/// The text, sealed along with the nonce it was sealed with.
+///
+/// None if there is no secret to seal it with.
+pub fn seal(text: &str) -> Option<Vec<u8>> {
+    let key = key()?;
+    let mut nonce = [0; NONCE_LEN];
+    SystemRandom::new().fill(&mut nonce).ok()?;
+    let mut sealed = text.as_bytes().to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed).ok()?;
+    Some([&nonce[..], &sealed].concat())
+}
+
+/// The text that was sealed.
+///
+/// None if there is no secret to open it with, or it was sealed with another one (or tampered with).
+pub fn open(sealed: &[u8]) -> Option<String> {
+    let key = key()?;
+    if sealed.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, sealed) = sealed.split_at(NONCE_LEN);
+    let mut text = sealed.to_vec();
+    let text = key.open_in_place(Nonce::try_assume_unique_for_key(nonce).ok()?, Aad::empty(), &mut text).ok()?;
+    String::from_utf8(text.to_vec()).ok()
+}
@@ -0,0 +1,92 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Small HTTP servers for those running the bot: one serving its metrics at /metrics for Prometheus,
+// and whether it is alive (/live) and connected to Discord (/health) for orchestrators, e.g.:
+// Kubernetes; it only starts if DENEDE_METRICS_PORT (or the configuration file) gives it a port.
+// Others (e.g.: the roll API) serve requests of their own the same way.
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use denede_discord_bot::metrics;
+
+use crate::health;
+
+/// Server running in the background, until it is stopped.
+pub struct Server {
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl Server {
+    /// Serve the metrics and health checks on the given port.
+    pub fn start(port: u16) -> Result<Server, String> {
+        Server::serve(port, "metrics", handle)
+    }
+
+    /// Answer requests on the given port with `handle`, listening on every interface so it can be
+    /// reached from outside a container. `what` names what is served, for the logs.
+    pub fn serve<F, R>(port: u16, what: &'static str, handle: F) -> Result<Server, String>
+    where
+        F: Fn(Request<Body>) -> R + Copy + Send + Sync + 'static,
+        R: Future<Output = Result<Response<Body>, Infallible>> + Send + 'static,
+    {
+        let address = SocketAddr::from(([0, 0, 0, 0], port));
+        let builder = hyper::Server::try_bind(&address).map_err(|why| format!("Could not listen on {address}: {why}"))?;
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let server = builder
+            .serve(make_service_fn(move |_| async move { Ok::<_, Infallible>(service_fn(handle)) }))
+            .with_graceful_shutdown(async {
+                stopped.await.ok();
+            });
+        info!("Serving {what} on http://{address}");
+        let task = tokio::spawn(async move {
+            if let Err(why) = server.await {
+                error!("The {what} server failed: {why}");
+            }
+        });
+        Ok(Server { shutdown, task })
+    }
+
+    /// Stop taking requests, and wait for those being answered.
+    pub async fn stop(self) {
+        self.shutdown.send(()).ok();
+        self.task.await.ok();
+    }
+}
+
+async fn handle(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => Response::builder()
+            .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+            .body(Body::from(metrics::get().gather())),
+        // Answered for as long as the process runs:
+        (&Method::GET, "/live") => Response::builder().body(Body::from("alive")),
+        (&Method::GET, "/health") if health::healthy() => Response::builder().body(Body::from("connected")),
+        (&Method::GET, "/health") => Response::builder().status(StatusCode::SERVICE_UNAVAILABLE).body(Body::from("not connected")),
+        _ => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()),
+    };
+    Ok(response.expect("No response?"))
+}
@@ -0,0 +1,472 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+// Changes to the schema, in order. The database's user_version tells how many have been made, so
+// each is made only once; new ones are added at the end, and old ones are never changed:
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE guild_settings (
+        guild_id INTEGER PRIMARY KEY,
+        open TEXT,
+        close TEXT,
+        strict INTEGER NOT NULL DEFAULT 0,
+        hidden_rolls INTEGER NOT NULL DEFAULT 0
+    );",
+    "CREATE TABLE channel_webhooks (
+        channel_id INTEGER PRIMARY KEY,
+        guild_id INTEGER NOT NULL,
+        sealed BLOB NOT NULL
+    );",
+    "ALTER TABLE guild_settings ADD COLUMN roll20 INTEGER NOT NULL DEFAULT 0;",
+    "CREATE TABLE user_macros (
+        user_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        expression TEXT NOT NULL,
+        PRIMARY KEY (user_id, name)
+    );",
+    "CREATE TABLE characters (
+        user_id INTEGER NOT NULL,
+        guild_id INTEGER NOT NULL,
+        sheet TEXT NOT NULL,
+        PRIMARY KEY (user_id, guild_id)
+    );",
+    "ALTER TABLE guild_settings ADD COLUMN embeds INTEGER NOT NULL DEFAULT 0;",
+    "CREATE TABLE disabled_channels (
+        channel_id INTEGER PRIMARY KEY,
+        guild_id INTEGER NOT NULL
+    );",
+    "CREATE TABLE user_preferences (
+        user_id INTEGER PRIMARY KEY,
+        compact INTEGER,
+        crits INTEGER,
+        pips INTEGER,
+        pseudo_random INTEGER
+    );",
+    "CREATE TABLE guild_tables (
+        guild_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        weight INTEGER NOT NULL,
+        entry TEXT NOT NULL,
+        PRIMARY KEY (guild_id, name, position)
+    );",
+    "CREATE TABLE user_stats (
+        user_id INTEGER NOT NULL,
+        guild_id INTEGER NOT NULL,
+        rolls INTEGER NOT NULL,
+        d20s INTEGER NOT NULL,
+        natural_20s INTEGER NOT NULL,
+        natural_1s INTEGER NOT NULL,
+        d20_sum INTEGER NOT NULL,
+        luckiest TEXT,
+        unluckiest TEXT,
+        PRIMARY KEY (user_id, guild_id)
+    );",
+];
+
+// Server id standing for rolls made outside of any server, as no server's id is 0:
+const OUTSIDE_GUILDS: u64 = 0;
+
+/// How a user wants their rolls shown. Whatever is None is shown as for everyone else.
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct Preferences {
+    pub compact: Option<bool>,
+    pub crits: Option<bool>,
+    pub pips: Option<bool>,
+    pub pseudo_random: Option<bool>,
+}
+
+/// A roll worth remembering, for how lucky or unlucky it was.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Notable {
+    /// From 0 (every die at its lowest) to 1 (every die at its highest).
+    pub luck: f64,
+    pub expression: String,
+    pub total: Option<i64>,
+    /// Where the roll can be found, e.g.: a link to the message it was shown in.
+    pub place: String,
+}
+
+/// Counters of the rolls of a user in a server (or outside of any).
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct RollStats {
+    pub rolls: u64,
+    pub d20s: u64,
+    pub natural_20s: u64,
+    pub natural_1s: u64,
+    pub d20_sum: i64,
+    pub luckiest: Option<Notable>,
+    pub unluckiest: Option<Notable>,
+}
+
+impl RollStats {
+    /// Count the rolls of other statistics too.
+    pub fn add(&mut self, other: &RollStats) {
+        self.rolls += other.rolls;
+        self.d20s += other.d20s;
+        self.natural_20s += other.natural_20s;
+        self.natural_1s += other.natural_1s;
+        self.d20_sum += other.d20_sum;
+        for notable in other.luckiest.iter().chain(&other.unluckiest) {
+            self.notice(notable);
+        }
+    }
+
+    /// Keep the roll if it is the luckiest or unluckiest yet.
+    pub fn notice(&mut self, notable: &Notable) {
+        if self.luckiest.as_ref().is_none_or(|luckiest| notable.luck > luckiest.luck) {
+            self.luckiest = Some(notable.clone());
+        }
+        if self.unluckiest.as_ref().is_none_or(|unluckiest| notable.luck < unluckiest.luck) {
+            self.unluckiest = Some(notable.clone());
+        }
+    }
+}
+
+/// Persistent state of the bot, kept in an SQLite database.
+pub struct Store {
+    connection: Mutex<Connection>,
+}
+
+impl Store {
+    /// Open the database at the path, creating it if needed, and bring its schema up to date.
+    pub fn open(path: &Path) -> Result<Store> {
+        Store::migrated(Connection::open(path)?)
+    }
+
+    /// Open a database that lives only as long as the store, e.g.: for tests.
+    pub fn in_memory() -> Result<Store> {
+        Store::migrated(Connection::open_in_memory()?)
+    }
+
+    fn migrated(mut connection: Connection) -> Result<Store> {
+        let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let transaction = connection.transaction()?;
+        for (applied, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            transaction.execute_batch(migration)?;
+            transaction.pragma_update(None, "user_version", applied + 1)?;
+        }
+        transaction.commit()?;
+        Ok(Store { connection: Mutex::new(connection) })
+    }
+
+    /// Changes made to the schema so far.
+    pub fn version(&self) -> Result<usize> {
+        self.connection().query_row("PRAGMA user_version", [], |row| row.get(0))
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.connection.lock().expect("No connection?")
+    }
+
+    /// What opens and closes rolls in the server's messages, if not the default.
+    pub fn delimiters(&self, guild_id: u64) -> Result<Option<(String, String)>> {
+        let saved = self.connection().query_row(
+            "SELECT open, close FROM guild_settings WHERE guild_id = ?1 AND open IS NOT NULL",
+            params![guild_id as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?;
+        Ok(saved)
+    }
+
+    /// Keep what opens and closes rolls in the server's messages, or go back to the default (None).
+    pub fn set_delimiters(&self, guild_id: u64, delimiters: Option<(&str, &str)>) -> Result<()> {
+        let (open, close) = delimiters.unzip();
+        self.connection().execute(
+            "INSERT INTO guild_settings (guild_id, open, close) VALUES (?1, ?2, ?3)
+             ON CONFLICT (guild_id) DO UPDATE SET open = excluded.open, close = excluded.close",
+            params![guild_id as i64, open, close],
+        )?;
+        Ok(())
+    }
+
+    /// Whether text within the server's delimiters that is no roll is pointed out.
+    pub fn strict(&self, guild_id: u64) -> Result<bool> {
+        self.flag(guild_id, "strict")
+    }
+
+    pub fn set_strict(&self, guild_id: u64, strict: bool) -> Result<()> {
+        self.set_flag(guild_id, "strict", strict)
+    }
+
+    /// Whether /roll hides its response from other users in the server, unless asked otherwise.
+    pub fn hidden_rolls(&self, guild_id: u64) -> Result<bool> {
+        self.flag(guild_id, "hidden_rolls")
+    }
+
+    pub fn set_hidden_rolls(&self, guild_id: u64, hidden: bool) -> Result<()> {
+        self.set_flag(guild_id, "hidden_rolls", hidden)
+    }
+
+    /// Whether rolls in the server are written in Roll20's notation, rather than in MapTool's.
+    pub fn roll20(&self, guild_id: u64) -> Result<bool> {
+        self.flag(guild_id, "roll20")
+    }
+
+    pub fn set_roll20(&self, guild_id: u64, roll20: bool) -> Result<()> {
+        self.set_flag(guild_id, "roll20", roll20)
+    }
+
+    /// Whether rolls in the server are shown as embeds, unless asked otherwise.
+    pub fn embeds(&self, guild_id: u64) -> Result<bool> {
+        self.flag(guild_id, "embeds")
+    }
+
+    pub fn set_embeds(&self, guild_id: u64, embeds: bool) -> Result<()> {
+        self.set_flag(guild_id, "embeds", embeds)
+    }
+
+    /// Whether rolls in the messages of the channel are ignored.
+    pub fn channel_disabled(&self, channel_id: u64) -> Result<bool> {
+        let disabled = self.connection().query_row(
+            "SELECT 1 FROM disabled_channels WHERE channel_id = ?1",
+            params![channel_id as i64],
+            |_| Ok(()),
+        ).optional()?;
+        Ok(disabled.is_some())
+    }
+
+    /// Channels of the server whose messages are not looked through for rolls.
+    pub fn disabled_channels(&self, guild_id: u64) -> Result<Vec<u64>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT channel_id FROM disabled_channels WHERE guild_id = ?1 ORDER BY channel_id")?;
+        let channels = statement.query_map(params![guild_id as i64], |row| Ok(row.get::<_, i64>(0)? as u64))?.collect();
+        channels
+    }
+
+    /// Ignore the rolls in the messages of the channel of the server, or look for them again.
+    pub fn set_channel_disabled(&self, guild_id: u64, channel_id: u64, disabled: bool) -> Result<()> {
+        if disabled {
+            self.connection().execute(
+                "INSERT INTO disabled_channels (channel_id, guild_id) VALUES (?1, ?2)
+                 ON CONFLICT (channel_id) DO UPDATE SET guild_id = excluded.guild_id",
+                params![channel_id as i64, guild_id as i64],
+            )?;
+        } else {
+            self.connection().execute("DELETE FROM disabled_channels WHERE channel_id = ?1", params![channel_id as i64])?;
+        }
+        Ok(())
+    }
+
+    /// Sealed URL of the webhook rolls in the channel's messages are delivered through, if any.
+    pub fn webhook(&self, channel_id: u64) -> Result<Option<Vec<u8>>> {
+        self.connection().query_row(
+            "SELECT sealed FROM channel_webhooks WHERE channel_id = ?1",
+            params![channel_id as i64],
+            |row| row.get(0),
+        ).optional()
+    }
+
+    /// Keep the sealed URL of the webhook rolls in the channel of the server are delivered through,
+    /// or go back to responding as the bot (None).
+    pub fn set_webhook(&self, guild_id: u64, channel_id: u64, sealed: Option<&[u8]>) -> Result<()> {
+        match sealed {
+            Some(sealed) => self.connection().execute(
+                "INSERT INTO channel_webhooks (channel_id, guild_id, sealed) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (channel_id) DO UPDATE SET guild_id = excluded.guild_id, sealed = excluded.sealed",
+                params![channel_id as i64, guild_id as i64, sealed],
+            )?,
+            None => self.connection().execute("DELETE FROM channel_webhooks WHERE channel_id = ?1", params![channel_id as i64])?,
+        };
+        Ok(())
+    }
+
+    /// Named expressions of the user, by name, e.g.: ("atk", "1d20+5").
+    pub fn macros(&self, user_id: u64) -> Result<Vec<(String, String)>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT name, expression FROM user_macros WHERE user_id = ?1 ORDER BY name")?;
+        let macros = statement.query_map(params![user_id as i64], |row| Ok((row.get(0)?, row.get(1)?)))?.collect();
+        macros
+    }
+
+    /// Keep the named expressions of the user, replacing those of the same names, all or none of them.
+    pub fn set_macros(&self, user_id: u64, macros: &[(String, String)]) -> Result<()> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        for (name, expression) in macros {
+            transaction.execute(
+                "INSERT INTO user_macros (user_id, name, expression) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (user_id, name) DO UPDATE SET expression = excluded.expression",
+                params![user_id as i64, name, expression],
+            )?;
+        }
+        transaction.commit()
+    }
+
+    /// Sheet of the user's character in the server, as JSON, if they have one.
+    pub fn character(&self, user_id: u64, guild_id: u64) -> Result<Option<String>> {
+        self.connection().query_row(
+            "SELECT sheet FROM characters WHERE user_id = ?1 AND guild_id = ?2",
+            params![user_id as i64, guild_id as i64],
+            |row| row.get(0),
+        ).optional()
+    }
+
+    /// Keep the sheet of the user's character in the server, or forget it (None).
+    pub fn set_character(&self, user_id: u64, guild_id: u64, sheet: Option<&str>) -> Result<()> {
+        match sheet {
+            Some(sheet) => self.connection().execute(
+                "INSERT INTO characters (user_id, guild_id, sheet) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (user_id, guild_id) DO UPDATE SET sheet = excluded.sheet",
+                params![user_id as i64, guild_id as i64, sheet],
+            )?,
+            None => self.connection().execute(
+                "DELETE FROM characters WHERE user_id = ?1 AND guild_id = ?2",
+                params![user_id as i64, guild_id as i64],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// How the user wants their rolls shown, as far as they have chosen.
+    pub fn preferences(&self, user_id: u64) -> Result<Preferences> {
+        let saved = self.connection().query_row(
+            "SELECT compact, crits, pips, pseudo_random FROM user_preferences WHERE user_id = ?1",
+            params![user_id as i64],
+            |row| Ok(Preferences { compact: row.get(0)?, crits: row.get(1)?, pips: row.get(2)?, pseudo_random: row.get(3)? }),
+        ).optional()?;
+        Ok(saved.unwrap_or_default())
+    }
+
+    /// Keep how the user wants their rolls shown, or forget it (None).
+    pub fn set_preferences(&self, user_id: u64, preferences: Option<&Preferences>) -> Result<()> {
+        match preferences {
+            Some(Preferences { compact, crits, pips, pseudo_random }) => self.connection().execute(
+                "INSERT INTO user_preferences (user_id, compact, crits, pips, pseudo_random) VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (user_id) DO UPDATE SET compact = excluded.compact, crits = excluded.crits, pips = excluded.pips,
+                                                     pseudo_random = excluded.pseudo_random",
+                params![user_id as i64, compact, crits, pips, pseudo_random],
+            )?,
+            None => self.connection().execute("DELETE FROM user_preferences WHERE user_id = ?1", params![user_id as i64])?,
+        };
+        Ok(())
+    }
+
+    /// Weighted entries of the server's table of the given name, in order, if it has one.
+    pub fn table(&self, guild_id: u64, name: &str) -> Result<Option<Vec<(i64, String)>>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT weight, entry FROM guild_tables WHERE guild_id = ?1 AND name = ?2 ORDER BY position")?;
+        let entries = statement.query_map(params![guild_id as i64, name], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<Result<Vec<_>>>()?;
+        Ok(Some(entries).filter(|entries| !entries.is_empty()))
+    }
+
+    /// Names of the server's tables, in order.
+    pub fn tables(&self, guild_id: u64) -> Result<Vec<String>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare("SELECT DISTINCT name FROM guild_tables WHERE guild_id = ?1 ORDER BY name")?;
+        let names = statement.query_map(params![guild_id as i64], |row| row.get(0))?.collect();
+        names
+    }
+
+    /// Keep the weighted entries of the server's table of the given name, replacing it whole, or
+    /// forget it (None).
+    pub fn set_table(&self, guild_id: u64, name: &str, entries: Option<&[(i64, String)]>) -> Result<()> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        transaction.execute("DELETE FROM guild_tables WHERE guild_id = ?1 AND name = ?2", params![guild_id as i64, name])?;
+        for (position, (weight, entry)) in entries.unwrap_or_default().iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO guild_tables (guild_id, name, position, weight, entry) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![guild_id as i64, name, position as i64, weight, entry],
+            )?;
+        }
+        transaction.commit()
+    }
+
+    /// Statistics of the user's rolls, by the server they were made in (None outside of any).
+    pub fn roll_stats(&self, user_id: u64) -> Result<Vec<(Option<u64>, RollStats)>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT guild_id, rolls, d20s, natural_20s, natural_1s, d20_sum, luckiest, unluckiest FROM user_stats WHERE user_id = ?1 ORDER BY guild_id",
+        )?;
+        let stats = statement.query_map(params![user_id as i64], |row| {
+            let guild_id = Some(row.get::<_, i64>(0)? as u64).filter(|guild_id| *guild_id != OUTSIDE_GUILDS);
+            Ok((guild_id, RollStats {
+                rolls: row.get::<_, i64>(1)? as u64,
+                d20s: row.get::<_, i64>(2)? as u64,
+                natural_20s: row.get::<_, i64>(3)? as u64,
+                natural_1s: row.get::<_, i64>(4)? as u64,
+                d20_sum: row.get(5)?,
+                luckiest: notable(row.get(6)?)?,
+                unluckiest: notable(row.get(7)?)?,
+            }))
+        })?.collect();
+        stats
+    }
+
+    /// Count the statistics of new rolls of the user in the server (None outside of any) towards those
+    /// kept, all or none of them.
+    pub fn add_roll_stats(&self, user_id: u64, guild_id: Option<u64>, rolled: &RollStats) -> Result<()> {
+        let guild_id = guild_id.unwrap_or(OUTSIDE_GUILDS);
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+
+        // The counters are added up by the database, and the notable rolls compared here:
+        let kept = transaction.query_row(
+            "SELECT luckiest, unluckiest FROM user_stats WHERE user_id = ?1 AND guild_id = ?2",
+            params![user_id as i64, guild_id as i64],
+            |row| Ok(RollStats { luckiest: notable(row.get(0)?)?, unluckiest: notable(row.get(1)?)?, ..RollStats::default() }),
+        ).optional()?;
+        let mut notables = kept.unwrap_or_default();
+        notables.add(rolled);
+
+        let json = |notable: &Option<Notable>| notable.as_ref().map(serde_json::to_string).transpose()
+            .map_err(|why| rusqlite::Error::ToSqlConversionFailure(Box::new(why)));
+        transaction.execute(
+            "INSERT INTO user_stats (user_id, guild_id, rolls, d20s, natural_20s, natural_1s, d20_sum, luckiest, unluckiest)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT (user_id, guild_id) DO UPDATE SET rolls = rolls + excluded.rolls, d20s = d20s + excluded.d20s,
+                natural_20s = natural_20s + excluded.natural_20s, natural_1s = natural_1s + excluded.natural_1s,
+                d20_sum = d20_sum + excluded.d20_sum, luckiest = excluded.luckiest, unluckiest = excluded.unluckiest",
+            params![
+                user_id as i64, guild_id as i64, rolled.rolls as i64, rolled.d20s as i64, rolled.natural_20s as i64,
+                rolled.natural_1s as i64, rolled.d20_sum, json(&notables.luckiest)?, json(&notables.unluckiest)?,
+            ],
+        )?;
+        transaction.commit()
+    }
+
+    // Column names are never taken from users, only from the accessors above:
+    fn flag(&self, guild_id: u64, column: &str) -> Result<bool> {
+        let flag = self.connection().query_row(
+            &format!("SELECT {column} FROM guild_settings WHERE guild_id = ?1"),
+            params![guild_id as i64],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(flag.unwrap_or(false))
+    }
+
+    fn set_flag(&self, guild_id: u64, column: &str, value: bool) -> Result<()> {
+        self.connection().execute(
+            &format!("INSERT INTO guild_settings (guild_id, {column}) VALUES (?1, ?2)
+                      ON CONFLICT (guild_id) DO UPDATE SET {column} = excluded.{column}"),
+            params![guild_id as i64, value],
+        )?;
+        Ok(())
+    }
+}
+
+// A notable roll kept as JSON, if any:
+fn notable(json: Option<String>) -> Result<Option<Notable>> {
+    json.map(|json| serde_json::from_str(&json)).transpose()
+        .map_err(|why| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(why)))
+}
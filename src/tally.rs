@@ -0,0 +1,80 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serenity::model::id::GuildId;
+
+use denede_discord_bot::dice::RollResult;
+use denede_discord_bot::metrics;
+
+/// What the bot has been up to since it started, as told by /uptime.
+#[derive(Default)]
+pub struct Tally {
+    // When the bot first connected to Discord:
+    connected: OnceLock<Instant>,
+    guilds: Mutex<HashSet<GuildId>>,
+    rolls: AtomicU64,
+    // Rolls that had to fall back to pseudo-random numbers:
+    pseudo_random: AtomicU64,
+}
+
+impl Tally {
+    /// Note that the bot is connected, and to which servers, unless it already was.
+    pub fn connect(&self, guilds: impl IntoIterator<Item=GuildId>) {
+        self.connected.get_or_init(Instant::now);
+        self.guilds.lock().expect("No guilds?").extend(guilds);
+    }
+
+    /// Note that the bot is in a server, returning whether it was not known to be.
+    pub fn join(&self, guild_id: GuildId) -> bool {
+        self.guilds.lock().expect("No guilds?").insert(guild_id)
+    }
+
+    pub fn leave(&self, guild_id: GuildId) {
+        self.guilds.lock().expect("No guilds?").remove(&guild_id);
+    }
+
+    /// Count a roll that was served, made from a seed or not.
+    pub fn count(&self, result: &RollResult) {
+        self.rolls.fetch_add(1, Ordering::Relaxed);
+        metrics::get().rolls.inc();
+        if !result.is_truly_random() && !matches!(result, RollResult::Seeded { .. }) {
+            self.pseudo_random.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// How long the bot has been connected, if it has been at all.
+    pub fn uptime(&self) -> Option<Duration> {
+        self.connected.get().map(Instant::elapsed)
+    }
+
+    pub fn guilds(&self) -> usize {
+        self.guilds.lock().expect("No guilds?").len()
+    }
+
+    pub fn rolls(&self) -> u64 {
+        self.rolls.load(Ordering::Relaxed)
+    }
+
+    pub fn pseudo_random(&self) -> u64 {
+        self.pseudo_random.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ring::{constant_time, hmac};
+
+use crate::dice::{styled, RollResult, Style};
+
+// Hexadecimal digits of the HMAC kept in a verification code:
+const CODE_DIGITS: usize = 8;
+
+// Secret the verification codes are made with, if the DENEDE_VERIFY_SECRET environment variable sets one:
+fn key() -> Option<hmac::Key> {
+    let secret = env::var("DENEDE_VERIFY_SECRET").ok().filter(|secret| !secret.is_empty())?;
+    Some(hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()))
+}
+
+/// Whether rolls are given verification codes.
+pub fn enabled() -> bool {
+    key().is_some()
+}
+
+/// Verification code of a roll with the given result, made right now, e.g.: "6710a3f2-9c1e77ab".
+///
+/// None if codes are disabled, or the roll has no numeric total.
+pub fn sign(expression: &str, result: &RollResult) -> Option<String> {
+    let key = key()?;
+    // Seeded rolls are not random, so there is nothing to vouch for:
+    if let RollResult::Seeded { .. } = result {
+        return None;
+    }
+    result.total()?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).expect("No time?").as_secs();
+    let results = styled(Style::plain(), || result.to_string());
+    Some(format!("{:x}-{}", timestamp, mac(&key, expression, &results, timestamp)))
+}
+
+/// Result of a roll as shown in chat (with its total in bold), followed by its verification code if
+/// rolls are given one.
+pub fn with_code(expression: &str, result: &RollResult) -> String {
+    with_note(expression, result, "")
+}
+
+/// Same as [`with_code`], with a note right after the result, e.g.: whether it met a difficulty class.
+pub fn with_note(expression: &str, result: &RollResult, note: &str) -> String {
+    let shown = if note.is_empty() { format!("{:#}", result) } else { format!("{:#} {}", result, note) };
+    match sign(expression, result) {
+        Some(code) => format!("{} · verify: `{}`", shown, code),
+        None => shown,
+    }
+}
+
+/// Whether the code was given to a roll of the expression with the given results, as shown with it
+/// (e.g.: "⚃, ⚄ + 3 = **12**"), every die and the total alike.
+///
+/// None if codes are disabled, or the code is not one.
+pub fn check(code: &str, expression: &str, results: &str) -> Option<bool> {
+    let key = key()?;
+    let (timestamp, claimed) = code.trim().split_once('-')?;
+    let timestamp = u64::from_str_radix(timestamp, 16).ok()?;
+    let expected = mac(&key, expression, results, timestamp);
+    Some(constant_time::verify_slices_are_equal(expected.as_bytes(), claimed.to_lowercase().as_bytes()).is_ok())
+}
+
+// Truncated HMAC of the canonical form of a roll:
+fn mac(key: &hmac::Key, expression: &str, results: &str, timestamp: u64) -> String {
+    let tag = hmac::sign(key, canonical(expression, results, timestamp).as_bytes());
+    tag.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect::<String>()[..CODE_DIGITS].to_string()
+}
+
+// Form of a roll that is signed, the same however the expression is spaced, cased or bracketed, and
+// however its results are styled: only their numbers count, in order, d6s shown by their faces
+// included. Anything from the code on, if pasted along, is left out:
+fn canonical(expression: &str, results: &str, timestamp: u64) -> String {
+    let expression = expression.trim().trim_start_matches('[').trim_end_matches(']');
+    let expression = expression.split_whitespace().collect::<String>().to_lowercase();
+    let results = results.split("verify:").next().unwrap_or_default().chars()
+        .map(|c| match c {
+            '⚀'..='⚅' => format!(" {} ", c as u32 - '⚀' as u32 + 1),
+            digit if digit.is_ascii_digit() => digit.to_string(),
+            _ => " ".to_string(),
+        })
+        .collect::<String>();
+    format!("{}\n{}\n{}", expression, results.split_whitespace().collect::<Vec<&str>>().join(","), timestamp)
+}
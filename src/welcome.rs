@@ -0,0 +1,53 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use serenity::builder::CreateMessage;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+use tracing::warn;
+
+use denede_discord_bot::retry::with_retries;
+
+use crate::config;
+
+// Introduction posted on joining a server, since rolls in chat cannot be discovered like slash commands:
+const WELCOME: &str = "Greetings, denizens of these halls! I am Denedé, keeper of the dice 🎲\n\
+Write a roll within square brackets anywhere in thy messages, e.g.: `[1d20+5]` or `Flogg takes [2d8+2] dmg`, and I shall cast it for thee. \
+Type `/` to behold my slash commands, such as `/roll` and `/explain`.\n\
+My source code can be found here: <https://github.com/0xb01u/denede>";
+
+/// Introduce the bot to a server it has just joined, in its system channel or else in the first
+/// channel it can write in.
+pub async fn greet(ctx: &Context, guild: &Guild, bot_id: UserId) {
+    if !config::get().welcome {
+        return;
+    }
+    let Some(member) = guild.members.get(&bot_id) else {
+        return;
+    };
+    let writable = |channel: &&GuildChannel| channel.kind == ChannelType::Text && guild.user_permissions_in(channel, member).send_messages();
+    let mut channels = guild.channels.values().filter(writable).collect::<Vec<_>>();
+    channels.sort_by_key(|channel| channel.position);
+    let system = guild.system_channel_id.and_then(|channel_id| guild.channels.get(&channel_id)).filter(writable);
+    let Some(channel) = system.or(channels.first().copied()) else {
+        return;
+    };
+
+    if let Err(why) = with_retries(|| channel.send_message(&ctx.http, CreateMessage::new().content(WELCOME))).await {
+        warn!("Could not greet {} in channel {}: {why}", guild.id, channel.id);
+    }
+}
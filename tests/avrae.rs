@@ -0,0 +1,96 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Aliases exported from Avrae, and which of them are taken as macros:
+use denede_discord_bot::avrae::{import, Import, Skip};
+use denede_discord_bot::dice::{DiceError, Syntax};
+
+fn imported(export: &str) -> Import {
+    match import(export, Syntax::MapTool) {
+        Ok(import) => import,
+        Err(why) => panic!("{} should be read: {}", export, why),
+    }
+}
+
+fn pairs(import: &Import) -> Vec<(&str, &str)> {
+    import.imported.iter().map(|(name, expression)| (name.as_str(), expression.as_str())).collect()
+}
+
+fn skipped<'a>(import: &'a Import, name: &str) -> &'a Skip {
+    match import.skipped.iter().find(|(skipped, _)| skipped == name) {
+        Some((_, why)) => why,
+        None => panic!("{} should be skipped", name),
+    }
+}
+
+#[test]
+fn commands() {
+    let import = imported("!alias atk r 1d20+5\n!alias dmg roll 2d6 + 3\n\n!alias adv r 2d20kh1+7 Longsword, with advantage\n!alias d20 r d20");
+    assert_eq!(pairs(&import), [("atk", "1d20+5"), ("dmg", "2d6 + 3"), ("adv", "2d20kh1+7"), ("d20", "d20")]);
+    assert!(import.skipped.is_empty());
+}
+
+#[test]
+fn json() {
+    let aliases = imported(r#"[{"name": "atk", "commands": "r 1d20+5"}, {"name": "hi", "commands": "echo Hello!"}]"#);
+    assert_eq!(pairs(&aliases), [("atk", "1d20+5")]);
+    assert!(matches!(skipped(&aliases, "hi"), Skip::NotRoll));
+    // A single alias, too:
+    assert_eq!(pairs(&imported(r#"{"name": "fb", "commands": "r 8d6"}"#)), [("fb", "8d6")]);
+    assert!(import(r#"[{"name": "atk"}]"#, Syntax::MapTool).is_err());
+}
+
+#[test]
+fn skips() {
+    let export = "\
+!alias init <drac2>
+return f'init {roll(\"1d20\")}'
+</drac2>
+!alias atk r 1d20+%1%
+!alias sneak r {stealth}
+!alias fire r 8d6 [fire]
+!alias bad`name r 1d20
+!alias huge r 1000000d6
+!alias nope attack longsword
+!alias atk2 r 1d20+4";
+    let import = imported(export);
+    // Lines of a multiline alias are part of it, not aliases of their own:
+    assert_eq!(pairs(&import), [("fire", "8d6"), ("atk2", "1d20+4")]);
+    assert_eq!(import.skipped.len(), 6);
+    assert!(matches!(skipped(&import, "init"), Skip::Draconic));
+    assert!(matches!(skipped(&import, "atk"), Skip::Arguments));
+    assert!(matches!(skipped(&import, "sneak"), Skip::Draconic));
+    assert!(matches!(skipped(&import, "bad`name"), Skip::Name));
+    assert!(matches!(skipped(&import, "huge"), Skip::Dice(DiceError::TooManyDice(_))));
+    assert!(matches!(skipped(&import, "nope"), Skip::NotRoll));
+    assert!(skipped(&import, "init").to_string().contains("Draconic"));
+}
+
+#[test]
+fn repeated() {
+    // The last roll of a name is the one kept, where it was last aliased:
+    let import = imported("!alias atk r 1d20+5\n!alias dmg r 1d8\n!alias atk r 1d20+6");
+    assert_eq!(pairs(&import), [("dmg", "1d8"), ("atk", "1d20+6")]);
+}
+
+#[test]
+fn notation() {
+    // Rolls are read in the notation of the server:
+    let export = "!alias fb r 8d6!";
+    assert!(imported(export).imported.is_empty());
+    assert!(import(export, Syntax::Roll20).is_ok_and(|import| import.imported.len() == 1));
+}
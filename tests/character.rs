@@ -0,0 +1,90 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Character sheets: what they accept, and the values rolls take from them:
+use denede_discord_bot::character::{skill, Ability, Character, Score};
+use denede_discord_bot::dice::{parse_bound, Syntax};
+
+fn scores(scores: [&str; 6]) -> [Score; 6] {
+    scores.map(|score| Score::parse(score).expect("Bad score"))
+}
+
+fn mira() -> Character {
+    Character::new("Mira", scores(["8", "16", "+1", "12", "13", "-1"]), 2, "Stealth, sleight of hand, perception").expect("Bad character")
+}
+
+#[test]
+fn scores_and_modifiers() {
+    assert_eq!(Score::parse("16"), Ok(Score { score: Some(16), modifier: 3 }));
+    assert_eq!(Score::parse("9"), Ok(Score { score: Some(9), modifier: -1 }));
+    assert_eq!(Score::parse("1"), Ok(Score { score: Some(1), modifier: -5 }));
+    assert_eq!(Score::parse("30"), Ok(Score { score: Some(30), modifier: 10 }));
+    assert_eq!(Score::parse(" +2 "), Ok(Score { score: None, modifier: 2 }));
+    assert_eq!(Score::parse("-1"), Ok(Score { score: None, modifier: -1 }));
+    assert_eq!(Score::parse("16").unwrap().to_string(), "16 (+3)");
+    assert_eq!(Score::parse("+0").unwrap().to_string(), "+0");
+    for wrong in ["0", "31", "+11", "-6", "strong", ""] {
+        assert!(Score::parse(wrong).is_err(), "{} should be refused", wrong);
+    }
+}
+
+#[test]
+fn sheets() {
+    let mira = mira();
+    assert_eq!(mira.ability(Ability::Dexterity).modifier, 3);
+    assert_eq!(mira.ability(Ability::Constitution), Score { score: None, modifier: 1 });
+    // Skills are kept in the order of a sheet, however they were written:
+    assert_eq!(mira.skills(), ["perception", "sleight_of_hand", "stealth"]);
+    assert_eq!(mira.skill_bonus("stealth"), Some(5));
+    assert_eq!(mira.skill_bonus("Acrobatics"), Some(3));
+    assert_eq!(mira.skill_bonus("perception"), Some(3));
+    assert_eq!(mira.skill_bonus("flying"), None);
+    assert_eq!(skill("Animal Handling"), Some(("animal_handling", Ability::Wisdom)));
+
+    let refused = [
+        Character::new("", scores(["10"; 6]), 2, ""),
+        Character::new(&"a".repeat(33), scores(["10"; 6]), 2, ""),
+        Character::new("Mira", scores(["10"; 6]), 11, ""),
+        Character::new("Mira", scores(["10"; 6]), -1, ""),
+        Character::new("Mira", scores(["10"; 6]), 2, "stealth, flying"),
+    ];
+    for character in refused {
+        assert!(character.is_err());
+    }
+}
+
+#[test]
+fn variables() {
+    let variables = mira().variables();
+    assert_eq!(variables["dex"], 3);
+    assert_eq!(variables["str"], -1);
+    assert_eq!(variables["prof"], 2);
+    assert_eq!(variables["init"], 3);
+    assert_eq!(variables["stealth"], 5);
+    assert_eq!(variables["sleight_of_hand"], 5);
+    assert_eq!(variables["athletics"], -1);
+    let (bound, roll) = parse_bound("[1d20 + $dex + $prof]", Syntax::MapTool, Some(&variables));
+    assert_eq!(bound, "[1d20 + 3 + 2]");
+    assert!(matches!(roll, Some(Ok(_))));
+}
+
+#[test]
+fn kept() {
+    // Sheets are kept as JSON, and come back the same:
+    let sheet = serde_json::to_string(&mira()).unwrap();
+    assert_eq!(serde_json::from_str::<Character>(&sheet).unwrap(), mira());
+}
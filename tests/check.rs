@@ -0,0 +1,80 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Checks of characters, rolled as any other roll and broken down into their bonuses:
+mod common;
+
+use denede_discord_bot::character::{Ability, Character, Check, Score};
+use denede_discord_bot::dice::RollResult;
+
+use common::roll;
+
+fn mira() -> Character {
+    let scores = ["8", "16", "+1", "12", "13", "-1"].map(|score| Score::parse(score).expect("Bad score"));
+    Character::new("Mira", scores, 2, "Stealth, perception").expect("Bad character")
+}
+
+async fn check(check: &Check, advantage: Option<bool>, dice: &[i64]) -> RollResult {
+    roll(&check.expression(advantage), dice).await
+}
+
+#[test]
+fn bonuses() {
+    let mira = mira();
+    assert_eq!(mira.skill_check("stealth"), Some(Check { ability: Ability::Dexterity, modifier: 3, proficiency: Some(2) }));
+    assert_eq!(mira.skill_check("Sleight of Hand"), Some(Check { ability: Ability::Dexterity, modifier: 3, proficiency: None }));
+    assert_eq!(mira.skill_check("athletics"), Some(Check { ability: Ability::Strength, modifier: -1, proficiency: None }));
+    assert_eq!(mira.skill_check("flying"), None);
+    assert_eq!(mira.ability_check(Ability::Charisma), Check { ability: Ability::Charisma, modifier: -1, proficiency: None });
+}
+
+#[test]
+fn expressions() {
+    let mira = mira();
+    let stealth = mira.skill_check("stealth").unwrap();
+    assert_eq!(stealth.expression(None), "[1d20+5]");
+    assert_eq!(stealth.expression(Some(true)), "[2d20kh1+5]");
+    assert_eq!(stealth.expression(Some(false)), "[2d20kl1+5]");
+    assert_eq!(mira.ability_check(Ability::Constitution).expression(None), "[1d20+1]");
+    assert_eq!(mira.ability_check(Ability::Strength).expression(None), "[1d20-1]");
+}
+
+#[tokio::test]
+async fn breakdowns() {
+    let mira = mira();
+    let stealth = mira.skill_check("stealth").unwrap();
+    let result = check(&stealth, None, &[14]).await;
+    assert_eq!(result.total(), Some(19));
+    assert_eq!(stealth.breakdown(None, &result).as_deref(), Some("1d20 (14) + DEX (3) + prof (2) = 19"));
+
+    let result = check(&stealth, Some(true), &[4, 17]).await;
+    assert_eq!(stealth.breakdown(Some(true), &result).as_deref(), Some("2d20kh1 (~~4~~, 17) + DEX (3) + prof (2) = 22"));
+    let result = check(&stealth, Some(false), &[9, 9]).await;
+    assert_eq!(stealth.breakdown(Some(false), &result).as_deref(), Some("2d20kl1 (~~9~~, 9) + DEX (3) + prof (2) = 14"));
+
+    let strength = mira.ability_check(Ability::Strength);
+    let result = check(&strength, None, &[1]).await;
+    assert_eq!(strength.breakdown(None, &result).as_deref(), Some("1d20 (1) + STR (-1) = 0"));
+}
+
+#[tokio::test]
+async fn verdicts() {
+    let stealth = mira().skill_check("stealth").unwrap();
+    let result = check(&stealth, None, &[14]).await;
+    assert_eq!(result.against(15), Some((true, "— **Success** (beat DC 15 by 4)".to_string())));
+    assert_eq!(result.against(20).map(|(success, _)| success), Some(false));
+}
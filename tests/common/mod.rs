@@ -0,0 +1,61 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Helpers shared by the tests of rolls, to roll them with predetermined dice. Not every test uses
+// every helper:
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+
+use denede_discord_bot::dice::{Roll, RollResult, Roller};
+
+// Hands out the given numbers, in order:
+pub struct Queue(pub VecDeque<i64>);
+
+impl Roller for Queue {
+    async fn gen(&mut self, num: i64, min: i64, max: i64) -> (Vec<i64>, bool) {
+        let seq = (0..num).map(|_| self.0.pop_front().expect("Ran out of dice")).collect::<Vec<i64>>();
+        assert!(seq.iter().all(|n| (min..=max).contains(n)), "{:?} out of {}..={}", seq, min, max);
+        (seq, true)
+    }
+}
+
+/// Roll of a rollable expression, e.g.: as parsed by [`Roll::parse`].
+pub fn rollable<E>(parsed: Option<Result<Roll, E>>, expr: &str) -> Roll {
+    match parsed {
+        Some(Ok(roll)) => roll,
+        _ => panic!("{} should be rollable", expr),
+    }
+}
+
+/// Roll the expression (in MapTool's notation) with the given dice, every one of which must be used.
+pub async fn roll(expr: &str, dice: &[i64]) -> RollResult {
+    roll_parsed(&rollable(Roll::parse(expr), expr), expr, dice).await
+}
+
+/// Roll the parsed expression with the given dice, every one of which must be used.
+pub async fn roll_parsed(roll: &Roll, expr: &str, dice: &[i64]) -> RollResult {
+    let mut queue = Queue(dice.iter().copied().collect());
+    let result = roll.roll_with(&mut queue).await;
+    assert!(queue.0.is_empty(), "{} left dice unrolled", expr);
+    result
+}
+
+/// Description of the expression (in MapTool's notation).
+pub fn describe(expr: &str) -> String {
+    rollable(Roll::parse(expr), expr).describe()
+}
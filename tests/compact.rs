@@ -0,0 +1,109 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Compact and verbose rolls, and how they are split into chat messages, with room for big rolls:
+use std::env;
+use std::sync::Once;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use denede_discord_bot::dice::{chunks, split_line, Roll, RollResult, MESSAGE_LENGTH};
+
+// The limits are read once, so every test must agree on them:
+fn configure() {
+    static CONFIGURE: Once = Once::new();
+    CONFIGURE.call_once(|| {
+        env::set_var("MAX_DICE_AMOUNT", "100");
+        env::set_var("DENEDE_COMPACT_DICE", "10");
+        env::set_var("DENEDE_PSEUDO_RANDOM_NOTE", "0");
+    });
+}
+
+fn roll(expr: &str, seed: u64) -> RollResult {
+    configure();
+    match Roll::parse(expr) {
+        Some(Ok(roll)) => roll.roll_local(&mut StdRng::seed_from_u64(seed)),
+        _ => panic!("{} should be rollable", expr),
+    }
+}
+
+// Every line must make it, in order and whole, into messages Discord accepts:
+fn check_chunks(lines: &[String]) -> usize {
+    let chunks = chunks(lines);
+    assert!(chunks.iter().all(|chunk| chunk.len() <= MESSAGE_LENGTH));
+    assert_eq!(chunks.concat(), lines.iter().map(|line| format!("{}\n", line)).collect::<String>());
+    chunks.len()
+}
+
+#[test]
+fn collapsed() {
+    // Up to the configured amount of dice, they are all shown:
+    let result = roll("[10d6]", 1);
+    assert_eq!(result.to_string().matches(", ").count(), 9);
+
+    // Past it, only the result is:
+    let result = roll("[40d6]", 1);
+    assert_eq!(result.to_string(), result.total().expect("No total?").to_string());
+    let result = roll("[1d20 + 11d4]", 1);
+    assert_eq!(format!("{:#}", result), format!("**{}**", result.total().expect("No total?")));
+}
+
+#[test]
+fn chunked() {
+    let verbose = (0..100).map(|seed| format!("{:#}", roll("[10d6]", seed))).collect::<Vec<String>>();
+    assert!(check_chunks(&verbose) > 1);
+
+    let compact = (0..100).map(|seed| format!("{:#}", roll("[! 10d6]", seed))).collect::<Vec<String>>();
+    assert_eq!(check_chunks(&compact), 1);
+    let collapsed = (0..100).map(|seed| format!("{:#}", roll("[100d6]", seed))).collect::<Vec<String>>();
+    assert_eq!(collapsed, (0..100).map(|seed| format!("{:#}", roll("[! 100d6]", seed))).collect::<Vec<String>>());
+    assert_eq!(check_chunks(&collapsed), 1);
+}
+
+#[test]
+fn split() {
+    // A single result longer than a message, e.g.: [! 500d1000] shown in full:
+    let numbers = (0..600).map(|n| (n * 7919 % 1000 + 1).to_string()).collect::<Vec<String>>();
+    let line = format!("{} = **{}**", numbers.join(", "), 123_456);
+    let parts = split_line(&line, 1000);
+    assert!(parts.len() > 1);
+    assert!(parts.iter().all(|part| part.len() <= 1000));
+    // Split only between numbers, never within one:
+    assert_eq!(parts.join(" "), line);
+    assert!(parts.iter().all(|part| !part.starts_with(' ') && !part.ends_with(' ')));
+
+    // Short lines are left whole, and text without spaces is cut where it must:
+    assert_eq!(split_line("3, 5 = **8**", 1000), ["3, 5 = **8**"]);
+    assert_eq!(split_line("ééé", 3), ["é", "é", "é"]);
+}
+
+#[test]
+fn oversized() {
+    // A single result of 4500 characters arrives in three messages, split between numbers:
+    let line = (0..1500).map(|n| format!("{:02}", n % 100)).collect::<Vec<String>>().join(" ");
+    assert_eq!(line.len(), 4499);
+    let messages = chunks(&[line.clone(), "= **42**".to_string()]);
+    assert_eq!(messages.len(), 3);
+    assert!(messages.iter().all(|message| !message.is_empty() && message.len() <= MESSAGE_LENGTH));
+    assert_eq!(messages.concat().split_whitespace().collect::<Vec<&str>>(), format!("{} = **42**", line).split_whitespace().collect::<Vec<&str>>());
+
+    // Nothing to send makes no messages, not an empty one:
+    assert!(chunks(&[]).is_empty());
+    let full = ["9".repeat(MESSAGE_LENGTH - 1)];
+    assert_eq!(chunks(&full), [format!("{}\n", full[0])]);
+}
@@ -0,0 +1,47 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::Mutex;
+use std::time::Duration;
+
+use denede_discord_bot::defer::patiently;
+
+// A roll taking the given time, noting when it is done:
+async fn roll(log: &Mutex<Vec<&'static str>>, time: Duration) -> i64 {
+    tokio::time::sleep(time).await;
+    log.lock().expect("No log?").push("rolled");
+    20
+}
+
+#[tokio::test]
+async fn slow() {
+    let log = Mutex::new(Vec::new());
+    let deferred = async { log.lock().expect("No log?").push("deferred") };
+    let (total, slow) = patiently(roll(&log, Duration::from_millis(200)), Duration::from_millis(20), deferred).await;
+    assert_eq!((total, slow), (20, true));
+    // Deferred before the roll was done, not after:
+    assert_eq!(*log.lock().expect("No log?"), ["deferred", "rolled"]);
+}
+
+#[tokio::test]
+async fn fast() {
+    let log = Mutex::new(Vec::new());
+    let deferred = async { log.lock().expect("No log?").push("deferred") };
+    let (total, slow) = patiently(roll(&log, Duration::ZERO), Duration::from_secs(2), deferred).await;
+    assert_eq!((total, slow), (20, false));
+    assert_eq!(*log.lock().expect("No log?"), ["rolled"]);
+}
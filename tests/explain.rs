@@ -0,0 +1,50 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Plain-words descriptions of rolls, made without rolling them:
+use denede_discord_bot::dice::{DieKind, Roll};
+
+fn describe(expr: &str) -> String {
+    Roll::parse(expr).expect("Not a roll").ok().expect("Bad roll").describe()
+}
+
+#[test]
+fn kinds() {
+    assert_eq!(DieKind::Regular.describe(), "add them up");
+    assert_eq!(DieKind::KeepMiddle(2).describe(), "keep the middle 2 of them and add those up");
+    assert_eq!(DieKind::Drop(1).describe(), "drop the lowest 1 of them and add up the rest");
+    assert_eq!(DieKind::Match(3).describe(), "count the faces that show up at least 3 times");
+    assert_eq!(DieKind::Shadowrun.describe(), "count the hits (5 or more) of the Shadowrun pool, watching out for glitches");
+    assert_eq!(DieKind::Again(9).describe(), "count the successes (8 or more) of the World of Darkness pool, rolling again every die that shows 9 or more");
+}
+
+#[test]
+fn dice() {
+    assert_eq!(describe("[1d20]"), "roll 1 die of 20 sides");
+    assert_eq!(describe("[2d6+3]"), "roll 2 dice of 6 sides and add them up, then add 3");
+    assert_eq!(describe("[4d6km2sd]"), "roll 4 dice of 6 sides and keep the middle 2 of them and add those up, showing them from highest to lowest");
+    assert_eq!(describe("[10d6m]"), "roll 10 dice of 6 sides and count the faces that show up at least 2 times");
+    assert_eq!(describe("[7d10wod]"), "roll 7 dice of 10 sides and count the successes (8 or more) of the World of Darkness pool, rolling again every die that shows 10 or more");
+}
+
+#[test]
+fn compound() {
+    assert_eq!(describe("[1d20 + 1d4 - 1]"), "add up the following:\n+ roll 1 die of 20 sides\n+ roll 1 die of 4 sides\n− the number 1");
+    assert_eq!(describe("[max(1d20, 1d20) + 5]"), "take the highest of the following, then add 5:\n• roll 1 die of 20 sides\n• roll 1 die of 20 sides");
+    assert_eq!(describe("[1d20+7 vs 15]"), "roll 1 die of 20 sides, then add 7\nThen compare the total against a difficulty class of 15: meeting or beating it is a success.");
+    assert_eq!(describe("[avg 2d6]"), "do not roll, but tell the average total of:\nroll 2 dice of 6 sides and add them up");
+}
@@ -0,0 +1,112 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Rolls written with the aliases of Foundry VTT, and what they are taken to be:
+mod common;
+
+use denede_discord_bot::dice::{scan_with, styled, Delimiters, DiceError, Roll, RollResult, Style, Syntax};
+
+use common::{describe, roll, rollable};
+
+// Shown result of a [min ...] or [max ...] roll:
+async fn bound(expr: &str) -> String {
+    rollable(Roll::parse(expr), expr).roll().await.to_string()
+}
+
+fn shown(result: &RollResult) -> String {
+    styled(Style::plain(), || result.to_string())
+}
+
+#[test]
+fn matrix() {
+    let matrix = [
+        ("[4d6min3]", "roll 4 dice of 6 sides and add them up, counting every die below 3 as 3"),
+        ("[4d6max4+1]", "roll 4 dice of 6 sides and add them up, counting every die above 4 as 4, then add 1"),
+        ("[5d10x]", "roll 5 dice of 10 sides and add them up, rolling one more die for every die that shows 10 or more"),
+        ("[2d20kh]", "roll 2 dice of 20 sides and drop the lowest 1 of them and add up the rest"),
+        ("[2d20kl]", "roll 2 dice of 20 sides and drop the highest 1 of them and add up the rest"),
+        ("[4d6kh3]", "roll 4 dice of 6 sides and drop the lowest 1 of them and add up the rest"),
+        ("[4d6kl2]", "roll 4 dice of 6 sides and drop the highest 2 of them and add up the rest"),
+        ("[1d20r]", "roll 1 dice of 20 sides and reroll once every die that shows 1 or less, and add them up"),
+        ("[1d20r1]", "roll 1 dice of 20 sides and reroll once every die that shows 1 or less, and add them up"),
+        ("[2d6r<3]", "roll 2 dice of 6 sides and reroll once every die that shows 2 or less, and add them up"),
+        ("[4d6min2sd]", "roll 4 dice of 6 sides and add them up, counting every die below 2 as 2, showing them from highest to lowest"),
+    ];
+    for (expr, description) in matrix {
+        assert_eq!(describe(expr), description, "{}", expr);
+    }
+}
+
+#[tokio::test]
+async fn precedence() {
+    // "min" and "max" right after the dice are clamps, never a match followed by something else:
+    assert_eq!(describe("[4d6min3]"), "roll 4 dice of 6 sides and add them up, counting every die below 3 as 3");
+    assert_eq!(describe("[10d6m3]"), "roll 10 dice of 6 sides and count the faces that show up at least 3 times");
+    assert_eq!(describe("[10d6m]"), "roll 10 dice of 6 sides and count the faces that show up at least 2 times");
+    // A "<=" after the dice is a roll under, as ever, so "r<=3" rerolls ones and checks the total against 3:
+    assert_eq!(describe("[2d6r<=3]"), "roll 2 dice of 6 sides and reroll once every die that shows 1 or less, and add them up\nThen check that the total does not exceed 3.");
+    // Whereas ahead of the dice, they still ask for the theoretical bounds of the roll:
+    assert_eq!(bound("[max 4d6min3]").await, "Theoretical maximum: 24 (this is not a roll)");
+    assert_eq!(bound("[min 4d6min3]").await, "Theoretical minimum: 12 (this is not a roll)");
+    assert_eq!(bound("[min 4d6max4]").await, "Theoretical minimum: 4 (this is not a roll)");
+    // MapTool's own "km" keeps the middle dice, unlike "kh" and "kl":
+    assert!(describe("[3d20km1]").contains("middle 1"));
+}
+
+#[test]
+fn unchanged() {
+    // MapTool's notation reads just as it did:
+    assert_eq!(describe("[4d6]"), "roll 4 dice of 6 sides and add them up");
+    assert_eq!(describe("[10d10wod]"), Roll::parse_with("[10d10wod]", Syntax::MapTool).expect("Not a roll").ok().expect("Bad roll").describe());
+    assert!(describe("[5d6sr]").contains("Shadowrun"));
+    assert!(describe("[3d20km1sa]").contains("from lowest to highest"));
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(Roll::parse("[3d6kh4]"), Some(Err(DiceError::KeepTooMany(_)))));
+    assert!(matches!(Roll::parse("[0d6kl]"), Some(Err(DiceError::KeepTooMany(_)))));
+    assert!(matches!(Roll::parse("[4d6min1234567890123456789]"), Some(Err(DiceError::NumberTooLarge(_)))));
+    assert!(matches!(Roll::parse("[1000000d6x]"), Some(Err(DiceError::TooManyDice(_)))));
+    // Trivial dice are still reckoned without rolling:
+    assert!(matches!(Roll::parse("[3d1min2]"), Some(Err(DiceError::Trivial(6, _)))));
+    assert!(matches!(Roll::parse("[2d1x]"), Some(Err(DiceError::Trivial(2, _)))));
+    // Neither a clamp without a bound, nor a stray rite:
+    for expr in ["[4d6min]", "[4d6max]", "[4d6xo]", "[4d6r>3]", "[4d6mi3]"] {
+        assert!(Roll::parse(expr).is_none(), "{} should be no roll", expr);
+    }
+}
+
+#[tokio::test]
+async fn rolls() {
+    assert_eq!(shown(&roll("[4d6min3]", &[1, 5, 2, 6]).await), "~~1~~→3, 5, ~~2~~→3, 6 = 17");
+    assert_eq!(shown(&roll("[3d6max4+1]", &[6, 4, 1]).await), "~~6~~→4, 4, 1 + 1 = 10");
+    assert_eq!(shown(&roll("[3d6min3sa]", &[6, 1, 4]).await), "~~1~~→3, 4, 6 = 13");
+    assert_eq!(shown(&roll("[2d20kh+5]", &[4, 17]).await), "~~4~~, 17 + 5 = 22");
+    assert_eq!(shown(&roll("[2d20kl]", &[4, 17]).await), "4, ~~17~~ = 4");
+    assert_eq!(shown(&roll("[2d6x]", &[6, 3, 2]).await), "(6, 2), 3 = 11");
+    assert_eq!(shown(&roll("[2d20r<3]", &[2, 15, 9]).await), "~~2~~, 9, 15 = 24");
+    assert_eq!(shown(&roll("[1d20r]", &[1, 1]).await), "~~1~~, 1 = 1");
+}
+
+#[test]
+fn scanned() {
+    let brackets = Delimiters::default();
+    let (rolls, _) = scan_with("Stats [4d6kh3], blast [8d6x] and [4d6min2]", &brackets, false, Syntax::MapTool, None);
+    assert_eq!(rolls.len(), 3);
+    assert!(rolls.iter().all(|(_, roll)| roll.is_ok()));
+}
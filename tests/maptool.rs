@@ -0,0 +1,123 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Operations of MapTool's dice expressions: those rolled here, and those only recognized:
+mod common;
+
+use denede_discord_bot::dice::{styled, DiceError, Roll, RollResult, Style};
+
+use common::{describe, roll};
+
+// The operation a recognized roll is not made for:
+fn not_supported(expr: &str) -> String {
+    match Roll::parse(expr) {
+        Some(Err(DiceError::NotSupported(op, _))) => op,
+        _ => panic!("{} should be recognized but not supported", expr),
+    }
+}
+
+fn shown(result: &RollResult) -> String {
+    styled(Style::plain(), || result.to_string())
+}
+
+// Shown result of a [min ...] or [max ...] roll:
+async fn bound(expr: &str) -> String {
+    match Roll::parse(expr) {
+        Some(Ok(roll)) => roll.roll().await.to_string(),
+        _ => panic!("{} should be rollable", expr),
+    }
+}
+
+#[test]
+fn parsed() {
+    let matrix = [
+        ("[4dF]", "roll 4 Fudge dice (each showing −1, 0 or +1) and add them up"),
+        ("[dF+1]", "roll 1 Fudge die (showing −1, 0 or +1), then add 1"),
+        ("[4df - 1]", "roll 4 Fudge dice (each showing −1, 0 or +1) and add them up, then subtract 1"),
+        ("[5d6h]", "roll 5 dice of 6 sides and add them up as STUN, counting their BODY too (none for a 1, 2 for the highest face, 1 otherwise)"),
+        ("[5d6b]", "roll 5 dice of 6 sides and count their BODY (none for a 1, 2 for the highest face, 1 otherwise)"),
+        ("[4d6k3]", "roll 4 dice of 6 sides and drop the lowest 1 of them and add up the rest"),
+        ("[4d6d1]", "roll 4 dice of 6 sides and drop the lowest 1 of them and add up the rest"),
+        ("[4d6dh1]", "roll 4 dice of 6 sides and drop the highest 1 of them and add up the rest"),
+        ("[6d6e]", "roll 6 dice of 6 sides and add them up, rolling one more die for every die that shows 6 or more"),
+    ];
+    for (expr, description) in matrix {
+        assert_eq!(describe(expr), description, "{}", expr);
+    }
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(Roll::parse("[0dF+2]"), Some(Err(DiceError::Trivial(2, _)))));
+    assert!(matches!(Roll::parse("[1000000dF]"), Some(Err(DiceError::TooManyDice(_)))));
+    assert!(matches!(Roll::parse("[3d6k4]"), Some(Err(DiceError::KeepTooMany(_)))));
+    assert!(matches!(Roll::parse("[3d6d4]"), Some(Err(DiceError::KeepTooMany(_)))));
+    assert!(matches!(Roll::parse("[1d1h]"), Some(Err(DiceError::Trivial(1, _)))));
+}
+
+#[test]
+fn recognized() {
+    assert_eq!(not_supported("[5d10o]"), "o");
+    assert_eq!(not_supported("[10d10s8]"), "s8");
+    assert_eq!(not_supported("[10d10es8]"), "es8");
+    assert_eq!(not_supported("[4d6rk3]"), "rk3");
+    assert_eq!(not_supported("[4d6rc3]"), "rc3");
+    assert_eq!(not_supported("[4d6r3]"), "r3");
+    assert_eq!(not_supported("[3dU+1]"), "U");
+    // Told apart from any other mistake, and quoted in the response:
+    let why = Roll::parse("[5d10o]").expect("Not a roll").err().expect("Rolled?");
+    assert!(why.quoting("[5d10o]").contains("MapTool rite `o`"));
+    // Whereas text with nothing of MapTool's is still no roll at all:
+    for expr in ["[5d10q]", "[do]", "[4d6rz]"] {
+        assert!(Roll::parse(expr).is_none(), "{} should be no roll", expr);
+    }
+}
+
+#[tokio::test]
+async fn rolled() {
+    // Fudge dice are rolled as d3s:
+    assert_eq!(shown(&roll("[4dF]", &[3, 1, 2, 3]).await), "+, −, 0, + = 1");
+    assert_eq!(shown(&roll("[2dF+2]", &[1, 1]).await), "−, − + 2 = 0");
+    assert_eq!(shown(&roll("[5d6h]", &[6, 1, 3, 4, 2]).await), "6, 1, 3, 4, 2 = 16 STUN, 5 BODY");
+    assert_eq!(shown(&roll("[5d6b]", &[6, 1, 3, 4, 2]).await), "6, 1, 3, 4, 2 → 5 BODY");
+    assert_eq!(roll("[5d6b+1]", &[6, 6, 1, 1, 1]).await.total(), Some(5));
+    assert_eq!(shown(&roll("[4d6k3]", &[1, 5, 3, 6]).await), "~~1~~, 5, 3, 6 = 14");
+    assert_eq!(shown(&roll("[4d6dh1]", &[1, 5, 3, 6]).await), "1, 5, 3, ~~6~~ = 9");
+    assert_eq!(shown(&roll("[2d6e]", &[6, 3, 2]).await), "(6, 2), 3 = 11");
+    assert_eq!(shown(&roll("[3d6hsa]", &[6, 1, 3]).await), "1, 3, 6 = 10 STUN, 3 BODY");
+}
+
+#[tokio::test]
+async fn bounds() {
+    assert_eq!(bound("[max 4dF]").await, "Theoretical maximum: 4 (this is not a roll)");
+    assert_eq!(bound("[min 4dF]").await, "Theoretical minimum: -4 (this is not a roll)");
+    assert_eq!(bound("[max 5d6b]").await, "Theoretical maximum: 10 (this is not a roll)");
+    assert_eq!(bound("[min 5d6h]").await, "Theoretical minimum: 5 (this is not a roll)");
+
+    // Subtracted dice are lowest at their highest face, and divisors highest at their lowest:
+    assert_eq!(bound("[min 1d20-1d6]").await, "Theoretical minimum: -5 (this is not a roll)");
+    assert_eq!(bound("[max 1d20-1d6]").await, "Theoretical maximum: 19 (this is not a roll)");
+    assert_eq!(bound("[max floor(1d20/1d4)]").await, "Theoretical maximum: 20 (this is not a roll)");
+    assert_eq!(bound("[min floor(1d20/1d4)]").await, "Theoretical minimum: 0 (this is not a roll)");
+    // Ten d6s always show a pair, but five may not; at most, every two dice are a pair:
+    assert_eq!(bound("[min 10d6m]").await, "Theoretical minimum: 1 (this is not a roll)");
+    assert_eq!(bound("[max 10d6m]").await, "Theoretical maximum: 5 (this is not a roll)");
+    assert_eq!(bound("[min 5d6m]").await, "Theoretical minimum: 0 (this is not a roll)");
+    assert_eq!(bound("[max 7d6m3]").await, "Theoretical maximum: 2 (this is not a roll)");
+    assert_eq!(bound("[max 12d6sr]").await, "Theoretical maximum: 12 (this is not a roll)");
+    assert_eq!(bound("[min 3d10wod]").await, "Theoretical minimum: 0 (this is not a roll)");
+}
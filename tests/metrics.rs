@@ -0,0 +1,48 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use denede_discord_bot::dice::{DiceError, Roll};
+use denede_discord_bot::lexer::Span;
+use denede_discord_bot::metrics;
+
+#[test]
+fn counted() {
+    let metrics = metrics::get();
+    let errors = metrics.parse_errors.with_label_values(&["division_by_zero"]).get();
+    metrics.parse_error(&DiceError::DivisionByZero(Span::default()));
+    assert_eq!(metrics.parse_errors.with_label_values(&["division_by_zero"]).get(), errors + 1);
+
+    metrics.randomorg_request(false, Duration::from_millis(300));
+    let text = metrics.gather();
+    assert!(text.contains("denede_parse_errors_total{kind=\"division_by_zero\"}"));
+    assert!(text.contains("denede_randomorg_requests_total{outcome=\"failed\"}"));
+    assert!(text.contains("denede_randomorg_latency_seconds_bucket{le=\"0.5\"}"));
+}
+
+#[test]
+fn simulations_not_counted() {
+    let metrics = metrics::get();
+    let middle = metrics.dice.with_label_values(&["keep_middle"]).get();
+    let roll = Roll::parse("[3d20km1]").expect("Not a roll").ok().expect("Bad roll");
+    roll.roll_local(&mut StdRng::seed_from_u64(1));
+    assert_eq!(metrics.dice.with_label_values(&["keep_middle"]).get(), middle);
+}
@@ -0,0 +1,295 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Parsing (and theoretical bounds, which never reach RANDOM.ORG) of every kind of roll and error:
+use denede_discord_bot::dice::{plain_text, quote, scan, scan_with, Delimiters, Dice, DiceError, DieKind, Roll, Syntax, MAX_EXPLOSIONS, MAX_EXPRESSIONS};
+use denede_discord_bot::lexer::tokenize;
+
+fn parse(expr: &str) -> Result<Roll, DiceError> {
+    match Roll::parse(expr) {
+        Some(roll) => roll,
+        None => panic!("{} should be a roll", expr),
+    }
+}
+
+fn error(expr: &str) -> DiceError {
+    match parse(expr) {
+        Ok(_) => panic!("{} should not be rollable", expr),
+        Err(why) => why,
+    }
+}
+
+// Shown result of a [min ...] or [max ...] roll:
+async fn bound(expr: &str) -> String {
+    match parse(expr) {
+        Ok(roll) => roll.roll().await.to_string(),
+        Err(why) => panic!("{} should be rollable: {}", expr, why),
+    }
+}
+
+#[test]
+fn not_rolls() {
+    for expr in ["[4]", "[hello]", "[1d20 hello]", "[]"] {
+        assert!(Roll::parse(expr).is_none(), "{} should not be a roll", expr);
+    }
+}
+
+#[test]
+fn shortcuts() {
+    for expr in ["[d]", "[3d]", "[d40]", "[2d6]", "[2d6+3]", "[2d6 - 3]", "[1d20 + 1d4 - 1]"] {
+        assert!(parse(expr).is_ok(), "{} should be rollable", expr);
+    }
+}
+
+#[tokio::test]
+async fn regular() {
+    assert_eq!(bound("[max 2d6+3]").await, "Theoretical maximum: 15 (this is not a roll)");
+    assert_eq!(bound("[min 2d6+3]").await, "Theoretical minimum: 5 (this is not a roll)");
+}
+
+#[tokio::test]
+async fn keep_middle() {
+    assert_eq!(bound("[max 4d6km2]").await, "Theoretical maximum: 12 (this is not a roll)");
+    assert_eq!(bound("[min 4d6km2]").await, "Theoretical minimum: 2 (this is not a roll)");
+}
+
+#[test]
+fn drop() {
+    assert!(Dice::new(4, 6, DieKind::Drop(1)).is_ok());
+    assert!(matches!(Dice::new(4, 6, DieKind::Drop(5)), Err(DiceError::KeepTooMany(_))));
+}
+
+#[tokio::test]
+async fn matches() {
+    assert_eq!(bound("[max 3d6m]").await, "Theoretical maximum: 1 (this is not a roll)");
+    assert_eq!(bound("[max 3d6m3]").await, "Theoretical maximum: 1 (this is not a roll)");
+}
+
+#[tokio::test]
+async fn shadowrun() {
+    assert_eq!(bound("[max 5d6sr]").await, "Theoretical maximum: 5 (this is not a roll)");
+    assert_eq!(bound("[min 5d6sr]").await, "Theoretical minimum: 0 (this is not a roll)");
+}
+
+#[tokio::test]
+async fn again() {
+    // Every die explodes until the cap, each of its rolls being a success:
+    assert_eq!(bound("[max 2d10wod]").await, "Theoretical maximum: 202 (this is not a roll)");
+    assert_eq!(bound("[min 2d10wod]").await, "Theoretical minimum: 0 (this is not a roll)");
+}
+
+#[tokio::test]
+async fn explosions_capped() {
+    // Reckoned with every die at its highest face, so every die explodes until the cap:
+    let chain = MAX_EXPLOSIONS as i64 + 1;
+    assert_eq!(bound("[max 1d6x]").await, format!("Theoretical maximum: {} (this is not a roll)", 6 * chain));
+    assert_eq!(bound("[max 3d6x+2]").await, format!("Theoretical maximum: {} (this is not a roll)", 3 * 6 * chain + 2));
+    assert_eq!(bound("[max sw4]").await, format!("Theoretical maximum: {} (this is not a roll)", 6 * chain));
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(error("[1234567890123456789d6]"), DiceError::NumberTooLarge(_)));
+    assert!(matches!(error("[21d6]"), DiceError::TooManyDice(_)));
+    assert!(matches!(error("[1d1001]"), DiceError::TooManySides(_)));
+    assert!(matches!(error("[1d6+61]"), DiceError::BonusTooLarge(_)));
+    assert!(matches!(error("[2d6km3]"), DiceError::KeepTooMany(_)));
+    assert!(matches!(error("[2d6m3]"), DiceError::MatchTooMany(_)));
+    assert!(matches!(error("[10d6m0]"), DiceError::MatchTooFew(_)));
+    assert!(matches!(error("[10d6m1]"), DiceError::MatchTooFew(_)));
+    assert!(matches!(error("[3d1+2]"), DiceError::Trivial(5, _)));
+    assert!(matches!(error("[2000000000d1]"), DiceError::Jest(_)));
+    assert!(matches!(error("[median(1d6, 2)]"), DiceError::UnknownFunction(name, _) if name == "median"));
+    assert!(matches!(error("[floor(1d6 / 0)]"), DiceError::DivisionByZero(_)));
+    assert!(matches!(error("[101 coins]"), DiceError::TooManyCoins(_)));
+    assert!(matches!(error("[1dH+2]"), DiceError::SymbolicArithmetic(_)));
+    assert!(matches!(error("[5d6wod]"), DiceError::AgainNotD10(_)));
+    for expr in ["[1d20+7 vs 15 vs 10]", "[1d20 dc 15 vs 10]", "[3d6 <= 12 vs 10]", "[1d20 vs 15 <= 12]"] {
+        assert!(matches!(error(expr), DiceError::SeveralComparisons(_)), "{} should be compared once", expr);
+    }
+    for expr in ["[avg avg 1d6]", "[avg max 2d6]", "[min avg 1d6]", "[max ! min 1d6]"] {
+        assert!(matches!(error(expr), DiceError::NotRolled(_)), "{} should not be reckoned", expr);
+    }
+    let huge = ["999999999999999999"; 10].join(" + ");
+    assert!(matches!(error(&format!("[{}]", huge)), DiceError::ResultTooLarge(_)));
+}
+
+#[test]
+fn limits_in_errors() {
+    // The default limits, as none are set through the environment:
+    assert!(error("[21d6]").to_string().ends_with("up to 20 at once!"));
+    assert!(error("[1d1001]").to_string().ends_with("unto 1000!"));
+}
+
+#[tokio::test]
+async fn beyond_sixteen_bits() {
+    // Values are i64 throughout, so totals past u16::MAX (65535) are reported as they are:
+    assert_eq!(bound("[max 20d1000 + 20d1000 + 20d1000 + 20d1000]").await, "Theoretical maximum: 80000 (this is not a roll)");
+    assert_eq!(bound("[min 1d20 - 70000]").await, "Theoretical minimum: -69999 (this is not a roll)");
+}
+
+// Every valid notation, pinned by the lowest and highest results it can yield:
+const GOLDEN: [(&str, i64, i64); 23] = [
+    ("d", 1, 20), ("3d", 3, 60), ("d40", 1, 40), ("2d20-5", -3, 35), ("3d20km1+2", 3, 22), ("10d6m3", 0, 3),
+    ("12d6sr", 0, 12), ("7d109a", 0, 707), ("15d6sa", 15, 90), ("max(1d20, 1d20) + 5", 6, 25),
+    ("min(2d6+3, 10)", 5, 10), ("floor(3d6 / 2)", 1, 9), ("ceil(1d20 / 3) + 1", 2, 8), ("round(7 / 2)", 4, 4),
+    ("(1d4)d6", 1, 24), ("(1d4+1)d6+2", 4, 32), ("1d20 + 1d4 - 1", 1, 23), ("-2", -2, -2), ("sw8+1", 2, 809),
+    ("1d20+5 vs 15", 6, 25), ("3d6 <= 12", 3, 18), ("2d6 dc 7", 2, 12), ("max(1d6,1d8)-3", -2, 5),
+];
+
+#[tokio::test]
+async fn golden() {
+    for (expr, min, max) in GOLDEN {
+        assert_eq!(bound(&format!("[min {}]", expr)).await, format!("Theoretical minimum: {} (this is not a roll)", min), "{}", expr);
+        assert_eq!(bound(&format!("[max {}]", expr)).await, format!("Theoretical maximum: {} (this is not a roll)", max), "{}", expr);
+    }
+}
+
+#[test]
+fn quoted_errors() {
+    // The part to blame is pointed at, unless it is all of the roll:
+    assert!(error("[21d6]").quoting("[21d6]").ends_with("…in `[21d6]`, at `21`"));
+    assert!(error("[3d1+2]").quoting("[3d1+2]").ends_with("…in `[3d1+2]`"));
+    let several = error("[max(21d6, 2, 1d1001)]").quoting("[max(21d6, 2, 1d1001)]");
+    let lines = several.lines().skip(1).collect::<Vec<&str>>();
+    assert!(lines[0].ends_with("…in `21d6`, at `21`") && lines[1].ends_with("…in `1d1001`, at `1001`"));
+    // Users cannot format or mention anything through the quote:
+    assert_eq!(quote("[`@everyone`]"), "`[ˋ@\u{200B}everyoneˋ]`");
+    assert_eq!(quote(&"1".repeat(50)), format!("`{}…`", "1".repeat(40)));
+}
+
+#[test]
+fn spans() {
+    // Errors are blamed on the bytes of the expression they come from:
+    for (expr, part) in [
+        ("[1d20 + 21d6]", "21"), ("[1d1001]", "1001"), ("[1d6 + 61]", "+ 61"), ("[4d6km5]", "km5"), ("[5d6wod]", "6"),
+        ("[2d6 vs 5 vs 3]", "vs 3"), ("[floor(1d6 / 0)]", "0"), ("[median(1d6, 2)]", "median"), ("[1dH+2]", "1dH"),
+        ("[(1d4)d6k30]", "k30"), ("[1234567890123456789d6]", "1234567890123456789"),
+    ] {
+        assert_eq!(error(expr).span().and_then(|span| span.of(expr)), Some(part), "{}", expr);
+    }
+    let tokens = tokenize("[2d6 + 3]").iter().map(|token| token.text).collect::<Vec<&str>>();
+    assert_eq!(tokens, ["[", "2", "d", "6", " ", "+", " ", "3", "]"]);
+    // Only ASCII digits make numbers, so others are no roll at all:
+    assert!(Roll::parse("[٣d6]").is_none());
+}
+
+#[test]
+fn several_errors() {
+    let why = error("[max(21d6, 2, 1d1001)]");
+    assert!(matches!(&why, DiceError::Several(errors) if errors.len() == 2));
+    let lines = why.to_string().lines().skip(1).map(str::to_owned).collect::<Vec<String>>();
+    assert!(lines[0].ends_with("…in `21d6`") && lines[1].ends_with("…in `1d1001`"));
+    // A single error is shown as is:
+    assert!(matches!(error("[1d20 + 21d6]"), DiceError::TooManyDice(_)));
+}
+
+#[test]
+fn secret() {
+    for expr in ["[gm 2d6]", "[GM 1d20+5]", "[seed:1 gm 3d6]", "[gm ! 20d6]"] {
+        assert!(parse(expr).is_ok_and(|roll| roll.is_secret()), "{} should be secret", expr);
+    }
+    assert!(parse("[2d6]").is_ok_and(|roll| !roll.is_secret()));
+    assert!(matches!(error("[gm 1d1001]"), DiceError::TooManySides(_)));
+    assert!(Roll::parse("[gm]").is_none());
+}
+
+#[test]
+fn scan_noise() {
+    for content in ["", "Flogg takes no damage", "[citation needed] [1] [] [[]]", "]]]d20[[[", &"[".repeat(2000)] {
+        let (rolls, capped) = scan(content);
+        assert!(rolls.is_empty() && !capped, "{:?} should hold no rolls", content);
+    }
+
+    let (rolls, capped) = scan("Flogg takes [2d8+2] dmg, [not a roll] and [25d6]");
+    assert_eq!(rolls.iter().map(|(expr, _)| expr.as_str()).collect::<Vec<&str>>(), ["[2d8+2]", "[25d6]"]);
+    assert!(rolls[0].1.is_ok() && rolls[1].1.is_err() && !capped);
+}
+
+#[test]
+fn scan_strict() {
+    let brackets = Delimiters::default();
+    let content = "Flogg takes [2d6kk] dmg and [1d20]";
+    let (rolls, _) = scan_with(content, &brackets, false, Syntax::MapTool, None);
+    assert_eq!(rolls.iter().map(|(expr, _)| expr.as_str()).collect::<Vec<&str>>(), ["[1d20]"]);
+
+    // Mistakes are pointed out instead of skipped:
+    let (rolls, _) = scan_with(content, &brackets, true, Syntax::MapTool, None);
+    assert_eq!(rolls.iter().map(|(expr, _)| expr.as_str()).collect::<Vec<&str>>(), ["[2d6kk]", "[1d20]"]);
+    let Err(why) = &rolls[0].1 else {
+        panic!("[2d6kk] should be ill-formed");
+    };
+    assert!(matches!(why, DiceError::IllFormed(_)));
+    assert!(why.quoting(&rolls[0].0).ends_with("…in `[2d6kk]`"));
+    // Still only within plain text:
+    assert!(scan_with("`[2d6kk]` ||[nope]||", &brackets, true, Syntax::MapTool, None).0.is_empty());
+}
+
+#[test]
+fn scan_cap() {
+    let content = "[d20]".repeat(MAX_EXPRESSIONS);
+    let (rolls, capped) = scan(&content);
+    assert_eq!(rolls.len(), MAX_EXPRESSIONS);
+    assert!(!capped);
+
+    // A full message of them, with some noise in between that does not count towards the cap:
+    let content = "[d20] [nope] ".repeat(150);
+    let (rolls, capped) = scan(&content);
+    assert_eq!(rolls.len(), MAX_EXPRESSIONS);
+    assert!(capped);
+}
+
+// Messages, and the rolls that are to be found in them:
+const MARKUP: &[(&str, &[&str])] = &[
+    ("[1d20] `[1d4]` [1d6]", &["[1d20]", "[1d6]"]),
+    ("```\nlog: [1d20]\n``` [1d6]", &["[1d6]"]),
+    ("```rust\nlet x = v[2d6];", &[]),
+    ("before ``code with ` [1d4] inside`` after [1d8]", &["[1d8]"]),
+    ("||[1d20] spoiled|| [1d6]", &["[1d6]"]),
+    ("||```[1d20]```|| [1d6]", &["[1d6]"]),
+    ("`||`[1d20]`||`", &["[1d20]"]),
+    ("a lone ` and a lone || do not hide [1d12]", &["[1d12]"]),
+    ("[1d`x`20]", &[]),
+    ("[1d20 `+ 5`]", &[]),
+];
+
+#[test]
+fn markup() {
+    for (content, expected) in MARKUP {
+        let (rolls, _) = scan(content);
+        assert_eq!(rolls.iter().map(|(expr, _)| expr.as_str()).collect::<Vec<&str>>(), *expected, "in {:?}", content);
+    }
+    assert_eq!(plain_text("a `b` c ||d|| e"), ["a ", " c ", " e"]);
+    assert_eq!(plain_text("plain"), ["plain"]);
+}
+
+#[test]
+fn delimiters() {
+    let found = |content: &str, open: &str, close: &str| {
+        let delimiters = Delimiters::new(open, close).expect("No delimiters?");
+        scan_with(content, &delimiters, false, Syntax::MapTool, None).0.into_iter().map(|(expr, _)| expr).collect::<Vec<String>>()
+    };
+    assert_eq!(found("Hit for {{2d8+2}} and {{1d6}} [1d4]", "{{", "}}"), ["[2d8+2]", "[1d6]"]);
+    assert_eq!(found("r!1d20+5 then r!2d6 but not bar!1d4 nor r! 1d8", "r!", ""), ["[1d20+5]", "[2d6]"]);
+    assert_eq!(found("[[1d20] (1d6) <<1d4>>", "(", ")"), ["[1d6]"]);
+    assert_eq!(found("`{1d20}` {1d6}", "{", "}"), ["[1d6]"]);
+
+    for (open, close) in [("", "]"), ("`", "`"), ("||", "||"), ("*", ""), ("{ {", "}}"), ("<<<<<", ">>>>>")] {
+        assert!(Delimiters::new(open, close).is_err(), "{:?} and {:?} should not be delimiters", open, close);
+    }
+    assert!(Delimiters::new("[", "]").expect("No brackets?").is_default());
+}
@@ -0,0 +1,62 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Distributions of the totals of rolls, reckoned without rolling them:
+use denede_discord_bot::dice::{Distribution, Roll};
+
+fn chances(expr: &str) -> Distribution {
+    Roll::parse(expr).expect("Not a roll").ok().expect("Bad roll").distribution().expect("No distribution").ok().expect("Too complex")
+}
+
+#[test]
+fn exact() {
+    let distribution = chances("[2d6+3]");
+    assert!(distribution.exact());
+    assert_eq!((distribution.minimum(), distribution.maximum()), (5, 15));
+    assert!((distribution.mean() - 10.0).abs() < 1e-9);
+    assert!((distribution.at_least(15) - 1.0 / 36.0).abs() < 1e-9);
+    assert!((distribution.at_least(5) - 1.0).abs() < 1e-9);
+
+    let distribution = chances("[1d20 - 1d4 + 2]");
+    assert!(distribution.exact());
+    assert_eq!((distribution.minimum(), distribution.maximum()), (-1, 21));
+}
+
+#[test]
+fn histogram() {
+    let histogram = chances("[1d20]").histogram(10);
+    assert_eq!(histogram.len(), 10);
+    assert_eq!((histogram[0].0, histogram[0].1), (1, 2));
+    assert_eq!((histogram[9].0, histogram[9].1), (19, 20));
+    assert!(histogram.iter().all(|&(_, _, chance)| (chance - 0.1).abs() < 1e-9));
+
+    // Fewer totals than buckets:
+    assert_eq!(chances("[1d4]").histogram(10).len(), 4);
+}
+
+#[test]
+fn estimated() {
+    let distribution = chances("[max(1d20, 1d20)]");
+    assert!(!distribution.exact());
+    assert_eq!((distribution.minimum(), distribution.maximum()), (1, 20));
+    assert!((distribution.mean() - 13.825).abs() < 0.1);
+}
+
+#[test]
+fn unbounded() {
+    assert!(Roll::parse("[3dH]").expect("Not a roll").ok().expect("Bad roll").distribution().is_none());
+}
@@ -0,0 +1,46 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Behaviour of the RANDOM.ORG client when it does not answer in time:
+use std::env;
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+use denede_discord_bot::dice::{styled, Roll, Style};
+
+#[tokio::test]
+async fn slow_answer_falls_back() {
+    // Accepts connections, but never answers. Every request, the quota check included, goes to it
+    // rather than to RANDOM.ORG itself:
+    let listener = TcpListener::bind("127.0.0.1:0").expect("No listener?");
+    env::set_var("RANDOMORG_URL", format!("http://{}", listener.local_addr().expect("No address?")));
+    env::set_var("RANDOMORG_TIMEOUT_MS", "200");
+    std::thread::spawn(move || {
+        let _connections = listener.incoming().collect::<Vec<_>>();
+    });
+
+    let roll = match Roll::parse("[2d20+1]") {
+        Some(Ok(roll)) => roll,
+        _ => panic!("[2d20+1] should be rollable"),
+    };
+    let start = Instant::now();
+    let result = roll.roll().await;
+    // Both the quota check and the request itself time out, well within the default timeout of each:
+    assert!(start.elapsed() < Duration::from_millis(1500), "took {:?}", start.elapsed());
+    assert!(!result.is_truly_random());
+    assert!(styled(Style { pseudo_random: true, ..Style::default() }, || result.to_string()).ends_with(" *[pseudo-random]*"));
+}
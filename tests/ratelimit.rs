@@ -0,0 +1,56 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::time::{Duration, Instant};
+
+use denede_discord_bot::ratelimit::{Limit, RateLimiter};
+
+#[test]
+fn burst() {
+    let limiter = RateLimiter::new(3, 6);
+    let now = Instant::now();
+    for _ in 0..3 {
+        assert_eq!(limiter.check(1, now), Limit::Allowed);
+    }
+    // Told once, then left alone until the notice cools down:
+    assert_eq!(limiter.check(1, now), Limit::Limited { notify: true });
+    assert_eq!(limiter.check(1, now), Limit::Limited { notify: false });
+    // Every user has their own bucket:
+    assert_eq!(limiter.check(2, now), Limit::Allowed);
+}
+
+#[test]
+fn refill() {
+    // One more roll every ten seconds:
+    let limiter = RateLimiter::new(1, 6);
+    let now = Instant::now();
+    assert_eq!(limiter.check(1, now), Limit::Allowed);
+    assert_eq!(limiter.check(1, now + Duration::from_secs(5)), Limit::Limited { notify: true });
+    assert_eq!(limiter.check(1, now + Duration::from_secs(10)), Limit::Allowed);
+    assert_eq!(limiter.check(1, now + Duration::from_secs(11)), Limit::Limited { notify: false });
+    // Waiting longer does not let more rolls pile up than the burst:
+    let later = now + Duration::from_secs(120);
+    assert_eq!(limiter.check(1, later), Limit::Allowed);
+    assert_eq!(limiter.check(1, later), Limit::Limited { notify: true });
+}
+
+#[test]
+fn disabled() {
+    let limiter = RateLimiter::new(0, 0);
+    let now = Instant::now();
+    assert!((0..100).all(|_| limiter.check(1, now) == Limit::Allowed));
+}
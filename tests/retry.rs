@@ -0,0 +1,85 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use serenity::Error;
+
+use denede_discord_bot::retry::{backoff, classify, classify_status, with_retries, Failure, ATTEMPTS};
+
+#[test]
+fn statuses() {
+    // Rate limited, or Discord failing:
+    for status in [429, 500, 502, 503, 504] {
+        assert_eq!(classify_status(status), Failure::Retryable, "{status}");
+    }
+    // E.g.: malformed (or too long), missing permissions, unknown channel:
+    for status in [400, 401, 403, 404, 413] {
+        assert_eq!(classify_status(status), Failure::Permanent, "{status}");
+    }
+}
+
+#[test]
+fn errors() {
+    assert_eq!(classify(&Error::Io(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))), Failure::Retryable);
+    assert_eq!(classify(&Error::Other("no")), Failure::Permanent);
+    assert_eq!(classify(&Error::ExceededLimit("too long".to_string(), 2000)), Failure::Permanent);
+}
+
+#[test]
+fn backoffs() {
+    for retry in 1..=3 {
+        let wait = Duration::from_millis(500) * 2u32.pow(retry - 1);
+        let backoff = backoff(retry);
+        assert!(wait <= backoff && backoff <= wait.mul_f64(1.5), "{backoff:?}");
+    }
+}
+
+#[tokio::test]
+async fn permanent() {
+    let attempts = AtomicU32::new(0);
+    let sent = with_retries(|| async {
+        attempts.fetch_add(1, Ordering::Relaxed);
+        Err::<(), _>(Error::Other("Missing permissions"))
+    }).await;
+    assert!(sent.is_err());
+    assert_eq!(attempts.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn retryable() {
+    let attempts = AtomicU32::new(0);
+    let sent = with_retries(|| async {
+        match attempts.fetch_add(1, Ordering::Relaxed) {
+            0 => Err(Error::Io(io::Error::new(io::ErrorKind::TimedOut, "timed out"))),
+            _ => Ok(7),
+        }
+    }).await;
+    assert_eq!(sent.ok(), Some(7));
+    assert_eq!(attempts.load(Ordering::Relaxed), 2);
+
+    // Given up on after the last attempt:
+    let attempts = AtomicU32::new(0);
+    let sent = with_retries(|| async {
+        attempts.fetch_add(1, Ordering::Relaxed);
+        Err::<(), _>(Error::Io(io::Error::new(io::ErrorKind::TimedOut, "timed out")))
+    }).await;
+    assert!(sent.is_err());
+    assert_eq!(attempts.load(Ordering::Relaxed), ATTEMPTS);
+}
@@ -0,0 +1,328 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Rolls with predetermined dice, to check the result of every kind of roll:
+mod common;
+
+use std::collections::VecDeque;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use denede_discord_bot::dice::{escape, styled, Dice, DieKind, Roll, RollResult, Roller, Style, MAX_EXPLOSIONS};
+use denede_discord_bot::randomorg;
+
+use common::{roll, Queue};
+
+// Always rolls the highest face:
+struct Highest;
+
+impl Roller for Highest {
+    async fn gen(&mut self, num: i64, _min: i64, max: i64) -> (Vec<i64>, bool) {
+        (vec![max; num as usize], true)
+    }
+}
+
+#[tokio::test]
+async fn regular() {
+    let result = roll("[2d6+3]", &[4, 5]).await;
+    assert_eq!(result.to_string(), "⚃, ⚄ + 3 = 12");
+    assert_eq!(result.total(), Some(12));
+}
+
+#[tokio::test]
+async fn keep_middle() {
+    let result = roll("[4d6km2]", &[6, 1, 3, 4]).await;
+    assert_eq!(result.to_string(), "~~⚅~~, ~~⚀~~, ⚂, ⚃ = 7");
+    assert_eq!(result.total(), Some(7));
+}
+
+#[tokio::test]
+async fn drop() {
+    // Only one of the tied lowest dice is dropped:
+    let dice = Dice::new(4, 6, DieKind::Drop(1)).ok().expect("No dice?");
+    let result = dice.roll_with(&mut Queue(VecDeque::from([3, 1, 1, 6]))).await;
+    assert_eq!(result.rolls(), [3, 1, 1, 6]);
+    assert_eq!(result.discarded(), [1]);
+    assert_eq!(result.to_string(), "⚂, ~~⚀~~, ⚀, ⚅ = 10");
+    assert_eq!(result.total(), 10);
+}
+
+// Rolls of dice tied at the cutoff, and which of them are kept (struck through otherwise). Exactly
+// as many dice as asked for are kept, the tied ones being picked by their order:
+const TIES: &[(&str, &[i64], &str)] = &[
+    ("[4d8kh1]", &[6, 6, 6, 1], "~~6~~, ~~6~~, 6, ~~1~~ = 6"),
+    ("[4d8kh1]", &[1, 1, 1, 6], "~~1~~, ~~1~~, ~~1~~, 6 = 6"),
+    ("[4d8kh2]", &[6, 6, 6, 1], "~~6~~, 6, 6, ~~1~~ = 12"),
+    ("[4d8kl1]", &[6, 6, 6, 1], "~~6~~, ~~6~~, ~~6~~, 1 = 1"),
+    ("[4d8kl1]", &[1, 1, 1, 6], "~~1~~, ~~1~~, 1, ~~6~~ = 1"),
+    ("[4d8kl2]", &[1, 1, 1, 6], "~~1~~, 1, 1, ~~6~~ = 2"),
+    ("[4d8d1]", &[6, 6, 6, 1], "6, 6, 6, ~~1~~ = 18"),
+    ("[4d8d1]", &[1, 1, 1, 6], "~~1~~, 1, 1, 6 = 8"),
+    ("[4d8d2]", &[1, 1, 1, 6], "~~1~~, ~~1~~, 1, 6 = 7"),
+    ("[4d8dh1]", &[6, 6, 6, 1], "~~6~~, 6, 6, 1 = 13"),
+    ("[4d8dh1]", &[1, 1, 1, 6], "1, 1, 1, ~~6~~ = 3"),
+    ("[4d8dh2]", &[6, 6, 6, 1], "~~6~~, ~~6~~, 6, 1 = 7"),
+];
+
+#[tokio::test]
+async fn ties() {
+    for (expr, dice, shown) in TIES {
+        let result = roll(expr, dice).await;
+        assert_eq!(result.to_string(), *shown, "{} with {:?}", expr, dice);
+        // Each struck die is one that was left out:
+        let struck = shown.split(", ").filter(|die| die.starts_with("~~")).count();
+        assert_eq!(result.individuals()[0].discarded().len(), struck, "{} with {:?}", expr, dice);
+    }
+}
+
+#[tokio::test]
+async fn matches() {
+    let result = roll("[5d6m]", &[2, 5, 2, 5, 5]).await;
+    assert_eq!(result.to_string(), "2, 5, 2, 5, 5 → 2 matches (2×2, 5×3)");
+    assert_eq!(result.total(), Some(2));
+}
+
+#[tokio::test]
+async fn shadowrun() {
+    let result = roll("[4d6sr]", &[5, 6, 1, 1]).await;
+    assert_eq!(result.to_string(), "5, 6, 1, 1 → 2 hits");
+    let result = roll("[3d6sr]", &[1, 1, 5]).await;
+    assert_eq!(result.to_string(), "1, 1, 5 → 1 hit — Glitch!");
+    assert_eq!(result.total(), Some(1));
+}
+
+#[tokio::test]
+async fn again() {
+    // The 10 is rolled again, showing an 8:
+    let result = roll("[2d10wod]", &[10, 3, 8]).await;
+    assert_eq!(result.to_string(), "(10, 8), 3 → 2 successes");
+    assert_eq!(result.total(), Some(2));
+}
+
+#[tokio::test]
+async fn wild() {
+    let result = roll("[sw8+1]", &[8, 2, 3]).await;
+    assert_eq!(result.to_string(), "trait: 8, 2 | wild: 3 → 10 + 1 = 11");
+    assert_eq!(result.total(), Some(11));
+}
+
+#[tokio::test]
+async fn sum() {
+    let result = roll("[1d20 + 1d4 - 1]", &[15, 2]).await;
+    assert_eq!(result.total(), Some(16));
+    // Computing with the dice themselves, rather than with the shown text:
+    let rolled = result.individuals().iter().map(|dice| dice.rolls().len()).sum::<usize>();
+    assert_eq!(rolled, 2);
+    assert!(result.individuals().iter().all(|dice| dice.is_truly_random()));
+}
+
+#[tokio::test]
+async fn explosions_follow_the_last_die() {
+    // Each reroll decides whether there is another one, rather than the first die (which would
+    // have them go on until the dice ran out):
+    let result = roll("[1d8x]", &[8, 8, 3]).await;
+    assert_eq!(result.total(), Some(19));
+    assert!(!result.to_string().contains("stopped after"));
+    assert_eq!(roll("[1d10wod]", &[10, 10, 4]).await.total(), Some(2));
+    assert_eq!(roll("[sw6]", &[6, 2, 4]).await.total(), Some(8));
+}
+
+#[tokio::test]
+async fn explosions_stop() {
+    let roll = match Roll::parse("[sw4]") {
+        Some(Ok(roll)) => roll,
+        _ => panic!("[sw4] should be rollable"),
+    };
+    let result = roll.roll_with(&mut Highest).await;
+    // Both dice explode a hundred times after their first roll, the wild d6 beating the trait d4:
+    assert_eq!(result.total(), Some(6 * 101));
+    assert!(result.to_string().contains("stopped after a hundred explosions"));
+}
+
+#[tokio::test]
+async fn explosions_capped() {
+    // Every die explodes MAX_EXPLOSIONS times after its first roll, and no more:
+    let chain = MAX_EXPLOSIONS as i64 + 1;
+    for (expr, total) in [("[1d6x]", 6 * chain), ("[3d6x+2]", 3 * 6 * chain + 2), ("[2d10wod]", 2 * chain)] {
+        let result = common::rollable(Roll::parse(expr), expr).roll_with(&mut Highest).await;
+        assert_eq!(result.total(), Some(total), "{}", expr);
+        assert!(result.individuals()[0].capped(), "{} should be capped", expr);
+    }
+    assert!(!roll("[1d6x]", &[6, 2]).await.individuals()[0].capped());
+}
+
+#[tokio::test]
+async fn sorted_discards() {
+    let result = roll("[4d6km2sd]", &[6, 1, 3, 4]).await;
+    assert_eq!(result.to_string(), "~~⚅~~, ⚃, ⚂, ~~⚀~~ = 7");
+}
+
+#[test]
+fn local() {
+    let roll = match Roll::parse("[max(2d6+3, 1d20) + 1]") {
+        Some(Ok(roll)) => roll,
+        _ => panic!("Should be rollable"),
+    };
+    let mut rng = StdRng::seed_from_u64(20);
+    let result = roll.roll_local(&mut rng);
+    assert!((6..=21).contains(&result.total().expect("No total?")));
+    assert!(result.individuals().iter().all(|dice| !dice.is_truly_random()));
+    // The same seed rolls the same dice:
+    let again = roll.roll_local(&mut StdRng::seed_from_u64(20));
+    assert_eq!(result.to_string(), again.to_string());
+}
+
+#[tokio::test]
+async fn seeded() {
+    // No dice are taken from the roller, all of them coming from the seed:
+    let first = roll("[seed:1234 4d10wod]", &[]).await.to_string();
+    let second = roll("[seed:1234 4d10wod]", &[]).await.to_string();
+    assert_eq!(first, second);
+    assert!(first.starts_with("Seeded roll (seed 1234; not random, not for actual play): "));
+    assert_ne!(roll("[seed:1235 10d100]", &[]).await.to_string(), roll("[seed:1234 10d100]", &[]).await.to_string());
+}
+
+#[tokio::test]
+async fn negative_bonuses() {
+    // Subtracted, rather than added as a negative number:
+    assert_eq!(roll("[max(1d6,1d8)-3]", &[2, 7]).await.to_string(), "max(⚁ = 2 | 7 ✓) - 3 = 4");
+    assert_eq!(roll("[2d20-5]", &[9, 3]).await.to_string(), "9, 3 - 5 = 7");
+    assert_eq!(roll("[floor(1d20 / 2) - 1]", &[9]).await.to_string(), "floor((9) / 2) - 1 = 3");
+    assert_eq!(roll("[sw8-2]", &[5, 3]).await.to_string(), "trait: 5 | wild: 3 → 5 - 2 = 3");
+}
+
+#[tokio::test]
+async fn markdown() {
+    // Only the final result stands out:
+    assert_eq!(format!("{:#}", roll("[1d20]", &[17]).await), "**17**");
+    assert_eq!(format!("{:#}", roll("[2d6+3]", &[4, 5]).await), "⚃, ⚄ + 3 = **12**");
+    assert_eq!(format!("{:#}", roll("[1d20 + 1d4 + 2]", &[11, 3]).await), "(11) + (3) + 2 = **16**");
+    assert_eq!(format!("{:#}", roll("[max(1d20, 1d20)]", &[4, 15]).await), "max(4 | 15 ✓) = **15**");
+    assert_eq!(format!("{:#}", roll("[1d20+7 vs 15]", &[8]).await), "8 + 7 = **15** — Success!");
+    assert_eq!(format!("{:#}", roll("[5d6sr]", &[5, 6, 1, 2, 3]).await), "5, 6, 1, 2, 3 → **2** hits");
+}
+
+#[test]
+fn escaped_labels() {
+    assert_eq!(escape("**Attack** of @everyone"), "\\*\\*Attack\\*\\* of @\u{200B}everyone");
+    assert_eq!(escape("# [link](url)"), "\\# \\[link\\](url)");
+}
+
+#[tokio::test]
+async fn naturals() {
+    assert_eq!(roll("[1d20+5]", &[20]).await.to_string(), "**20** 🎉 + 5 = 25");
+    assert_eq!(format!("{:#}", roll("[1d20]", &[1]).await), "**1** 💀");
+    assert_eq!(roll("[max(1d20, 1d20)]", &[1, 20]).await.to_string(), "max(**1** 💀 | **20** 🎉 ✓) = 20");
+    // Only for d20s, and only for the dice that are kept:
+    assert_eq!(roll("[2d6+1d20]", &[1, 6, 20]).await.to_string(), "(⚀, ⚅ = 7) + (**20** 🎉) = 27");
+    assert_eq!(roll("[3d20km1]", &[20, 1, 9]).await.to_string(), "~~20~~, ~~1~~, 9 = 9");
+    let result = roll("[3d20km1]", &[1, 20, 20]).await;
+    assert_eq!(result.to_string(), "~~1~~, **20** 🎉, ~~20~~ = 20");
+    assert_eq!(result.individuals()[0].sides(), 20);
+}
+
+#[tokio::test]
+async fn compact() {
+    assert_eq!(format!("{:#}", roll("[! 4d6+2]", &[1, 2, 3, 4]).await), "**12**");
+    assert_eq!(roll("[!max(1d20, 1d20)]", &[4, 15]).await.to_string(), "15");
+    let seeded = roll("[seed:1 ! 12d6]", &[]).await;
+    assert!(seeded.to_string().starts_with(&format!("Seeded roll (seed 1; not random, not for actual play): {} ", seeded.total().expect("No total?"))));
+    // Nothing to sum up:
+    assert_eq!(roll("[! 2dH]", &[6, 3]).await.to_string(), "Crit, Hit → 1× Crit, 1× Hit");
+}
+
+#[tokio::test]
+async fn pips() {
+    // The faces are followed by the total, even for a single die:
+    assert_eq!(roll("[1d6]", &[5]).await.to_string(), "⚄ = 5");
+    assert_eq!(format!("{:#}", roll("[3d6]", &[1, 5, 3]).await), "⚀, ⚄, ⚂ = **9**");
+    // Only d6s are shown by their faces:
+    let result = roll("[2d6 + 1d20 + 1d8]", &[2, 6, 17, 8]).await;
+    assert_eq!(result.to_string(), "(⚁, ⚅ = 8) + (17) + (8) = 33");
+    assert_eq!(styled(Style { pips: false, ..Style::default() }, || result.to_string()), "(2, 6 = 8) + (17) + (8) = 33");
+}
+
+#[tokio::test]
+async fn success() {
+    assert_eq!(roll("[1d20+7 vs 15]", &[8]).await.success(), Some(true));
+    assert_eq!(roll("[1d20+7 dc 15]", &[7]).await.success(), Some(false));
+    assert_eq!(roll("[3d6 <= 12]", &[4, 4, 4]).await.success(), Some(true));
+    // A critical success, over the target number:
+    assert_eq!(roll("[gurps3]", &[1, 1, 2]).await.success(), Some(true));
+    assert_eq!(roll("[gurps16]", &[6, 6, 5]).await.success(), Some(false));
+    assert_eq!(roll("[2d6]", &[1, 2]).await.success(), None);
+}
+
+#[test]
+fn pseudo_random_note() {
+    // Seeded rolls are never truly random:
+    let roll = match Roll::parse("[2d8]") {
+        Some(Ok(roll)) => roll,
+        _ => panic!("[2d8] should be rollable"),
+    };
+    let result = roll.roll_local(&mut StdRng::seed_from_u64(1));
+    let shown = styled(Style { pseudo_random: true, ..Style::default() }, || result.to_string());
+    assert!(shown.ends_with(" *[pseudo-random]*"));
+    let shown = styled(Style { pseudo_random: false, ..Style::default() }, || result.to_string());
+    assert!(!shown.contains("pseudo-random"));
+
+    // The same note, as any other response made with numbers tells it:
+    let noted = Style { pseudo_random: true, ..Style::default() };
+    assert_eq!(styled(noted, || randomorg::note(false)), Some(randomorg::PSEUDO_RANDOM_NOTE));
+    assert_eq!(styled(noted, || randomorg::note(true)), None);
+    assert_eq!(styled(Style { pseudo_random: false, ..noted }, || randomorg::note(false)), None);
+}
+
+#[tokio::test]
+async fn advantage() {
+    assert_eq!(roll("[1d20+5]", &[14]).await.lone_d20(), Some((14, 5)));
+    assert_eq!(roll("[3 + 1d20 - 1]", &[9]).await.lone_d20(), Some((9, 2)));
+    assert_eq!(roll("[! 1d20]", &[9]).await.lone_d20(), Some((9, 0)));
+    for (expr, dice) in [("[2d20]", &[9, 3][..]), ("[1d12+5]", &[9]), ("[10 - 1d20]", &[9]), ("[1d20 vs 15]", &[9]), ("[max(1d20, 10)]", &[9])] {
+        assert_eq!(roll(expr, dice).await.lone_d20(), None, "{} is no lone d20", expr);
+    }
+
+    let result = RollResult::advantage_with(7, 5, true, true, &mut Queue(VecDeque::from([12]))).await;
+    assert_eq!(result.to_string(), "~~7~~, 12 + 5 = 17");
+    let result = RollResult::advantage_with(7, 5, false, true, &mut Queue(VecDeque::from([20]))).await;
+    assert_eq!(format!("{:#}", result), "7, ~~20~~ + 5 = **12**");
+}
+
+#[tokio::test]
+async fn against() {
+    let verdict = |result: &RollResult, dc| result.against(dc).map(|(_, verdict)| verdict);
+    let result = roll("[1d20+5]", &[14]).await;
+    assert_eq!(result.against(15), Some((true, "— **Success** (beat DC 15 by 4)".to_string())));
+    assert_eq!(verdict(&result, 21).as_deref(), Some("— **Failure** (missed DC 21 by 2)"));
+    // Ties succeed:
+    assert_eq!(result.against(19), Some((true, "— **Success** (met DC 19 exactly)".to_string())));
+    // Negative totals:
+    let result = roll("[1d4-6]", &[1]).await;
+    assert_eq!(result.value(), Some(-5.0));
+    assert_eq!(verdict(&result, -5).as_deref(), Some("— **Success** (met DC -5 exactly)"));
+    assert_eq!(verdict(&result, 0).as_deref(), Some("— **Failure** (missed DC 0 by 5)"));
+    // Whole averages are compared as they are, and fractional ones rounded down, even below zero:
+    let result = roll("[avg 2d6]", &[]).await;
+    assert_eq!(verdict(&result, 6).as_deref(), Some("— **Success** (beat DC 6 by 1)"));
+    let result = roll("[avg 1d6]", &[]).await;
+    assert_eq!(result.value(), Some(3.5));
+    assert_eq!(verdict(&result, 3).as_deref(), Some("— **Success** (3.50 rounded down to 3, met DC 3 exactly)"));
+    assert_eq!(verdict(&roll("[avg 1d4-4]", &[]).await, -1).as_deref(), Some("— **Failure** (-1.50 rounded down to -2, missed DC -1 by 1)"));
+    // Symbols have no value to compare:
+    assert_eq!(roll("[2dH]", &[1, 2]).await.against(1), None);
+}
@@ -0,0 +1,139 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Rolls written in Roll20's notation, and what they are taken to be:
+mod common;
+
+use denede_discord_bot::dice::{scan_with, styled, Delimiters, DiceError, Roll, RollResult, Style, Syntax};
+
+use common::{roll_parsed, rollable};
+
+fn parse(expr: &str) -> Option<Result<Roll, DiceError>> {
+    Roll::parse_with(expr, Syntax::Roll20)
+}
+
+fn describe(expr: &str) -> String {
+    rollable(parse(expr), expr).describe()
+}
+
+// The part of the expression an unsupported roll is blamed on:
+fn unsupported(expr: &str) -> String {
+    match parse(expr) {
+        Some(Err(DiceError::Unsupported(part, _))) => part,
+        _ => panic!("{} should be unsupported", expr),
+    }
+}
+
+async fn roll(expr: &str, dice: &[i64]) -> RollResult {
+    roll_parsed(&rollable(parse(expr), expr), expr, dice).await
+}
+
+fn shown(result: &RollResult) -> String {
+    styled(Style::plain(), || result.to_string())
+}
+
+#[test]
+fn matrix() {
+    let matrix = [
+        ("[1d20]", "roll 1 die of 20 sides"),
+        ("[d20+5]", "roll 1 die of 20 sides, then add 5"),
+        ("[2d6 + 3 - 1]", "roll 2 dice of 6 sides and add them up, then add 2"),
+        ("[2d20kh1]", "roll 2 dice of 20 sides and drop the lowest 1 of them and add up the rest"),
+        ("[2d20k1]", "roll 2 dice of 20 sides and drop the lowest 1 of them and add up the rest"),
+        ("[2d20kl1]", "roll 2 dice of 20 sides and drop the highest 1 of them and add up the rest"),
+        ("[4d6kh3]", "roll 4 dice of 6 sides and drop the lowest 1 of them and add up the rest"),
+        ("[4d6d1]", "roll 4 dice of 6 sides and drop the lowest 1 of them and add up the rest"),
+        ("[4d6dl1]", "roll 4 dice of 6 sides and drop the lowest 1 of them and add up the rest"),
+        ("[4d6dh1]", "roll 4 dice of 6 sides and drop the highest 1 of them and add up the rest"),
+        ("[8d6!]", "roll 8 dice of 6 sides and add them up, rolling one more die for every die that shows 6 or more"),
+        ("[8d6!>5]", "roll 8 dice of 6 sides and add them up, rolling one more die for every die that shows 5 or more"),
+        ("[4d6!!]", "roll 4 dice of 6 sides and add them up, rolling again every die that shows 6 or more and adding it to that die"),
+        ("[1d20ro<2]", "roll 1 dice of 20 sides and reroll once every die that shows 2 or less, and add them up"),
+        ("[2d6ro<2+4]", "roll 2 dice of 6 sides and reroll once every die that shows 2 or less, and add them up, then add 4"),
+        ("[4d6kh3sd]", "roll 4 dice of 6 sides and drop the lowest 1 of them and add up the rest, showing them from highest to lowest"),
+        ("[3d6s]", "roll 3 dice of 6 sides and add them up, showing them from lowest to highest"),
+        ("[1d20 + 1d4 - 1]", "add up the following:\n+ roll 1 die of 20 sides\n+ roll 1 die of 4 sides\n− the number 1"),
+        ("[{1d20+5, 1d20+3}kh1]", "take the highest of the following:\n• roll 1 die of 20 sides, then add 5\n• roll 1 die of 20 sides, then add 3"),
+        ("[{1d20, 1d20}kl1 + 2]", "take the lowest of the following, then add 2:\n• roll 1 die of 20 sides\n• roll 1 die of 20 sides"),
+        ("[{2d6, 1d8}]", "add up the following:\n+ roll 2 dice of 6 sides and add them up\n+ roll 1 die of 8 sides\n+ the number 0"),
+    ];
+    for (expr, description) in matrix {
+        assert_eq!(describe(expr), description, "{}", expr);
+    }
+}
+
+#[test]
+fn shared() {
+    // Whatever is written the same in both notations is taken the same:
+    for expr in ["[1d20+7 vs 15]", "[gm 2d6]", "[avg 2d6+3]", "[seed:7 3d6]", "[3d6 <= 12]", "[sw8]"] {
+        assert_eq!(describe(expr), Roll::parse(expr).expect("Not a roll").ok().expect("Bad roll").describe(), "{}", expr);
+    }
+    // Modifiers of Roll20 reach within them too:
+    assert_eq!(describe("[2d20kh1+5 vs 15]"), "roll 2 dice of 20 sides and drop the lowest 1 of them and add up the rest, then add 5\nThen compare the total against a difficulty class of 15: meeting or beating it is a success.");
+}
+
+#[test]
+fn unsupported_parts() {
+    assert_eq!(unsupported("[1d20cs>19]"), "cs>19");
+    assert_eq!(unsupported("[10d6>4]"), ">4");
+    assert_eq!(unsupported("[4d6r<2]"), "r<2");
+    assert_eq!(unsupported("[1d20ro1]"), "ro1");
+    assert_eq!(unsupported("[4d6mt]"), "mt");
+    // Only one modifier (besides sorting) at once:
+    assert_eq!(unsupported("[4d6!kh3]"), "kh3");
+    assert_eq!(unsupported("[8d6!>1]"), "!>1");
+    assert_eq!(unsupported("[{1d20, 1d20, 1d20}kh2]"), "kh2");
+    assert_eq!(unsupported("[{1d20, 1d20}d1]"), "d1");
+    // Ill-formed, rather than unsupported:
+    assert!(matches!(parse("[3d6kh4]"), Some(Err(DiceError::KeepTooMany(_)))));
+    assert!(matches!(parse("[1000000d6!]"), Some(Err(DiceError::TooManyDice(_)))));
+    assert!(matches!(parse("[1d20cs>19 + 1d6r1]"), Some(Err(DiceError::Several(errors))) if errors.len() == 2));
+    let why = parse("[1d20cs>19]").expect("Not a roll").err().expect("Rolled?");
+    assert!(why.quoting("[1d20cs>19]").contains("`cs>19`"));
+}
+
+#[test]
+fn not_rolls() {
+    for expr in ["[art]", "[see above]", "[5]", "[d]", "[x1d20]", "[]"] {
+        assert!(parse(expr).is_none(), "{} should be no roll", expr);
+    }
+    // MapTool's own modifiers are not Roll20's:
+    assert_eq!(unsupported("[3d20km1]"), "km1");
+    assert_eq!(unsupported("[10d6m]"), "m");
+    assert!(Roll::parse("[8d6!]").is_none());
+}
+
+#[tokio::test]
+async fn rolls() {
+    assert_eq!(shown(&roll("[2d20kh1+5]", &[4, 17]).await), "~~4~~, 17 + 5 = 22");
+    assert_eq!(shown(&roll("[2d20kl1]", &[4, 17]).await), "4, ~~17~~ = 4");
+    assert_eq!(shown(&roll("[8d6!]", &[6, 6, 1, 1, 1, 1, 1, 1, 2, 6, 3]).await), "(6, 2), (6, 6, 3), 1, 1, 1, 1, 1, 1 = 29");
+    assert_eq!(shown(&roll("[2d6!!+1]", &[6, 3, 6, 4]).await), "(6+6+4), 3 + 1 = 20");
+    assert_eq!(shown(&roll("[2d20ro<2]", &[1, 15, 1]).await), "~~1~~, 1, 15 = 16");
+    assert_eq!(roll("[{1d20+5, 1d20+3}kh1]", &[10, 14]).await.total(), Some(17));
+    assert_eq!(roll("[{2d6, 1d8}+1]", &[1, 2, 3]).await.total(), Some(7));
+}
+
+#[test]
+fn scanned() {
+    let brackets = Delimiters::default();
+    let (rolls, _) = scan_with("Attack [{1d20+5, 1d20+3}kh1] for [8d6!] and [2d20kh1]", &brackets, false, Syntax::Roll20, None);
+    assert_eq!(rolls.len(), 3);
+    assert!(rolls.iter().all(|(_, roll)| roll.is_ok()));
+    // The same message, read as MapTool's, only holds the roll spelt alike in Foundry VTT:
+    assert_eq!(scan_with("Attack [{1d20+5, 1d20+3}kh1] for [8d6!] and [2d20kh1]", &brackets, false, Syntax::MapTool, None).0.len(), 1);
+}
@@ -0,0 +1,46 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Sealing with a known secret:
+use std::env;
+
+use denede_discord_bot::seal;
+
+#[test]
+fn sealed() {
+    env::set_var("DENEDE_WEBHOOK_SECRET", "hunter2");
+    assert!(seal::enabled());
+
+    let url = "https://discord.com/api/webhooks/123/token";
+    let sealed = seal::seal(url).expect("Not sealed?");
+    assert!(!sealed.windows(url.len()).any(|window| window == url.as_bytes()));
+    assert_eq!(seal::open(&sealed).as_deref(), Some(url));
+    // Sealed anew, it looks different every time:
+    assert_ne!(seal::seal(url), Some(sealed.clone()));
+
+    // Tampered with, or sealed with another secret, it cannot be opened:
+    let mut tampered = sealed.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert_eq!(seal::open(&tampered), None);
+    assert_eq!(seal::open(b"short"), None);
+    env::set_var("DENEDE_WEBHOOK_SECRET", "hunter3");
+    assert_eq!(seal::open(&sealed), None);
+
+    env::remove_var("DENEDE_WEBHOOK_SECRET");
+    assert!(!seal::enabled());
+    assert_eq!(seal::seal(url), None);
+}
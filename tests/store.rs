@@ -0,0 +1,220 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::fs;
+
+use denede_discord_bot::store::{Notable, Preferences, RollStats, Store};
+
+#[test]
+fn defaults() {
+    let store = Store::in_memory().unwrap();
+    assert_eq!(store.delimiters(1).unwrap(), None);
+    assert!(!store.strict(1).unwrap());
+    assert!(!store.hidden_rolls(1).unwrap());
+    assert!(!store.embeds(1).unwrap());
+    assert!(!store.channel_disabled(10).unwrap());
+    assert_eq!(store.preferences(1).unwrap(), Preferences::default());
+    assert_eq!(store.table(1, "loot").unwrap(), None);
+    assert!(store.roll_stats(1).unwrap().is_empty());
+}
+
+#[test]
+fn settings() {
+    let store = Store::in_memory().unwrap();
+    store.set_delimiters(1, Some(("{{", "}}"))).unwrap();
+    store.set_strict(1, true).unwrap();
+    store.set_hidden_rolls(2, true).unwrap();
+    assert_eq!(store.delimiters(1).unwrap(), Some(("{{".to_string(), "}}".to_string())));
+    assert!(store.strict(1).unwrap());
+    assert!(!store.hidden_rolls(1).unwrap());
+    assert_eq!(store.delimiters(2).unwrap(), None);
+    assert!(!store.strict(2).unwrap());
+    assert!(store.hidden_rolls(2).unwrap());
+
+    // Changing one setting leaves the others be:
+    store.set_delimiters(1, None).unwrap();
+    assert_eq!(store.delimiters(1).unwrap(), None);
+    assert!(store.strict(1).unwrap());
+    store.set_strict(1, false).unwrap();
+    assert!(!store.strict(1).unwrap());
+
+    // Ids beyond what an SQLite integer holds still come back the same:
+    store.set_delimiters(u64::MAX, Some(("r!", ""))).unwrap();
+    assert_eq!(store.delimiters(u64::MAX).unwrap(), Some(("r!".to_string(), String::new())));
+}
+
+#[test]
+fn embeds() {
+    let store = Store::in_memory().unwrap();
+    store.set_strict(1, true).unwrap();
+    store.set_embeds(1, true).unwrap();
+    assert!(store.embeds(1).unwrap());
+    assert!(store.strict(1).unwrap());
+    assert!(!store.embeds(2).unwrap());
+    store.set_embeds(1, false).unwrap();
+    assert!(!store.embeds(1).unwrap());
+}
+
+#[test]
+fn disabled_channels() {
+    let store = Store::in_memory().unwrap();
+    store.set_channel_disabled(1, 11, true).unwrap();
+    store.set_channel_disabled(1, 10, true).unwrap();
+    store.set_channel_disabled(2, 20, true).unwrap();
+    assert!(store.channel_disabled(10).unwrap());
+    assert_eq!(store.disabled_channels(1).unwrap(), vec![10, 11]);
+    assert_eq!(store.disabled_channels(3).unwrap(), Vec::<u64>::new());
+
+    // Disabling twice is no different, and enabling leaves the other channels be:
+    store.set_channel_disabled(1, 10, true).unwrap();
+    store.set_channel_disabled(1, 10, false).unwrap();
+    assert!(!store.channel_disabled(10).unwrap());
+    assert_eq!(store.disabled_channels(1).unwrap(), vec![11]);
+    assert_eq!(store.disabled_channels(2).unwrap(), vec![20]);
+}
+
+#[test]
+fn preferences() {
+    let store = Store::in_memory().unwrap();
+    let compact = Preferences { compact: Some(true), pips: Some(false), ..Preferences::default() };
+    store.set_preferences(1, Some(&compact)).unwrap();
+    store.set_preferences(2, Some(&Preferences { crits: Some(false), ..Preferences::default() })).unwrap();
+    assert_eq!(store.preferences(1).unwrap(), compact);
+
+    // Replaced whole, or forgotten, on their own:
+    let crits = Preferences { crits: Some(true), ..Preferences::default() };
+    store.set_preferences(1, Some(&crits)).unwrap();
+    assert_eq!(store.preferences(1).unwrap(), crits);
+    store.set_preferences(1, None).unwrap();
+    assert_eq!(store.preferences(1).unwrap(), Preferences::default());
+    assert_eq!(store.preferences(2).unwrap().crits, Some(false));
+}
+
+#[test]
+fn tables() {
+    let store = Store::in_memory().unwrap();
+    let entries = |pairs: &[(i64, &str)]| pairs.iter().map(|(weight, entry)| (*weight, entry.to_string())).collect::<Vec<_>>();
+    store.set_table(1, "loot", Some(&entries(&[(3, "Goblins"), (1, "Orc"), (2, "Nothing")]))).unwrap();
+    store.set_table(1, "weather", Some(&entries(&[(1, "Rain")]))).unwrap();
+    store.set_table(2, "loot", Some(&entries(&[(1, "Gold")]))).unwrap();
+    assert_eq!(store.table(1, "loot").unwrap(), Some(entries(&[(3, "Goblins"), (1, "Orc"), (2, "Nothing")])));
+    assert_eq!(store.tables(1).unwrap(), vec!["loot", "weather"]);
+
+    // Replaced whole, or forgotten, without touching the tables of other servers:
+    store.set_table(1, "loot", Some(&entries(&[(1, "Dragon")]))).unwrap();
+    assert_eq!(store.table(1, "loot").unwrap(), Some(entries(&[(1, "Dragon")])));
+    store.set_table(1, "loot", None).unwrap();
+    assert_eq!(store.table(1, "loot").unwrap(), None);
+    assert_eq!(store.tables(1).unwrap(), vec!["weather"]);
+    assert_eq!(store.table(2, "loot").unwrap(), Some(entries(&[(1, "Gold")])));
+}
+
+#[test]
+fn roll_stats() {
+    let store = Store::in_memory().unwrap();
+    let notable = |luck: f64, place: &str| Notable { luck, expression: "1d20".to_string(), total: Some(10), place: place.to_string() };
+    let rolled = |natural: i64, place: &str| {
+        let mut stats = RollStats { rolls: 1, d20s: 1, d20_sum: natural, ..RollStats::default() };
+        stats.natural_20s = (natural == 20) as u64;
+        stats.natural_1s = (natural == 1) as u64;
+        stats.notice(&notable((natural - 1) as f64 / 19.0, place));
+        stats
+    };
+    store.add_roll_stats(1, Some(10), &rolled(20, "first")).unwrap();
+    store.add_roll_stats(1, Some(10), &rolled(1, "second")).unwrap();
+    store.add_roll_stats(1, Some(10), &rolled(12, "third")).unwrap();
+    store.add_roll_stats(1, None, &rolled(5, "elsewhere")).unwrap();
+    store.add_roll_stats(2, Some(10), &rolled(7, "another")).unwrap();
+
+    // Counted apart in every server, and outside of any:
+    let stats = store.roll_stats(1).unwrap();
+    assert_eq!(stats.len(), 2);
+    let (outside, in_guild) = (&stats[0], &stats[1]);
+    assert_eq!(outside.0, None);
+    assert_eq!((outside.1.rolls, outside.1.d20_sum), (1, 5));
+    assert_eq!(in_guild.0, Some(10));
+    assert_eq!(in_guild.1.rolls, 3);
+    assert_eq!(in_guild.1.d20s, 3);
+    assert_eq!((in_guild.1.natural_20s, in_guild.1.natural_1s), (1, 1));
+    assert_eq!(in_guild.1.d20_sum, 33);
+    assert_eq!(in_guild.1.luckiest.as_ref().unwrap().place, "first");
+    assert_eq!(in_guild.1.unluckiest.as_ref().unwrap().place, "second");
+    assert_eq!(store.roll_stats(2).unwrap()[0].1.rolls, 1);
+}
+
+#[test]
+fn webhooks() {
+    let store = Store::in_memory().unwrap();
+    assert_eq!(store.webhook(10).unwrap(), None);
+    store.set_webhook(1, 10, Some(b"sealed")).unwrap();
+    store.set_webhook(1, 11, Some(b"other")).unwrap();
+    assert_eq!(store.webhook(10).unwrap(), Some(b"sealed".to_vec()));
+
+    // Set anew, or taken away, without touching other channels:
+    store.set_webhook(1, 10, Some(b"resealed")).unwrap();
+    assert_eq!(store.webhook(10).unwrap(), Some(b"resealed".to_vec()));
+    store.set_webhook(1, 10, None).unwrap();
+    assert_eq!(store.webhook(10).unwrap(), None);
+    assert_eq!(store.webhook(11).unwrap(), Some(b"other".to_vec()));
+}
+
+#[test]
+fn macros() {
+    let store = Store::in_memory().unwrap();
+    assert!(store.macros(1).unwrap().is_empty());
+    let macros = |pairs: &[(&str, &str)]| pairs.iter().map(|(name, expression)| (name.to_string(), expression.to_string())).collect::<Vec<_>>();
+    store.set_macros(1, &macros(&[("atk", "1d20+5"), ("dmg", "1d8+3")])).unwrap();
+    store.set_macros(2, &macros(&[("atk", "1d20")])).unwrap();
+
+    // Those of the same name are replaced, and the rest kept, in order of their names:
+    store.set_macros(1, &macros(&[("atk", "1d20+6"), ("bless", "1d4")])).unwrap();
+    assert_eq!(store.macros(1).unwrap(), macros(&[("atk", "1d20+6"), ("bless", "1d4"), ("dmg", "1d8+3")]));
+    assert_eq!(store.macros(2).unwrap(), macros(&[("atk", "1d20")]));
+}
+
+#[test]
+fn characters() {
+    let store = Store::in_memory().unwrap();
+    assert_eq!(store.character(1, 10).unwrap(), None);
+    store.set_character(1, 10, Some("{\"name\": \"Mira\"}")).unwrap();
+    store.set_character(1, 11, Some("{\"name\": \"Tam\"}")).unwrap();
+
+    // One per user in every server, replaced or forgotten on its own:
+    store.set_character(1, 10, Some("{\"name\": \"Mira II\"}")).unwrap();
+    assert_eq!(store.character(1, 10).unwrap().as_deref(), Some("{\"name\": \"Mira II\"}"));
+    assert_eq!(store.character(2, 10).unwrap(), None);
+    store.set_character(1, 10, None).unwrap();
+    assert_eq!(store.character(1, 10).unwrap(), None);
+    assert_eq!(store.character(1, 11).unwrap().as_deref(), Some("{\"name\": \"Tam\"}"));
+}
+
+#[test]
+fn migrations() {
+    let store = Store::in_memory().unwrap();
+    let version = store.version().unwrap();
+    assert!(version > 0);
+
+    // Reopening a database keeps what it had, without migrating it again:
+    let path = std::env::temp_dir().join(format!("denede-store-{}.sqlite3", std::process::id()));
+    let _ = fs::remove_file(&path);
+    Store::open(&path).unwrap().set_strict(7, true).unwrap();
+    let reopened = Store::open(&path).unwrap();
+    assert_eq!(reopened.version().unwrap(), version);
+    assert!(reopened.strict(7).unwrap());
+    drop(reopened);
+    fs::remove_file(&path).unwrap();
+}
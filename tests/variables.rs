@@ -0,0 +1,71 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Variables of rolls, bound to the values of a character:
+use denede_discord_bot::dice::{parse_bound, scan_with, Delimiters, DiceError, Syntax, Variables};
+
+fn character() -> Variables {
+    [("dex", 3), ("prof", 2), ("str", -1)].into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+}
+
+// Expression as rolled, and its description:
+fn bound(expr: &str) -> (String, String) {
+    match parse_bound(expr, Syntax::MapTool, Some(&character())) {
+        (bound, Some(Ok(roll))) => (bound, roll.describe()),
+        _ => panic!("{} should be rollable", expr),
+    }
+}
+
+fn error(expr: &str, variables: Option<&Variables>) -> DiceError {
+    match parse_bound(expr, Syntax::MapTool, variables) {
+        (_, Some(Err(why))) => why,
+        _ => panic!("{} should not be rollable", expr),
+    }
+}
+
+#[test]
+fn substituted() {
+    assert_eq!(bound("[1d20 + $dex + $prof]"), ("[1d20 + 3 + 2]".to_string(), "add up the following:\n+ roll 1 die of 20 sides\n+ the number 3\n+ the number 2".to_string()));
+    assert_eq!(bound("[1d20+$DEX]").0, "[1d20+3]");
+    // Negative values turn the sign before them:
+    assert_eq!(bound("[1d20 + $str]").0, "[1d20 - 1]");
+    assert_eq!(bound("[1d20 - $str]").0, "[1d20 + 1]");
+    assert_eq!(bound("[+$dex]").0, "[+3]");
+    assert_eq!(bound("[max(1d20 + $dex, 10)]").0, "[max(1d20 + 3, 10)]");
+    // Without variables, expressions are left as written:
+    assert_eq!(bound("[2d6+3]").0, "[2d6+3]");
+}
+
+#[test]
+fn unbound() {
+    assert!(matches!(error("[1d20 + $dexx]", Some(&character())), DiceError::UnknownVariable(name, _) if name == "$dexx"));
+    assert!(matches!(error("[1d20 + $dex]", None), DiceError::NoCharacter(name, _) if name == "$dex"));
+    // Only rolls are told off, not any other text with a dollar sign:
+    assert!(parse_bound("[$dex check, anyone?]", Syntax::MapTool, None).1.is_none());
+    assert!(parse_bound("[costs $5]", Syntax::MapTool, Some(&character())).1.is_none());
+}
+
+#[test]
+fn scanned() {
+    let brackets = Delimiters::default();
+    let (rolls, _) = scan_with("Sneaking [1d20 + $dex] with [1d20 + $luck]", &brackets, false, Syntax::MapTool, Some(&character()));
+    assert_eq!(rolls.len(), 2);
+    assert_eq!(rolls[0].0, "[1d20 + 3]");
+    assert!(rolls[0].1.is_ok());
+    assert_eq!(rolls[1].0, "[1d20 + $luck]");
+    assert!(matches!(&rolls[1].1, Err(DiceError::UnknownVariable(_, _))));
+}
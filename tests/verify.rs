@@ -0,0 +1,56 @@
+/*
+ *  Denedé: Discord bot for generating D&D dice rolls, written in Rust.
+ *  Copyright (C) 2023-2024  Bolu <bolu@tuta.io>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU Affero General Public License as published
+ *  by the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ *  GNU Affero General Public License for more details.
+ *
+ *  You should have received a copy of the GNU Affero General Public License
+ *  along with this program. If not, see <https://www.gnu.org/licenses/>.
+ */
+// Verification codes of rolls, made and checked with a known secret:
+mod common;
+
+use std::env;
+
+use denede_discord_bot::dice::{styled, Roll, Style};
+use denede_discord_bot::verify;
+
+use common::roll;
+
+#[tokio::test]
+async fn codes() {
+    env::set_var("DENEDE_VERIFY_SECRET", "hunter2");
+
+    let bound = match Roll::parse("[max 2d6+3]") {
+        Some(Ok(roll)) => roll,
+        _ => panic!("Should be rollable"),
+    };
+    // Theoretical bounds have no total, so they cannot be signed:
+    assert!(verify::sign("[max 2d6+3]", &bound.roll().await).is_none());
+
+    let result = roll("[2d6 + 3]", &[4, 5]).await;
+    let code = verify::sign("[2d6 + 3]", &result).expect("No code?");
+
+    // However the results are styled, or whatever is pasted along with them:
+    let shown = verify::with_code("[2d6 + 3]", &result);
+    assert!(shown.contains(" · verify: `"));
+    for results in ["4, 5 + 3 = 12", "⚃, ⚄ + 3 = **12**", shown.as_str(), &styled(Style::plain(), || result.to_string())] {
+        assert_eq!(verify::check(&code, "2d6+3", results), Some(true), "{}", results);
+    }
+    assert_eq!(verify::check(&code, "[2D6 + 3]", "4, 5 + 3 = 12"), Some(true));
+
+    // Other dice, even adding up to the same total, were never rolled:
+    assert_eq!(verify::check(&code, "2d6+3", "3, 6 + 3 = 12"), Some(false));
+    assert_eq!(verify::check(&code, "2d6+3", "5, 4 + 3 = 12"), Some(false));
+    assert_eq!(verify::check(&code, "2d6+3", "4, 5 + 3 = 13"), Some(false));
+    assert_eq!(verify::check(&code, "2d6+4", "4, 5 + 3 = 12"), Some(false));
+    assert_eq!(verify::check("nonsense", "2d6+3", "4, 5 + 3 = 12"), None);
+}